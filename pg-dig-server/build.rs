@@ -1,6 +1,13 @@
+#[cfg(feature = "libpq")]
 use std::env;
+#[cfg(feature = "libpq")]
 use std::path::PathBuf;
 
+/// Without the libpq feature there is nothing to link or generate.
+#[cfg(not(feature = "libpq"))]
+fn main() {}
+
+#[cfg(feature = "libpq")]
 fn main() {
     if cfg!(unix) {
         println!("cargo:rustc-link-search=/usr/include/postgresql");
@@ -0,0 +1,312 @@
+use crate::postgres::capture::ReplaySpeed;
+use crate::postgres::common::lsn::Lsn;
+use crate::postgres::error::PgDigError;
+use crate::postgres::feedback::DEFAULT_STATUS_INTERVAL;
+use crate::postgres::supervisor::DEFAULT_MAX_RECONNECT_DELAY;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::{env, fs};
+
+pub const DEFAULT_CONNECTION_STRING: &str = "host=localhost user=postgres dbname=postgres password=postgres replication=database";
+pub const DEFAULT_SLOT_NAME: &str = "physical";
+pub const DEFAULT_START_LSN: u64 = 0x100_0000;
+
+/// Where replication should begin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartPosition {
+    /// A fixed LSN, e.g. "0/1000000"
+    Lsn(u64),
+    /// Resume from the slot's restart_lsn ("slot")
+    SlotRestartLsn,
+    /// The server's current WAL position ("current")
+    CurrentInsert,
+}
+
+impl FromStr for StartPosition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "slot" | "restart_lsn" => Ok(StartPosition::SlotRestartLsn),
+            "current" => Ok(StartPosition::CurrentInsert),
+            lsn => Ok(StartPosition::Lsn(Lsn::from_str(lsn)?.to_u64())),
+        }
+    }
+}
+
+impl fmt::Display for StartPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartPosition::Lsn(lsn) => write!(f, "{}", Lsn::from_u64(*lsn)),
+            StartPosition::SlotRestartLsn => write!(f, "slot"),
+            StartPosition::CurrentInsert => write!(f, "current"),
+        }
+    }
+}
+
+/// Config holds everything needed to stream WAL from a cluster.
+///
+/// Settings are layered, each overriding the last: defaults, then a TOML file (`--config` or
+/// `PGDIG_CONFIG`), then `PGDIG_*` environment variables, then command line flags.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub connection_string: String,
+    pub slot_name: String,
+    pub start_position: StartPosition,
+    /// Timeline to stream, or the server's current one when unset
+    pub timeline: Option<u32>,
+    pub status_interval: Duration,
+    /// Create the slot when it doesn't exist
+    pub create_slot: bool,
+    /// Use a temporary slot, which the server drops when we disconnect
+    pub temporary_slot: bool,
+    /// Read WAL from segment files here instead of a server
+    pub wal_path: Option<PathBuf>,
+    /// WAL segment size in bytes, or taken from the first segment when unset
+    pub segment_size: Option<u32>,
+    /// Record every message received from the server to this file
+    pub capture_path: Option<PathBuf>,
+    /// Replay a capture file instead of connecting
+    pub replay_path: Option<PathBuf>,
+    pub replay_speed: ReplaySpeed,
+    /// Reconnect when a live connection is lost, resuming after the last confirmed record
+    pub reconnect: bool,
+    /// The longest wait between reconnect attempts
+    pub max_reconnect_delay: Duration,
+}
+
+/// The TOML file layout. Every key is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    connection_string: Option<String>,
+    slot_name: Option<String>,
+    start_lsn: Option<String>,
+    timeline: Option<u32>,
+    status_interval_secs: Option<u64>,
+    create_slot: Option<bool>,
+    temporary_slot: Option<bool>,
+    wal_path: Option<PathBuf>,
+    segment_size_mb: Option<u32>,
+    capture: Option<PathBuf>,
+    replay: Option<PathBuf>,
+    replay_speed: Option<String>,
+    reconnect: Option<bool>,
+    max_reconnect_delay_secs: Option<u64>,
+}
+
+/// Settings that can be given as an environment variable and a command line flag.
+const SETTINGS: [(&str, &str); 14] = [
+    ("PGDIG_CONNECTION_STRING", "--connection-string"),
+    ("PGDIG_SLOT", "--slot"),
+    ("PGDIG_START_LSN", "--start-lsn"),
+    ("PGDIG_TIMELINE", "--timeline"),
+    ("PGDIG_STATUS_INTERVAL", "--status-interval"),
+    ("PGDIG_CREATE_SLOT", "--create-slot"),
+    ("PGDIG_TEMPORARY_SLOT", "--temporary-slot"),
+    ("PGDIG_WAL_PATH", "--wal-path"),
+    ("PGDIG_SEGMENT_SIZE_MB", "--segment-size-mb"),
+    ("PGDIG_CAPTURE", "--capture"),
+    ("PGDIG_REPLAY", "--replay"),
+    ("PGDIG_REPLAY_SPEED", "--replay-speed"),
+    ("PGDIG_RECONNECT", "--reconnect"),
+    ("PGDIG_MAX_RECONNECT_DELAY", "--max-reconnect-delay"),
+];
+
+/// Flags that take no value.
+const SWITCHES: [&str; 5] = ["--create-slot", "--no-create-slot", "--temporary-slot", "--reconnect", "--no-reconnect"];
+
+const CONFIG_FILE_VAR: &str = "PGDIG_CONFIG";
+const CONFIG_FILE_FLAG: &str = "--config";
+
+pub const USAGE: &str = "usage: pg_dig_server [--config FILE] [--connection-string CONNINFO] [--slot NAME] \
+[--start-lsn LSN|slot|current] [--timeline TLI] [--status-interval SECS] [--create-slot|--no-create-slot] [--temporary-slot] [--wal-path DIR|FILE] [--segment-size-mb MB] \
+[--capture FILE] [--replay FILE] [--replay-speed FACTOR|max] [--reconnect|--no-reconnect] [--max-reconnect-delay SECS]";
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            connection_string: DEFAULT_CONNECTION_STRING.to_string(),
+            slot_name: DEFAULT_SLOT_NAME.to_string(),
+            start_position: StartPosition::Lsn(DEFAULT_START_LSN),
+            timeline: None,
+            status_interval: DEFAULT_STATUS_INTERVAL,
+            create_slot: true,
+            temporary_slot: false,
+            wal_path: None,
+            segment_size: None,
+            capture_path: None,
+            replay_path: None,
+            replay_speed: ReplaySpeed::default(),
+            reconnect: true,
+            max_reconnect_delay: DEFAULT_MAX_RECONNECT_DELAY,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config from the process arguments, environment and config file.
+    pub fn load() -> Result<Config, PgDigError> {
+        Self::from_sources(env::args().skip(1), |name| env::var(name).ok())
+    }
+
+    /// Loads the config from the given arguments (without the program name) and environment.
+    pub fn from_sources<I, E>(args: I, env: E) -> Result<Config, PgDigError>
+    where
+        I: IntoIterator<Item = String>,
+        E: Fn(&str) -> Option<String>,
+    {
+        let flags = parse_flags(args)?;
+        let mut config = Config::default();
+
+        let config_file = flags.iter()
+            .rev()
+            .find(|(flag, _)| flag == CONFIG_FILE_FLAG)
+            .map(|(_, path)| path.clone())
+            .or_else(|| env(CONFIG_FILE_VAR));
+
+        if let Some(path) = config_file {
+            let contents = fs::read_to_string(&path)
+                .map_err(|e| PgDigError::Config(format!("failed to read {}: {}", path, e)))?;
+            config.apply_toml(&contents)?;
+        }
+
+        for (var, flag) in SETTINGS {
+            if let Some(value) = env(var) {
+                config.set(flag, &value)?;
+            }
+        }
+
+        for (flag, value) in flags.iter().filter(|(flag, _)| flag != CONFIG_FILE_FLAG) {
+            config.set(flag, value)?;
+        }
+
+        Ok(config)
+    }
+
+    /// Overrides settings with the ones present in a TOML document.
+    pub fn apply_toml(&mut self, contents: &str) -> Result<(), PgDigError> {
+        let file: ConfigFile = toml::from_str(contents)
+            .map_err(|e| PgDigError::Config(format!("invalid config file: {}", e)))?;
+
+        if let Some(connection_string) = file.connection_string {
+            self.connection_string = connection_string;
+        }
+        if let Some(slot_name) = file.slot_name {
+            self.slot_name = slot_name;
+        }
+        if let Some(start_lsn) = file.start_lsn {
+            self.set("--start-lsn", &start_lsn)?;
+        }
+        if let Some(timeline) = file.timeline {
+            self.timeline = Some(timeline);
+        }
+        if let Some(secs) = file.status_interval_secs {
+            self.set("--status-interval", &secs.to_string())?;
+        }
+        if let Some(create_slot) = file.create_slot {
+            self.create_slot = create_slot;
+        }
+        if let Some(temporary_slot) = file.temporary_slot {
+            self.temporary_slot = temporary_slot;
+        }
+        if let Some(wal_path) = file.wal_path {
+            self.wal_path = Some(wal_path);
+        }
+        if let Some(segment_size_mb) = file.segment_size_mb {
+            self.set("--segment-size-mb", &segment_size_mb.to_string())?;
+        }
+        if let Some(capture) = file.capture {
+            self.capture_path = Some(capture);
+        }
+        if let Some(replay) = file.replay {
+            self.replay_path = Some(replay);
+        }
+        if let Some(replay_speed) = file.replay_speed {
+            self.set("--replay-speed", &replay_speed)?;
+        }
+        if let Some(reconnect) = file.reconnect {
+            self.reconnect = reconnect;
+        }
+        if let Some(secs) = file.max_reconnect_delay_secs {
+            self.max_reconnect_delay = Duration::from_secs(secs);
+        }
+
+        Ok(())
+    }
+
+    fn set(&mut self, flag: &str, value: &str) -> Result<(), PgDigError> {
+        let invalid = |e: String| PgDigError::Config(format!("invalid value for {}: {}", flag, e));
+
+        match flag {
+            "--connection-string" => self.connection_string = value.to_string(),
+            "--slot" => self.slot_name = value.to_string(),
+            "--start-lsn" => self.start_position = value.parse().map_err(invalid)?,
+            "--timeline" => self.timeline = Some(value.parse().map_err(|e| invalid(format!("{}", e)))?),
+            "--status-interval" => {
+                let secs: u64 = value.parse().map_err(|e| invalid(format!("{}", e)))?;
+                if secs == 0 {
+                    return Err(invalid("must be at least 1".to_string()));
+                }
+                self.status_interval = Duration::from_secs(secs);
+            }
+            "--create-slot" => self.create_slot = value.parse().map_err(|e| invalid(format!("{}", e)))?,
+            "--no-create-slot" => self.create_slot = !value.parse::<bool>().map_err(|e| invalid(format!("{}", e)))?,
+            "--temporary-slot" => self.temporary_slot = value.parse().map_err(|e| invalid(format!("{}", e)))?,
+            "--wal-path" => self.wal_path = Some(PathBuf::from(value)),
+            "--segment-size-mb" => {
+                let megabytes: u32 = value.parse().map_err(|e| invalid(format!("{}", e)))?;
+                if !megabytes.is_power_of_two() || megabytes > 1024 {
+                    return Err(invalid("must be a power of two between 1 and 1024".to_string()));
+                }
+                self.segment_size = Some(megabytes * 1024 * 1024);
+            }
+            "--capture" => self.capture_path = Some(PathBuf::from(value)),
+            "--replay" => self.replay_path = Some(PathBuf::from(value)),
+            "--replay-speed" => self.replay_speed = value.parse().map_err(invalid)?,
+            "--reconnect" => self.reconnect = value.parse().map_err(|e| invalid(format!("{}", e)))?,
+            "--no-reconnect" => self.reconnect = !value.parse::<bool>().map_err(|e| invalid(format!("{}", e)))?,
+            "--max-reconnect-delay" => {
+                let secs: u64 = value.parse().map_err(|e| invalid(format!("{}", e)))?;
+                self.max_reconnect_delay = Duration::from_secs(secs);
+            }
+            unknown => return Err(PgDigError::Config(format!("unknown option {}\n{}", unknown, USAGE))),
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits `--flag value` and `--flag=value` arguments into pairs. Switches are paired with "true".
+fn parse_flags<I: IntoIterator<Item = String>>(args: I) -> Result<Vec<(String, String)>, PgDigError> {
+    let mut flags = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--help" {
+            return Err(PgDigError::Config(USAGE.to_string()));
+        }
+        if !arg.starts_with("--") {
+            return Err(PgDigError::Config(format!("unexpected argument {}\n{}", arg, USAGE)));
+        }
+
+        if SWITCHES.contains(&arg.as_str()) {
+            flags.push((arg, "true".to_string()));
+            continue;
+        }
+
+        match arg.split_once('=') {
+            Some((flag, value)) => flags.push((flag.to_string(), value.to_string())),
+            None => {
+                let value = args.next()
+                    .ok_or_else(|| PgDigError::Config(format!("missing value for {}\n{}", arg, USAGE)))?;
+                flags.push((arg, value));
+            }
+        }
+    }
+
+    Ok(flags)
+}
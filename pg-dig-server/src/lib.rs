@@ -1,2 +1,3 @@
+pub mod config;
 pub mod postgres;
 pub mod util;
\ No newline at end of file
@@ -5,77 +5,156 @@ use bevy::asset::RenderAssetUsages;
 use bevy::color::palettes::css;
 use bevy::prelude::*;
 use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
-use pg_dig_server::postgres::bindings::PQfinish;
-use pg_dig_server::postgres::connection::connect;
-use pg_dig_server::postgres::replication::{read_message, start_replication};
-use std::sync::mpsc::*;
-use std::sync::Mutex;
-use std::thread;
+use pg_dig_server::config::Config;
+use pg_dig_server::postgres::activity::ActivityTracker;
+use pg_dig_server::postgres::common::lsn::Lsn;
+use pg_dig_server::postgres::common::RelFileLocator;
+use pg_dig_server::postgres::consumption::ConsumptionTracker;
+use pg_dig_server::postgres::lifecycle::LifecycleEvent;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use pg_dig_server::postgres::message_stream::{MessageStream, DEFAULT_STREAM_CAPACITY};
+use pg_dig_server::postgres::records::brin::BrinRecord;
+use pg_dig_server::postgres::records::btree::BtreeRecord;
+use pg_dig_server::postgres::records::gin::GinRecord;
+use pg_dig_server::postgres::records::gist::GistRecord;
+use pg_dig_server::postgres::records::hash::HashRecord;
+use pg_dig_server::postgres::records::heap::HeapRecord;
+use pg_dig_server::postgres::records::heap2::Heap2Record;
+use pg_dig_server::postgres::records::logical_message::LogicalMessageRecord;
+use pg_dig_server::postgres::records::spgist::SpgistRecord;
+use pg_dig_server::postgres::records::standby::StandbyRecord;
+use pg_dig_server::postgres::records::xlog::XLogRecord;
+use pg_dig_server::postgres::records::RmgrRecord;
 use pg_dig_server::postgres::xlog_message::XLogMessage;
+use pg_dig_server::postgres::source::open_source;
+use pg_dig_server::postgres::supervisor::ConnectionState;
 
 const IMAGE_WIDTH: u32 = 512;
 const IMAGE_HEIGHT: u32 = 512;
 
 #[derive(Resource)]
 struct ReceiveChannel {
-    receiver: Mutex<Receiver<XLogMessage>>,
+    receiver: Mutex<MessageStream>,
 }
 
+/// The latest connection state, as text for the status line.
+#[derive(Resource, Clone, Default)]
+struct ConnectionStatus(Arc<Mutex<String>>);
+
+/// Marks the text that shows the connection status.
+#[derive(Component)]
+struct ConnectionStatusText;
+
 /// Store the image handle that we will draw to, here.
 #[derive(Resource)]
 struct MyProcGenImage(Handle<Image>);
 
-const LOCAL_CONNECTION_STRING: &str = "host=localhost user=postgres dbname=postgres password=postgres replication=database";
 fn main() {
-    let (tx, rx): (Sender<XLogMessage>, Receiver<XLogMessage>) = channel();
-
-    let consumer_handle = thread::spawn(move || {
-        unsafe {
-            let conn = connect(LOCAL_CONNECTION_STRING);
-            start_replication(conn).unwrap();
-
-            loop {
-                match read_message(conn) {
-                    Ok(message) => {
-                        println!("debug: {}", message);
-                        tx.send(message).unwrap();
-                        //break;
-                    },
-                    Err(e) => {
-                        println!("failed to read message: {}", e);
-                        break;
-                    }
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    let status = ConnectionStatus::default();
+    let listener_status = status.clone();
+    let listener = Box::new(move |state: &ConnectionState| {
+        println!("connection: {}", state);
+        *listener_status.0.lock().unwrap() = state.to_string();
+    });
+
+    let stream = MessageStream::spawn(move || open_source(&config, listener), DEFAULT_STREAM_CAPACITY);
+
+    //start_renderer(stream, status);
+    start_dummy_consumer(stream);
+}
+
+fn start_dummy_consumer(stream: MessageStream) {
+    let mut activity = ActivityTracker::new();
+    let mut consumption = ConsumptionTracker::new();
+
+    for message in stream {
+        match message {
+            Ok(message) => {
+                println!("debug: {}", message);
+                LifecycleEvent::from_message(&message).iter().for_each(|event| println!("lifecycle: {}", event));
+                if let RmgrRecord::LogicalMessage(LogicalMessageRecord::Message(marker)) = &message.record {
+                    println!("marker: {}", marker);
+                }
+                activity.observe(&message);
+                consumption.observe(&message);
+                if let RmgrRecord::Standby(StandbyRecord::RunningXacts(_)) = message.record {
+                    print_activity(&activity);
+                    print_consumption(&consumption);
                 }
+            },
+            Err(e) => {
+                println!("failed to read message: {}", e);
+                break;
             }
-
-            PQfinish(conn);
         }
-    });
+    }
+}
 
-    //start_renderer(rx);
-    start_dummy_consumer(rx);
+/// Summarizes in-progress transactions and locks, as of the last RUNNING_XACTS snapshot.
+fn print_activity(activity: &ActivityTracker) {
+    let in_progress = activity.in_progress();
+    match in_progress.first() {
+        Some((xid, oldest)) => println!(
+            "activity: {} transactions in progress, oldest {} since {}, {} AccessExclusive locks",
+            in_progress.len(), xid.0, Lsn::from_u64(oldest.first_lsn), activity.lock_count()
+        ),
+        None => println!("activity: no transactions in progress, {} AccessExclusive locks", activity.lock_count()),
+    }
 }
 
-fn start_dummy_consumer(rx: Receiver<XLogMessage>) {
-    loop {
-        let _ = rx.recv();
+/// Summarizes xid and multixact consumption and the busiest sequence.
+fn print_consumption(consumption: &ConsumptionTracker) {
+    let show = |value: Option<u32>| value.map_or("unknown".to_string(), |value| value.to_string());
+    let rate = consumption.xids_per_second().map_or("unknown".to_string(), |rate| format!("{:.1}", rate));
+
+    println!(
+        "consumption: xid age {}, {} xids/s, multixact age {}, {} multixact members",
+        show(consumption.xid_age()), rate, show(consumption.multixact_age()), consumption.multixact_members()
+    );
+    if let Some((locator, sequence)) = consumption.busiest_sequences().first() {
+        println!(
+            "consumption: busiest sequence rel {} in db {}, logged {} times, at {}",
+            locator.rel_number, locator.db_oid, sequence.records, sequence.last_value
+        );
     }
 }
 
-fn start_renderer(rx: Receiver<XLogMessage>) {
+fn start_renderer(rx: MessageStream, status: ConnectionStatus) {
     App::new()
         .insert_resource(ReceiveChannel { receiver: Mutex::new(rx) })
+        .insert_resource(status)
         .add_plugins(DefaultPlugins)
         .add_systems(Startup, setup)
         .add_systems(FixedUpdate, draw)
+        .add_systems(Update, show_connection_status)
         .run();
 }
 
+fn show_connection_status(status: Res<ConnectionStatus>, mut texts: Query<&mut Text, With<ConnectionStatusText>>) {
+    let status = status.0.lock().unwrap();
+
+    for mut text in &mut texts {
+        if text.0 != *status {
+            text.0 = status.clone();
+        }
+    }
+}
+
 fn draw(
     handle: Res<MyProcGenImage>,
     mut images: ResMut<Assets<Image>>,
     receiver_channel: Res<ReceiveChannel>,
-    mut draw_color: Local<Color>
+    mut draw_color: Local<Color>,
+    mut painted: Local<HashMap<RelFileLocator, HashSet<u32>>>,
 ) {
     let receiver = match receiver_channel.receiver
         .try_lock() {
@@ -87,25 +166,115 @@ fn draw(
     let image = images.get_mut(&handle.0).expect("Image not found");
 
     match receiver.try_recv() {
-        Ok(message) => {
+        Some(Ok(message)) => {
             println!("message: {}", message);
+            if let RmgrRecord::XLog(record) = &message.record {
+                if let Some(checkpoint) = record.checkpoint() {
+                    println!("checkpoint: {}, redo at {}", message.record_type(), Lsn::from_u64(checkpoint.redo));
+                }
+            }
+            if let RmgrRecord::LogicalMessage(LogicalMessageRecord::Message(marker)) = &message.record {
+                println!("marker: {}", marker);
+            }
+            for event in LifecycleEvent::from_message(&message) {
+                println!("lifecycle: {}", event);
+                if let Some(locator) = event.resets_relation() {
+                    let from_block = match event {
+                        LifecycleEvent::RelationTruncated { block, .. } => block,
+                        _ => 0,
+                    };
+                    clear_relation(image, &mut painted, locator, from_block);
+                }
+            }
             let block_numbers = message.get_block_numbers();
             match block_numbers.first() {
                 Some(block_number) => {
                     if *block_number <= IMAGE_WIDTH * IMAGE_HEIGHT {
-                        *draw_color = Color::linear_rgb(255f32, 0f32, 0f32);
+                        *draw_color = operation_color(&message);
                         let (x, y) = (*block_number % IMAGE_WIDTH, *block_number / IMAGE_WIDTH);
                         println!("writing at ({}, {})", x, y);
                         image
                             .set_color_at(x, y, *draw_color)
                             .unwrap();
+                        if let Some(locator) = message.wal_block_headers.first().and_then(|header| header.rel_file_locator) {
+                            painted.entry(locator).or_default().insert(*block_number);
+                        }
                     }
                 },
                 None => {}
             }
 
         }
-        Err(_) => {}
+        Some(Err(e)) => println!("failed to read message: {}", e),
+        None => {}
+    }
+}
+
+/// Blanks the blocks drawn for a relation from `from_block` on, once its file was created
+/// afresh, truncated or dropped.
+fn clear_relation(
+    image: &mut Image,
+    painted: &mut HashMap<RelFileLocator, HashSet<u32>>,
+    locator: &RelFileLocator,
+    from_block: u32,
+) {
+    if let Some(blocks) = painted.get_mut(locator) {
+        blocks.retain(|block_number| {
+            if *block_number < from_block {
+                return true;
+            }
+            let (x, y) = (*block_number % IMAGE_WIDTH, *block_number / IMAGE_WIDTH);
+            image.set_color_at(x, y, Color::from(css::BLACK)).unwrap();
+            false
+        });
+    }
+}
+
+/// Colors a block by what the record did to it.
+fn operation_color(message: &XLogMessage) -> Color {
+    match &message.record {
+        RmgrRecord::Heap(HeapRecord::Insert(_)) => Color::linear_rgb(0f32, 1f32, 0f32),
+        RmgrRecord::Heap(HeapRecord::Update(_)) => Color::linear_rgb(1f32, 1f32, 0f32),
+        RmgrRecord::Heap(HeapRecord::HotUpdate(_)) => Color::linear_rgb(1f32, 0.5f32, 0f32),
+        RmgrRecord::Heap(HeapRecord::Delete(_) | HeapRecord::Truncate(_)) => Color::linear_rgb(1f32, 0f32, 0f32),
+        RmgrRecord::Heap(_) => Color::linear_rgb(0f32, 0.5f32, 1f32),
+        RmgrRecord::Heap2(Heap2Record::MultiInsert(_)) => Color::linear_rgb(0f32, 1f32, 0f32),
+        /* vacuum and freeze activity, apart from user writes */
+        RmgrRecord::Heap2(
+            Heap2Record::Prune(_) | Heap2Record::Vacuum { .. } | Heap2Record::FreezePage(_) | Heap2Record::Visible(_),
+        ) => Color::linear_rgb(0.6f32, 0f32, 1f32),
+        RmgrRecord::Heap2(_) => Color::linear_rgb(0f32, 0.5f32, 1f32),
+        /* index pages: splits stand out, cleanup is dimmed */
+        RmgrRecord::Btree(BtreeRecord::SplitLeft(_) | BtreeRecord::SplitRight(_) | BtreeRecord::NewRoot { .. })
+        | RmgrRecord::Gin(GinRecord::Split(_))
+        | RmgrRecord::Gist(GistRecord::PageSplit(_))
+        | RmgrRecord::SPGist(SpgistRecord::PickSplit(_))
+        | RmgrRecord::Hash(HashRecord::SplitAllocatePage(_) | HashRecord::SplitPage) => Color::linear_rgb(1f32, 0f32, 1f32),
+        RmgrRecord::Btree(
+            BtreeRecord::Delete(_) | BtreeRecord::Vacuum { .. } | BtreeRecord::MarkPageHalfdead(_) | BtreeRecord::UnlinkPage(_)
+            | BtreeRecord::UnlinkPageMeta(_),
+        )
+        | RmgrRecord::Gin(
+            GinRecord::VacuumPage | GinRecord::VacuumDataLeafPage | GinRecord::DeletePage { .. } | GinRecord::DeleteListPages { .. },
+        )
+        | RmgrRecord::Gist(GistRecord::Delete(_) | GistRecord::PageDelete { .. })
+        | RmgrRecord::SPGist(
+            SpgistRecord::VacuumLeaf { .. } | SpgistRecord::VacuumRoot { .. } | SpgistRecord::VacuumRedirect { .. },
+        )
+        | RmgrRecord::Hash(
+            HashRecord::SqueezePage(_) | HashRecord::Delete { .. } | HashRecord::SplitCleanup | HashRecord::VacuumOnePage(_),
+        )
+        | RmgrRecord::BRIN(BrinRecord::Desummarize { .. }) => Color::linear_rgb(0.4f32, 0f32, 0.4f32),
+        RmgrRecord::Btree(_)
+        | RmgrRecord::Hash(_)
+        | RmgrRecord::Gin(_)
+        | RmgrRecord::Gist(_)
+        | RmgrRecord::SPGist(_)
+        | RmgrRecord::BRIN(_)
+        | RmgrRecord::Generic(_) => Color::linear_rgb(0f32, 1f32, 1f32),
+        /* full-page images, which pile up right after a checkpoint */
+        RmgrRecord::XLog(XLogRecord::Fpi | XLogRecord::FpiForHint) => Color::linear_rgb(1f32, 0.3f32, 0.3f32),
+        _ => Color::linear_rgb(1f32, 1f32, 1f32),
     }
 }
 
@@ -137,6 +306,18 @@ fn setup(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
     // create a sprite entity using our image
     commands.spawn(Sprite::from_image(handle.clone()));
     commands.insert_resource(MyProcGenImage(handle));
+
+    // the connection status, top left
+    commands.spawn((
+        Text::new("connecting"),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        },
+        ConnectionStatusText,
+    ));
 }
 //
 // let record_block_headers.iter().map(|block_header| {
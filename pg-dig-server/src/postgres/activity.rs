@@ -0,0 +1,123 @@
+use crate::postgres::common::timestamp::TimestampTz;
+use crate::postgres::common::transaction_id::{TransactionId, INVALID_TRANSACTION_ID};
+use crate::postgres::records::standby::{RunningXacts, StandbyLock, StandbyRecord};
+use crate::postgres::records::{Oid, RmgrRecord};
+use crate::postgres::xlog_message::XLogMessage;
+use std::collections::HashMap;
+
+/// What the WAL has shown of a transaction that has not finished yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionActivity {
+    /// The first record seen for the transaction
+    pub first_lsn: u64,
+    pub first_seen: Option<TimestampTz>,
+    /// How many records it has written
+    pub records: u64,
+}
+
+/// A live model of in-progress transactions and the AccessExclusiveLocks they hold, built from
+/// WAL alone, the way a hot standby tracks them.
+///
+/// A transaction is in progress from its first record until its commit or abort. RUNNING_XACTS
+/// snapshots from the primary drop the ones whose end we missed, e.g. from before we started.
+#[derive(Debug, Default)]
+pub struct ActivityTracker {
+    transactions: HashMap<TransactionId, TransactionActivity>,
+    locks: HashMap<TransactionId, Vec<StandbyLock>>,
+}
+
+impl ActivityTracker {
+    pub fn new() -> ActivityTracker {
+        ActivityTracker::default()
+    }
+
+    /// Updates the model with the next record from the stream.
+    pub fn observe(&mut self, message: &XLogMessage) {
+        let xid = message.wal_header.xl_xid;
+        let time = message.message_time();
+
+        match &message.record {
+            RmgrRecord::Transaction(record) => {
+                /* a prepared transaction stays in progress until its COMMIT PREPARED or ABORT PREPARED */
+                for completed in record.completed_xids(xid) {
+                    self.transactions.remove(&completed);
+                    self.locks.remove(&completed);
+                }
+                return;
+            },
+            RmgrRecord::Standby(StandbyRecord::Lock(locks)) => {
+                for lock in locks {
+                    self.begin(lock.xid, message.header.start_lsn, time);
+                    self.locks.entry(lock.xid).or_default().push(*lock);
+                }
+            },
+            RmgrRecord::Standby(StandbyRecord::RunningXacts(running)) => self.apply_snapshot(running),
+            _ => {},
+        }
+
+        if xid != INVALID_TRANSACTION_ID {
+            self.begin(xid, message.header.start_lsn, time).records += 1;
+        }
+    }
+
+    fn begin(&mut self, xid: TransactionId, lsn: u64, time: Option<TimestampTz>) -> &mut TransactionActivity {
+        self.transactions.entry(xid).or_insert(TransactionActivity {
+            first_lsn: lsn,
+            first_seen: time,
+            records: 0,
+        })
+    }
+
+    /* forget transactions the snapshot says are over; those assigned after it was taken stay */
+    fn apply_snapshot(&mut self, running: &RunningXacts) {
+        let still_running = |xid: &TransactionId| match running.subxid_overflow {
+            /* subtransactions are missing from the list, so only the horizon can be trusted */
+            true => !xid.precedes(running.oldest_running_xid),
+            false => {
+                running.xids.contains(xid) || running.subxids.contains(xid) || !xid.precedes(running.next_xid)
+            },
+        };
+
+        self.transactions.retain(|xid, _| still_running(xid));
+        self.locks.retain(|xid, _| still_running(xid));
+    }
+
+    /// The transactions in progress, oldest first.
+    pub fn in_progress(&self) -> Vec<(TransactionId, &TransactionActivity)> {
+        let mut transactions: Vec<_> = self.transactions.iter().map(|(xid, activity)| (*xid, activity)).collect();
+        transactions.sort_by_key(|(xid, activity)| (activity.first_lsn, xid.0));
+        transactions
+    }
+
+    /// The transaction that has been running the longest.
+    pub fn oldest(&self) -> Option<(TransactionId, &TransactionActivity)> {
+        self.in_progress().into_iter().next()
+    }
+
+    /// Transactions first seen before `time`, oldest first; those seen without a time are left out.
+    pub fn running_since(&self, time: TimestampTz) -> Vec<(TransactionId, &TransactionActivity)> {
+        self.in_progress()
+            .into_iter()
+            .filter(|(_, activity)| activity.first_seen.is_some_and(|first_seen| first_seen < time))
+            .collect()
+    }
+
+    /// The transactions holding an AccessExclusiveLock on each relation, keyed by database and
+    /// relation.
+    pub fn locks_by_relation(&self) -> HashMap<(Oid, Oid), Vec<TransactionId>> {
+        let mut relations: HashMap<(Oid, Oid), Vec<TransactionId>> = HashMap::new();
+
+        for lock in self.locks.values().flatten() {
+            let holders = relations.entry((lock.db_id, lock.rel_id)).or_default();
+            if !holders.contains(&lock.xid) {
+                holders.push(lock.xid);
+            }
+        }
+        relations
+    }
+
+    /// How many AccessExclusiveLocks are held.
+    pub fn lock_count(&self) -> usize {
+        self.locks.values().map(Vec::len).sum()
+    }
+}
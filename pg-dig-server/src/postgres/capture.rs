@@ -0,0 +1,309 @@
+use crate::postgres::common::timestamp::TimestampTz;
+use crate::postgres::error::PgDigError;
+use crate::postgres::replication::{decode_record, push_xlog_data};
+use crate::postgres::source::WalSource;
+use crate::postgres::timeline::SystemIdentification;
+use crate::postgres::xlog::reassembler::RecordReassembler;
+use crate::postgres::xlog_message::XLogMessage;
+use log::warn;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/*
+    Capture file layout, all integers little-endian:
+
+    file header
+        magic       8 bytes, "PGDIGCAP"
+        version     u16
+        system_id   u64     IDENTIFY_SYSTEM of the captured server
+        timeline    u32
+        xlogpos     u64
+        dbname      u16 length (0xFFFF for NULL), then that many bytes of UTF-8
+
+    then one entry per CopyData message
+        received_at i64     TimestampTz when the message was read
+        length      u32
+        payload     length bytes, starting with the message type ('w', 'k')
+ */
+pub const CAPTURE_MAGIC: &[u8; 8] = b"PGDIGCAP";
+pub const CAPTURE_VERSION: u16 = 1;
+const NULL_DBNAME: u16 = 0xFFFF;
+
+/// One CopyData message from a capture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedMessage {
+    pub received_at: TimestampTz,
+    pub payload: Vec<u8>,
+}
+
+/// CaptureWriter records the raw messages of a replication session.
+pub struct CaptureWriter<W: Write> {
+    writer: W,
+}
+
+impl CaptureWriter<BufWriter<File>> {
+    /// Creates a capture file at `path`, replacing any file already there.
+    pub fn create(path: &Path, system: &SystemIdentification) -> Result<Self, PgDigError> {
+        let file = File::create(path).map_err(|e| capture_error(path, e))?;
+        CaptureWriter::new(BufWriter::new(file), system)
+    }
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Starts a capture by writing the file header.
+    pub fn new(mut writer: W, system: &SystemIdentification) -> Result<Self, PgDigError> {
+        let mut header = CAPTURE_MAGIC.to_vec();
+        header.extend_from_slice(&CAPTURE_VERSION.to_le_bytes());
+        header.extend_from_slice(&system.system_id.to_le_bytes());
+        header.extend_from_slice(&system.timeline.to_le_bytes());
+        header.extend_from_slice(&system.xlogpos.to_le_bytes());
+
+        match &system.dbname {
+            Some(dbname) if dbname.len() < NULL_DBNAME as usize => {
+                header.extend_from_slice(&(dbname.len() as u16).to_le_bytes());
+                header.extend_from_slice(dbname.as_bytes());
+            },
+            Some(dbname) => return Err(PgDigError::Capture(format!("database name is too long: {}", dbname))),
+            None => header.extend_from_slice(&NULL_DBNAME.to_le_bytes()),
+        }
+
+        writer.write_all(&header).map_err(write_error)?;
+        Ok(CaptureWriter { writer })
+    }
+
+    pub fn write_message(&mut self, received_at: TimestampTz, payload: &[u8]) -> Result<(), PgDigError> {
+        self.writer.write_all(&received_at.to_le_bytes()).map_err(write_error)?;
+        self.writer.write_all(&(payload.len() as u32).to_le_bytes()).map_err(write_error)?;
+        self.writer.write_all(payload).map_err(write_error)
+    }
+
+    pub fn flush(&mut self) -> Result<(), PgDigError> {
+        self.writer.flush().map_err(write_error)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// CaptureReader reads back what a CaptureWriter wrote.
+pub struct CaptureReader<R: Read> {
+    reader: R,
+    system: SystemIdentification,
+}
+
+impl CaptureReader<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<Self, PgDigError> {
+        let file = File::open(path).map_err(|e| capture_error(path, e))?;
+        CaptureReader::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Reads the file header.
+    pub fn new(mut reader: R) -> Result<Self, PgDigError> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic).map_err(read_error)?;
+
+        if &magic != CAPTURE_MAGIC {
+            return Err(PgDigError::Capture("not a capture file".to_string()));
+        }
+
+        let version = u16::from_le_bytes(read_array(&mut reader)?);
+        if version != CAPTURE_VERSION {
+            return Err(PgDigError::Capture(format!("unsupported capture version {}", version)));
+        }
+
+        let system_id = u64::from_le_bytes(read_array(&mut reader)?);
+        let timeline = u32::from_le_bytes(read_array(&mut reader)?);
+        let xlogpos = u64::from_le_bytes(read_array(&mut reader)?);
+        let dbname = match u16::from_le_bytes(read_array(&mut reader)?) {
+            NULL_DBNAME => None,
+            length => {
+                let mut dbname = vec![0; length as usize];
+                reader.read_exact(&mut dbname).map_err(read_error)?;
+                Some(String::from_utf8(dbname).map_err(|e| PgDigError::Capture(format!("invalid database name: {}", e)))?)
+            },
+        };
+
+        Ok(CaptureReader {
+            reader,
+            system: SystemIdentification {
+                system_id,
+                timeline,
+                xlogpos,
+                dbname,
+            },
+        })
+    }
+
+    /// The IDENTIFY_SYSTEM result of the captured server.
+    pub fn system(&self) -> &SystemIdentification {
+        &self.system
+    }
+
+    /// Reads the next message, or None at the end of the capture.
+    ///
+    /// A capture cut off partway through a message, e.g. because the process was killed,
+    /// ends at the last complete one.
+    pub fn next_message(&mut self) -> Result<Option<CapturedMessage>, PgDigError> {
+        let mut received_at = [0u8; 8];
+
+        match self.reader.read_exact(&mut received_at) {
+            Ok(()) => {},
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(read_error(e)),
+        }
+
+        let message = read_array(&mut self.reader).and_then(|length| {
+            let mut payload = vec![0; u32::from_le_bytes(length) as usize];
+            self.reader.read_exact(&mut payload).map_err(read_error)?;
+            Ok(payload)
+        });
+
+        match message {
+            Ok(payload) => Ok(Some(CapturedMessage {
+                received_at: i64::from_le_bytes(received_at),
+                payload,
+            })),
+            Err(e) => {
+                warn!("capture ends with a partial message: {}", e);
+                Ok(None)
+            },
+        }
+    }
+}
+
+/// How fast a capture is replayed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Keep the original gaps between messages, divided by this factor
+    Scaled(f64),
+    /// Don't wait between messages at all
+    Unthrottled,
+}
+
+impl FromStr for ReplaySpeed {
+    type Err = String;
+
+    /// Parses "max" or a speed-up factor such as "1" (the original timing) or "10".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "max" => Ok(ReplaySpeed::Unthrottled),
+            factor => match factor.parse::<f64>() {
+                Ok(factor) if factor > 0.0 && factor.is_finite() => Ok(ReplaySpeed::Scaled(factor)),
+                _ => Err(format!("expected a positive number or \"max\", got {}", s)),
+            },
+        }
+    }
+}
+
+impl Default for ReplaySpeed {
+    fn default() -> Self {
+        ReplaySpeed::Scaled(1.0)
+    }
+}
+
+/// ReplaySource plays a capture back as if it were coming from the server.
+pub struct ReplaySource<R: Read> {
+    capture: CaptureReader<R>,
+    speed: ReplaySpeed,
+    reassembler: RecordReassembler,
+    /// When replay started, and the capture time of the first message
+    started: Option<(Instant, TimestampTz)>,
+    send_time: u64,
+    last_record_end: u64,
+}
+
+impl ReplaySource<BufReader<File>> {
+    pub fn open(path: &Path, speed: ReplaySpeed) -> Result<Self, PgDigError> {
+        Ok(ReplaySource::new(CaptureReader::open(path)?, speed))
+    }
+}
+
+impl<R: Read> ReplaySource<R> {
+    pub fn new(capture: CaptureReader<R>, speed: ReplaySpeed) -> Self {
+        ReplaySource {
+            capture,
+            speed,
+            reassembler: RecordReassembler::new(),
+            started: None,
+            send_time: 0,
+            last_record_end: 0,
+        }
+    }
+
+    pub fn system(&self) -> &SystemIdentification {
+        self.capture.system()
+    }
+
+    /// Sleeps until `received_at` comes round again on the replay clock.
+    fn wait_for(&mut self, received_at: TimestampTz) {
+        let (replay_start, capture_start) = *self.started.get_or_insert((Instant::now(), received_at));
+
+        if let ReplaySpeed::Scaled(factor) = self.speed {
+            let offset = Duration::from_micros((received_at - capture_start).max(0) as u64).div_f64(factor);
+            let due = replay_start + offset;
+            let now = Instant::now();
+
+            if due > now {
+                thread::sleep(due - now);
+            }
+        }
+    }
+}
+
+impl<R: Read> WalSource for ReplaySource<R> {
+    fn next_message(&mut self) -> Result<XLogMessage, PgDigError> {
+        loop {
+            if let Some(record) = self.reassembler.next_record() {
+                let record = record?;
+                /* a reconnect during capture streams the last records again */
+                if record.end_lsn <= self.last_record_end {
+                    continue;
+                }
+                self.last_record_end = record.end_lsn;
+                return decode_record(record, self.send_time);
+            }
+
+            let message = match self.capture.next_message()? {
+                Some(message) => message,
+                None => return Err(PgDigError::EndOfStream),
+            };
+
+            self.wait_for(message.received_at);
+
+            /* keepalives only matter to a live connection */
+            if message.payload.first() == Some(&b'w') {
+                let header = push_xlog_data(&mut self.reassembler, &message.payload[1..])?;
+                self.send_time = header.send_time;
+            }
+        }
+    }
+
+    fn last_record_end(&self) -> u64 {
+        self.last_record_end
+    }
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N], PgDigError> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes).map_err(read_error)?;
+    Ok(bytes)
+}
+
+fn capture_error(path: &Path, e: std::io::Error) -> PgDigError {
+    PgDigError::Capture(format!("{}: {}", path.display(), e))
+}
+
+fn read_error(e: std::io::Error) -> PgDigError {
+    PgDigError::Capture(format!("failed to read capture: {}", e))
+}
+
+fn write_error(e: std::io::Error) -> PgDigError {
+    PgDigError::Capture(format!("failed to write capture: {}", e))
+}
@@ -17,12 +17,12 @@ impl Lsn {
     }
 
     /// Converts the LSN to a single 64-bit value
-    fn to_u64(&self) -> u64 {
+    pub fn to_u64(self) -> u64 {
         ((self.high as u64) << 32) | (self.low as u64)
     }
 
     /// Creates an LSN from a 64-bit value
-    pub(crate) fn from_u64(value: u64) -> Self {
+    pub fn from_u64(value: u64) -> Self {
         let high = (value >> 32) as u32;
         let low = (value & 0xFFFF_FFFF) as u32;
         Lsn::new(high, low)
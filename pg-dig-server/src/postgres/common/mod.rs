@@ -4,9 +4,10 @@ use std::ffi::c_uint;
 pub mod lsn;
 pub mod transaction_id;
 pub mod rmgr;
+pub mod timestamp;
 
 #[repr(C)]
-#[derive(Debug, Pread, PartialEq)]
+#[derive(Debug, Clone, Copy, Pread, PartialEq, Eq, Hash)]
 pub struct RelFileLocator {
     pub spc_oid: c_uint,    /* tablespace */
     pub db_oid: c_uint,     /* database */
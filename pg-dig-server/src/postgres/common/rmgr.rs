@@ -3,12 +3,19 @@
 
 use std::fmt;
 use scroll::Pread;
+use crate::postgres::error::PgDigError;
+use crate::postgres::records::{
+    brin, btree, clog, commit_ts, dbase, generic, gin, gist, hash, heap, heap2, logical_message, multixact, relmap,
+    replorigin, sequence, spgist, standby, storage, tablespace, xact, xlog, XLR_RMGR_INFO_MASK,
+};
+use crate::postgres::xlog::decode_error::DecodeError;
 
 #[repr(C)]
 #[derive(Clone, Debug, Pread, PartialEq)]
 pub struct RmgrId(pub u8);
 
 #[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ResourceManager {
     XLOG = 0,
     Transaction = 1,
@@ -35,8 +42,35 @@ pub enum ResourceManager {
 }
 
 impl ResourceManager {
-    pub(crate) fn get_record_type(&self, rmgr_info: u8) -> String {
-        "NYI".to_string()
+    /// Names the record type in `xl_info` the way pg_waldump does, e.g. "HOT_UPDATE". The
+    /// XLR_* flag bits are ignored.
+    pub fn get_record_type(&self, xl_info: u8) -> String {
+        let info = xl_info & XLR_RMGR_INFO_MASK;
+
+        match self {
+            ResourceManager::XLOG => xlog::identify(info).to_string(),
+            ResourceManager::Transaction => xact::identify(info).to_string(),
+            ResourceManager::Storage => storage::identify(info).to_string(),
+            ResourceManager::CLOG => clog::identify(info).to_string(),
+            ResourceManager::Database => dbase::identify(info).to_string(),
+            ResourceManager::Tablespace => tablespace::identify(info).to_string(),
+            ResourceManager::MultiXact => multixact::identify(info).to_string(),
+            ResourceManager::RelMap => relmap::identify(info).to_string(),
+            ResourceManager::Standby => standby::identify(info).to_string(),
+            ResourceManager::Heap => heap::identify(info).to_string(),
+            ResourceManager::Heap2 => heap2::identify(info).to_string(),
+            ResourceManager::Btree => btree::identify(info).to_string(),
+            ResourceManager::Hash => hash::identify(info).to_string(),
+            ResourceManager::Gin => gin::identify(info).to_string(),
+            ResourceManager::Gist => gist::identify(info).to_string(),
+            ResourceManager::Sequence => sequence::identify(info).to_string(),
+            ResourceManager::SPGist => spgist::identify(info).to_string(),
+            ResourceManager::BRIN => brin::identify(info).to_string(),
+            ResourceManager::CommitTs => commit_ts::identify(info).to_string(),
+            ResourceManager::ReplicationOrigin => replorigin::identify(info).to_string(),
+            ResourceManager::Generic => generic::identify(info).to_string(),
+            ResourceManager::LogicalMessage => logical_message::identify(info).to_string(),
+        }
     }
 }
 
@@ -45,13 +79,15 @@ pub struct SimpleRmgrInfo {
     pub record_type: String
 }
 
-pub fn get_simple_rmgr_info(rmgr_id: RmgrId, rmgr_info: u8) -> SimpleRmgrInfo {
-    let resource_manager = ResourceManager::try_from(rmgr_id).expect("invalid rmgr_id");
-    let record_type = resource_manager.get_record_type(rmgr_info);
-    SimpleRmgrInfo {
+/// Names the resource manager and record type of a record from its `xl_rmid` and `xl_info`.
+pub fn get_simple_rmgr_info(rmgr_id: RmgrId, xl_info: u8) -> Result<SimpleRmgrInfo, PgDigError> {
+    let rmid = rmgr_id.0;
+    let resource_manager = ResourceManager::try_from(rmgr_id)
+        .map_err(|_| PgDigError::MalformedRecord(DecodeError::InvalidResourceManager { rmid }))?;
+    Ok(SimpleRmgrInfo {
         rmgr_name: resource_manager.to_string(),
-        record_type: "NYI".to_string()
-    }
+        record_type: resource_manager.get_record_type(xl_info),
+    })
 }
 
 impl TryFrom<RmgrId> for ResourceManager {
@@ -115,18 +151,3 @@ impl fmt::Display for ResourceManager {
         write!(f, "{}", name)
     }
 }
-
-
-/* XLOG info values for XLOG rmgr */
-pub const XLOG_CHECKPOINT_SHUTDOWN: u32 = 0x00;
-pub const XLOG_CHECKPOINT_ONLINE: u32 = 0x10;
-pub const XLOG_NOOP: u32 = 0x20;
-pub const XLOG_NEXTOID: u32 = 0x30;
-pub const XLOG_SWITCH: u32 = 0x40;
-pub const XLOG_BACKUP_END: u32 = 0x50;
-pub const XLOG_PARAMETER_CHANGE: u32 = 0x60;
-pub const XLOG_RESTORE_POINT: u32 = 0x70;
-pub const XLOG_FPW_CHANGE: u32 = 0x80;
-pub const XLOG_END_OF_RECOVERY: u32 = 0x90;
-pub const XLOG_FPI_FOR_HINT: u32 = 0xA0;
-pub const XLOG_FPI: u32 = 0xB0;
@@ -0,0 +1,62 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Seconds between the Unix epoch and the PostgreSQL epoch (2000-01-01 00:00:00 UTC).
+pub const POSTGRES_EPOCH_UNIX_SECS: u64 = 946_684_800;
+
+/// A PostgreSQL TimestampTz: microseconds since 2000-01-01 00:00:00 UTC.
+pub type TimestampTz = i64;
+
+/// Converts a system time into a PostgreSQL timestamp.
+pub fn to_timestamp(time: SystemTime) -> TimestampTz {
+    let postgres_epoch = UNIX_EPOCH + Duration::from_secs(POSTGRES_EPOCH_UNIX_SECS);
+
+    match time.duration_since(postgres_epoch) {
+        Ok(since) => since.as_micros() as TimestampTz,
+        Err(e) => -(e.duration().as_micros() as TimestampTz),
+    }
+}
+
+/// Converts a PostgreSQL timestamp into a system time.
+pub fn from_timestamp(timestamp: TimestampTz) -> SystemTime {
+    let postgres_epoch = UNIX_EPOCH + Duration::from_secs(POSTGRES_EPOCH_UNIX_SECS);
+    let offset = Duration::from_micros(timestamp.unsigned_abs());
+
+    match timestamp >= 0 {
+        true => postgres_epoch + offset,
+        false => postgres_epoch - offset,
+    }
+}
+
+/// Formats a PostgreSQL timestamp the way pg_waldump does, e.g. "2025-10-18 09:20:00.123456 UTC".
+pub fn format_timestamp(timestamp: TimestampTz) -> String {
+    const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+    let days = timestamp.div_euclid(MICROS_PER_DAY);
+    let micros = timestamp.rem_euclid(MICROS_PER_DAY);
+    let (year, month, day) = civil_from_days(days + (POSTGRES_EPOCH_UNIX_SECS / 86_400) as i64);
+    let secs = micros / 1_000_000;
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:06} UTC",
+        year, month, day, secs / 3600, secs / 60 % 60, secs % 60, micros % 1_000_000
+    )
+}
+
+/* the proleptic Gregorian date of a day count from 1970-01-01, after Howard Hinnant's algorithm */
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+
+    (year_of_era + era * 400 + (month <= 2) as i64, month, day)
+}
+
+/// The current time as a PostgreSQL timestamp.
+pub fn now() -> TimestampTz {
+    to_timestamp(SystemTime::now())
+}
@@ -1,9 +1,25 @@
 use scroll::Pread;
 
 #[repr(C)]
-#[derive(Debug, Copy, Clone, Pread, PartialEq)]
+#[derive(Debug, Copy, Clone, Pread, PartialEq, Eq, Hash)]
 pub struct TransactionId(pub u32);
 
+impl TransactionId {
+    /// Whether this transaction started before `other`, the way TransactionIdPrecedes compares
+    /// normal xids modulo 2^32 and special ones as plain numbers.
+    pub fn precedes(self, other: TransactionId) -> bool {
+        if self.0 < FIRST_NORMAL_TRANSACTION_ID.0 || other.0 < FIRST_NORMAL_TRANSACTION_ID.0 {
+            return self.0 < other.0;
+        }
+        (self.0.wrapping_sub(other.0) as i32) < 0
+    }
+}
+
+/// A TransactionId together with its 32-bit epoch.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pread, PartialEq)]
+pub struct FullTransactionId(pub u64);
+
 pub const INVALID_TRANSACTION_ID: TransactionId = TransactionId(0);
 pub const BOOTSTRAP_TRANSACTION_ID: TransactionId = TransactionId(1);
 pub const FROZEN_TRANSACTION_ID: TransactionId = TransactionId(2);
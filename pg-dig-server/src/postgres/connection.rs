@@ -1,9 +1,105 @@
-use crate::postgres::bindings::{pg_conn, PQconnectdb};
-use std::ffi::CString;
-
-pub unsafe fn connect(conn_string: &str) -> *mut pg_conn {
-    let conn_string = CString::new(conn_string).expect("failed to build connection string");
-    println!("connecting: {:?}", conn_string);
-    let conn = PQconnectdb(conn_string.as_ptr());
-    conn
+use crate::postgres::error::PgDigError;
+#[cfg(feature = "native-client")]
+use crate::postgres::native::NativeConnection;
+#[cfg(all(feature = "libpq", not(feature = "native-client")))]
+use crate::postgres::pq_connection::PqConnection;
+use crate::postgres::timeline::{parse_column, parse_lsn_column};
+use std::time::Duration;
+
+#[cfg(not(any(feature = "libpq", feature = "native-client")))]
+compile_error!("enable the libpq or native-client feature to connect to servers");
+
+/// How the server ended a COPY stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StreamEnd {
+    /// The timeline being streamed ended; the next one starts at `start_lsn`.
+    TimelineSwitch { timeline: u32, start_lsn: u64 },
+    /// The server is done sending WAL, e.g. because it is shutting down.
+    Finished,
+}
+
+impl StreamEnd {
+    /// Reads the next_tli and next_tli_startpos row the server sends after a timeline ends.
+    pub(crate) fn timeline_switch(row: &[Option<String>]) -> Result<StreamEnd, PgDigError> {
+        Ok(StreamEnd::TimelineSwitch {
+            timeline: parse_column(&row[0], "next_tli")?,
+            start_lsn: parse_lsn_column(&row[1], "next_tli_startpos")?,
+        })
+    }
+}
+
+/// What `get_copy_data` found.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CopyData {
+    /// A CopyData message from the server
+    Message(Vec<u8>),
+    /// No complete message has arrived yet
+    Pending,
+    /// The server has ended the COPY
+    Done,
+}
+
+/// Interrupter wakes a connection that is waiting for the server from another thread, by
+/// shutting its socket down. The connection fails with a connection error from then on.
+pub struct Interrupter {
+    shutdown: Box<dyn Fn() + Send + Sync>,
+}
+
+impl Interrupter {
+    pub fn new(shutdown: impl Fn() + Send + Sync + 'static) -> Self {
+        Interrupter {
+            shutdown: Box::new(shutdown),
+        }
+    }
+
+    pub fn interrupt(&self) {
+        (self.shutdown)()
+    }
+}
+
+/// ReplicationConnection is a connection in replication mode, made through libpq or the
+/// native client.
+///
+/// It covers what streaming needs and no more: commands that return a single row, and the
+/// COPY BOTH stream that START_REPLICATION opens.
+pub trait ReplicationConnection {
+    /// Runs a command that returns a single row, such as IDENTIFY_SYSTEM, and reads `columns`
+    /// from it. NULL values come back as None.
+    fn query_row(&mut self, stmt: &str, columns: &[&str]) -> Result<Vec<Option<String>>, PgDigError>;
+
+    /// Runs a command that returns no rows, such as DROP_REPLICATION_SLOT.
+    fn exec_command(&mut self, stmt: &str) -> Result<(), PgDigError>;
+
+    /// Runs a command that puts the connection into COPY BOTH mode, i.e. START_REPLICATION.
+    fn start_copy_both(&mut self, stmt: &str) -> Result<(), PgDigError>;
+
+    /// Takes the next CopyData message from the server if one has arrived, without waiting.
+    /// Once it returns `Done`, `end_copy` must be called.
+    fn get_copy_data(&mut self) -> Result<CopyData, PgDigError>;
+
+    /// Waits up to `timeout` for the server to send something. Returns false on timeout.
+    fn wait_readable(&mut self, timeout: Duration) -> Result<bool, PgDigError>;
+
+    /// Sends a CopyData message to the server.
+    fn put_copy_data(&mut self, bytes: &[u8]) -> Result<(), PgDigError>;
+
+    /// Ends our half of a COPY the server has ended, and reads why it ended it.
+    fn end_copy(&mut self) -> Result<StreamEnd, PgDigError>;
+
+    /// Makes an Interrupter for this connection's socket.
+    fn interrupter(&self) -> Result<Interrupter, PgDigError>;
+
+    /// Describes the state of the connection in libpq's terms, e.g. "connection ok".
+    fn status(&self) -> String;
+}
+
+/// Connects to the server in `conn_string`, a libpq style `key=value` connection string.
+///
+/// The native client is used when the native-client feature is enabled, and libpq otherwise.
+pub fn connect(conn_string: &str) -> Result<Box<dyn ReplicationConnection>, PgDigError> {
+    #[cfg(feature = "native-client")]
+    return Ok(Box::new(NativeConnection::connect(conn_string)?));
+
+    #[cfg(all(feature = "libpq", not(feature = "native-client")))]
+    return Ok(Box::new(PqConnection::connect(conn_string)?));
 }
\ No newline at end of file
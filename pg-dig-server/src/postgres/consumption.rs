@@ -0,0 +1,130 @@
+use crate::postgres::common::timestamp::TimestampTz;
+use crate::postgres::common::transaction_id::{TransactionId, INVALID_TRANSACTION_ID};
+use crate::postgres::common::RelFileLocator;
+use crate::postgres::records::clog::ClogRecord;
+use crate::postgres::records::multixact::{MultiXactId, MultiXactRecord};
+use crate::postgres::records::sequence::SequenceRecord;
+use crate::postgres::records::RmgrRecord;
+use crate::postgres::xlog_message::XLogMessage;
+use std::collections::HashMap;
+
+/* TimestampTz counts microseconds */
+const USECS_PER_SEC: f64 = 1_000_000.0;
+
+/// How often a sequence was logged and the value it was last logged at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceActivity {
+    /// SEQ_LOG records, one per `log_cnt` nextval() calls
+    pub records: u64,
+    pub last_value: i64,
+}
+
+/// Tracks how fast transaction ids and multixact ids are used up, and how far they are from the
+/// oldest ones still in tables, from WAL alone.
+///
+/// The oldest ids come from checkpoints and CLOG/MultiXact truncations, so ages are unknown until
+/// one of those has been seen.
+#[derive(Debug, Default)]
+pub struct ConsumptionTracker {
+    newest_xid: Option<TransactionId>,
+    oldest_xid: Option<TransactionId>,
+    newest_multi: Option<MultiXactId>,
+    oldest_multi: Option<MultiXactId>,
+    /* the first and latest (time, xid) seen, for the rate */
+    first_sample: Option<(TimestampTz, TransactionId)>,
+    last_sample: Option<(TimestampTz, TransactionId)>,
+    multixact_members: u64,
+    sequences: HashMap<RelFileLocator, SequenceActivity>,
+}
+
+impl ConsumptionTracker {
+    pub fn new() -> ConsumptionTracker {
+        ConsumptionTracker::default()
+    }
+
+    /// Updates the counters with the next record from the stream.
+    pub fn observe(&mut self, message: &XLogMessage) {
+        let xid = message.wal_header.xl_xid;
+        if xid != INVALID_TRANSACTION_ID {
+            self.see_xid(xid, message.message_time());
+        }
+
+        match &message.record {
+            RmgrRecord::XLog(record) => {
+                if let Some(checkpoint) = record.checkpoint() {
+                    self.oldest_xid = Some(checkpoint.oldest_xid);
+                    self.oldest_multi = Some(checkpoint.oldest_multi);
+                }
+            },
+            RmgrRecord::CLOG(ClogRecord::Truncate { oldest_xact, .. }) => self.oldest_xid = Some(*oldest_xact),
+            RmgrRecord::MultiXact(MultiXactRecord::Create(create)) => {
+                if self.newest_multi.is_none_or(|newest| multi_precedes(newest, create.mid)) {
+                    self.newest_multi = Some(create.mid);
+                }
+                self.multixact_members += create.members.len() as u64;
+            },
+            RmgrRecord::MultiXact(MultiXactRecord::Truncate(truncate)) => self.oldest_multi = Some(truncate.end_trunc_off),
+            RmgrRecord::Sequence(SequenceRecord::Log { locator, last_value, .. }) => {
+                let sequence = self.sequences.entry(*locator).or_insert(SequenceActivity { records: 0, last_value: 0 });
+                sequence.records += 1;
+                sequence.last_value = *last_value;
+            },
+            _ => {},
+        }
+    }
+
+    fn see_xid(&mut self, xid: TransactionId, time: Option<TimestampTz>) {
+        if self.newest_xid.is_some_and(|newest| !newest.precedes(xid)) {
+            return;
+        }
+        self.newest_xid = Some(xid);
+
+        if let Some(time) = time {
+            self.first_sample.get_or_insert((time, xid));
+            self.last_sample = Some((time, xid));
+        }
+    }
+
+    /// The newest transaction id seen.
+    pub fn newest_xid(&self) -> Option<TransactionId> {
+        self.newest_xid
+    }
+
+    /// How many xids the newest one is past the oldest a table may still hold, the age that
+    /// approaches 2^31 before wraparound.
+    pub fn xid_age(&self) -> Option<u32> {
+        Some(self.newest_xid?.0.wrapping_sub(self.oldest_xid?.0))
+    }
+
+    /// How many multixact ids the newest one is past the oldest still needed.
+    pub fn multixact_age(&self) -> Option<u32> {
+        Some(self.newest_multi?.wrapping_sub(self.oldest_multi?))
+    }
+
+    /// Transaction ids used per second between the first and latest record with a time.
+    pub fn xids_per_second(&self) -> Option<f64> {
+        let ((first_time, first_xid), (last_time, last_xid)) = (self.first_sample?, self.last_sample?);
+        if last_time <= first_time {
+            return None;
+        }
+
+        Some(last_xid.0.wrapping_sub(first_xid.0) as f64 / ((last_time - first_time) as f64 / USECS_PER_SEC))
+    }
+
+    /// How many member entries the multixacts created so far take up.
+    pub fn multixact_members(&self) -> u64 {
+        self.multixact_members
+    }
+
+    /// The sequences logged, most often first.
+    pub fn busiest_sequences(&self) -> Vec<(RelFileLocator, &SequenceActivity)> {
+        let mut sequences: Vec<_> = self.sequences.iter().map(|(locator, activity)| (*locator, activity)).collect();
+        sequences.sort_by_key(|(locator, activity)| (std::cmp::Reverse(activity.records), locator.rel_number));
+        sequences
+    }
+}
+
+/* MultiXactIdPrecedes: compared modulo 2^32 like xids */
+fn multi_precedes(a: MultiXactId, b: MultiXactId) -> bool {
+    (a.wrapping_sub(b) as i32) < 0
+}
@@ -0,0 +1,61 @@
+use crate::postgres::xlog::decode_error::DecodeError;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// PgDigError is the error type for connecting, replicating and decoding.
+///
+/// The variants are split by what a consumer should do about them: connection and protocol
+/// errors end the stream, while unsupported or malformed records can be skipped.
+#[derive(Debug)]
+pub enum PgDigError {
+    /// The configuration is missing or invalid.
+    Config(String),
+    /// The connection could not be made or was lost.
+    Connection(String),
+    /// The server rejected a command or sent something we don't understand.
+    Protocol(String),
+    /// A WAL segment file could not be read or is not a valid segment.
+    WalFile(String),
+    /// A capture file could not be written or read back.
+    Capture(String),
+    /// The server ended the COPY stream.
+    EndOfStream,
+    /// The record could not be decoded.
+    MalformedRecord(DecodeError),
+}
+
+impl PgDigError {
+    /// Whether reading can carry on with the next record after this error.
+    pub fn is_skippable(&self) -> bool {
+        matches!(self, PgDigError::MalformedRecord(_))
+    }
+}
+
+impl fmt::Display for PgDigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PgDigError::Config(reason) => write!(f, "config error: {}", reason),
+            PgDigError::Connection(reason) => write!(f, "connection error: {}", reason),
+            PgDigError::Protocol(reason) => write!(f, "protocol error: {}", reason),
+            PgDigError::WalFile(reason) => write!(f, "WAL file error: {}", reason),
+            PgDigError::Capture(reason) => write!(f, "capture error: {}", reason),
+            PgDigError::EndOfStream => write!(f, "end of stream"),
+            PgDigError::MalformedRecord(error) => write!(f, "malformed record: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for PgDigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PgDigError::MalformedRecord(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecodeError> for PgDigError {
+    fn from(error: DecodeError) -> Self {
+        PgDigError::MalformedRecord(error)
+    }
+}
@@ -0,0 +1,131 @@
+use crate::postgres::common::timestamp::{now, TimestampTz};
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+use std::time::{Duration, Instant};
+
+/// How often a status update is sent when the server doesn't ask for one.
+pub const DEFAULT_STATUS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Primary keepalive message ('k'), sent by the walsender.
+///
+/// Like the rest of the replication protocol, its fields are in network byte order.
+#[derive(Debug, PartialEq)]
+pub struct PrimaryKeepalive {
+    /// The end of WAL on the server
+    pub wal_end: u64,
+    /// When it was sent
+    pub send_time: TimestampTz,
+    /// Whether the server wants a status update straight away
+    pub reply_requested: bool,
+}
+
+impl PrimaryKeepalive {
+    /// Reads a keepalive from the bytes after the 'k' message type.
+    pub fn from_bytes(bytes: &[u8]) -> Result<PrimaryKeepalive, DecodeError> {
+        let mut reader = ByteReader::with_endian(bytes, scroll::BE);
+
+        Ok(PrimaryKeepalive {
+            wal_end: reader.read_u64("wal_end")?,
+            send_time: reader.read::<i64>("send_time")?,
+            reply_requested: reader.read_u8("reply_requested")? != 0,
+        })
+    }
+}
+
+/// Standby status update message ('r'), sent back to the walsender.
+#[derive(Debug, PartialEq)]
+pub struct StandbyStatusUpdate {
+    /// The location after the last WAL byte received
+    pub write_lsn: u64,
+    /// The location after the last WAL byte the consumer has handled
+    pub flush_lsn: u64,
+    /// The location after the last WAL byte the consumer has handled
+    pub apply_lsn: u64,
+    pub clock: TimestampTz,
+    /// Asks the server to reply with a keepalive immediately
+    pub reply_requested: bool,
+}
+
+impl StandbyStatusUpdate {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(34);
+        bytes.push(b'r');
+        bytes.extend_from_slice(&self.write_lsn.to_be_bytes());
+        bytes.extend_from_slice(&self.flush_lsn.to_be_bytes());
+        bytes.extend_from_slice(&self.apply_lsn.to_be_bytes());
+        bytes.extend_from_slice(&self.clock.to_be_bytes());
+        bytes.push(self.reply_requested as u8);
+        bytes
+    }
+}
+
+/// FeedbackTracker decides what to report to the server, and when.
+///
+/// The write position follows what has been received. The flush and apply positions only move
+/// when the consumer confirms it has handled a record, so the replication slot never releases
+/// WAL we haven't looked at.
+pub struct FeedbackTracker {
+    written_lsn: u64,
+    flushed_lsn: u64,
+    status_interval: Duration,
+    last_sent: Option<Instant>,
+}
+
+impl FeedbackTracker {
+    pub fn new(status_interval: Duration) -> Self {
+        FeedbackTracker {
+            written_lsn: 0,
+            flushed_lsn: 0,
+            status_interval,
+            last_sent: None,
+        }
+    }
+
+    pub fn received(&mut self, lsn: u64) {
+        self.written_lsn = self.written_lsn.max(lsn);
+    }
+
+    pub fn confirm(&mut self, lsn: u64) {
+        self.flushed_lsn = self.flushed_lsn.max(lsn);
+        self.written_lsn = self.written_lsn.max(lsn);
+    }
+
+    pub fn flushed_lsn(&self) -> u64 {
+        self.flushed_lsn
+    }
+
+    /// Whether a periodic status update is due.
+    pub fn is_due(&self, now: Instant) -> bool {
+        match self.last_sent {
+            Some(last_sent) => now.duration_since(last_sent) >= self.status_interval,
+            None => true,
+        }
+    }
+
+    /// How long until a periodic status update is due; zero if it already is.
+    pub fn time_until_due(&self, now: Instant) -> Duration {
+        match self.last_sent {
+            Some(last_sent) => self.status_interval.saturating_sub(now.duration_since(last_sent)),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Builds a status update with the current positions and records that it was sent.
+    pub fn status_update(&mut self, sent_at: Instant) -> StandbyStatusUpdate {
+        self.last_sent = Some(sent_at);
+
+        StandbyStatusUpdate {
+            write_lsn: self.written_lsn,
+            flush_lsn: self.flushed_lsn,
+            apply_lsn: self.flushed_lsn,
+            clock: now(),
+            reply_requested: false,
+        }
+    }
+}
+
+impl Default for FeedbackTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_STATUS_INTERVAL)
+    }
+}
@@ -0,0 +1,94 @@
+use crate::postgres::common::RelFileLocator;
+use crate::postgres::records::dbase::DbaseRecord;
+use crate::postgres::records::storage::{fork_name, ForkNumber, StorageRecord, MAIN_FORKNUM};
+use crate::postgres::records::tablespace::TablespaceRecord;
+use crate::postgres::records::xact::XactRecord;
+use crate::postgres::records::{BlockNumber, Oid, RmgrRecord};
+use crate::postgres::xlog_message::XLogMessage;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// A relation, database or tablespace coming into existence, changing size or going away.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LifecycleEvent {
+    RelationCreated { locator: RelFileLocator, fork: ForkNumber },
+    /// Only the first `block` blocks are left
+    RelationTruncated { locator: RelFileLocator, block: BlockNumber },
+    /// The files are removed once the dropping transaction commits, or the creating one aborts
+    RelationDropped { locator: RelFileLocator },
+    DatabaseCreated { db_id: Oid, tablespace_id: Oid },
+    DatabaseDropped { db_id: Oid },
+    TablespaceCreated { ts_id: Oid, path: String },
+    TablespaceDropped { ts_id: Oid },
+}
+
+impl LifecycleEvent {
+    /// The lifecycle events a record stands for, if any.
+    pub fn from_message(message: &XLogMessage) -> Vec<LifecycleEvent> {
+        match &message.record {
+            RmgrRecord::Storage(StorageRecord::Create { locator, fork }) => {
+                vec![LifecycleEvent::RelationCreated { locator: *locator, fork: *fork }]
+            },
+            RmgrRecord::Storage(StorageRecord::Truncate { block, locator, .. }) => {
+                vec![LifecycleEvent::RelationTruncated { locator: *locator, block: *block }]
+            },
+            RmgrRecord::Transaction(
+                XactRecord::Commit(completion)
+                | XactRecord::Abort(completion)
+                | XactRecord::CommitPrepared(completion)
+                | XactRecord::AbortPrepared(completion),
+            ) => completion
+                .dropped_relations
+                .iter()
+                .map(|locator| LifecycleEvent::RelationDropped { locator: *locator })
+                .collect(),
+            RmgrRecord::Database(
+                DbaseRecord::CreateFileCopy { db_id, tablespace_id, .. } | DbaseRecord::CreateWalLog { db_id, tablespace_id },
+            ) => vec![LifecycleEvent::DatabaseCreated { db_id: *db_id, tablespace_id: *tablespace_id }],
+            RmgrRecord::Database(DbaseRecord::Drop { db_id, .. }) => vec![LifecycleEvent::DatabaseDropped { db_id: *db_id }],
+            RmgrRecord::Tablespace(TablespaceRecord::Create { ts_id, path }) => {
+                vec![LifecycleEvent::TablespaceCreated { ts_id: *ts_id, path: path.clone() }]
+            },
+            RmgrRecord::Tablespace(TablespaceRecord::Drop { ts_id }) => vec![LifecycleEvent::TablespaceDropped { ts_id: *ts_id }],
+            _ => Vec::new(),
+        }
+    }
+
+    /// The relation whose blocks are no longer what was drawn for them: a main fork that was
+    /// created afresh, truncated or dropped.
+    pub fn resets_relation(&self) -> Option<&RelFileLocator> {
+        match self {
+            LifecycleEvent::RelationCreated { locator, fork: MAIN_FORKNUM }
+            | LifecycleEvent::RelationTruncated { locator, .. }
+            | LifecycleEvent::RelationDropped { locator } => Some(locator),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LifecycleEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LifecycleEvent::RelationCreated { locator, fork: MAIN_FORKNUM } => {
+                write!(f, "rel {} created in db {}", locator.rel_number, locator.db_oid)
+            },
+            LifecycleEvent::RelationCreated { locator, fork } => write!(
+                f,
+                "rel {} {} fork created in db {}",
+                locator.rel_number, fork_name(*fork), locator.db_oid
+            ),
+            LifecycleEvent::RelationTruncated { locator, block } => {
+                write!(f, "rel {} in db {} truncated to block {}", locator.rel_number, locator.db_oid, block)
+            },
+            LifecycleEvent::RelationDropped { locator } => {
+                write!(f, "rel {} dropped from db {}", locator.rel_number, locator.db_oid)
+            },
+            LifecycleEvent::DatabaseCreated { db_id, tablespace_id } => {
+                write!(f, "db {} created in tablespace {}", db_id, tablespace_id)
+            },
+            LifecycleEvent::DatabaseDropped { db_id } => write!(f, "db {} dropped", db_id),
+            LifecycleEvent::TablespaceCreated { ts_id, path } => write!(f, "tablespace {} created at {}", ts_id, path),
+            LifecycleEvent::TablespaceDropped { ts_id } => write!(f, "tablespace {} dropped", ts_id),
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use crate::postgres::connection::Interrupter;
+use crate::postgres::error::PgDigError;
+use crate::postgres::source::WalSource;
+use crate::postgres::xlog_message::XLogMessage;
+use futures_core::Stream;
+use log::debug;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How many messages can wait for the consumer before reading pauses.
+pub const DEFAULT_STREAM_CAPACITY: usize = 1024;
+
+/// How long reading pauses at a time while the consumer is behind, between keepalives.
+const BACKPRESSURE_WAIT: Duration = Duration::from_secs(1);
+
+/// MessageStream reads a source on a background thread and hands its messages over through a
+/// bounded queue.
+///
+/// When the consumer falls behind and the queue fills up, reading pauses, and a live server
+/// in turn stops sending once the socket buffers are full. Meanwhile the source is kept alive
+/// so the server doesn't time us out. Like `Messages`, a message is confirmed to the source
+/// once the consumer asks for the one after it, not when it is queued.
+///
+/// The messages can be taken by blocking (`recv`, or as an Iterator), by polling (`try_recv`),
+/// or as a futures Stream. Skippable errors are passed over; the first other error is handed
+/// out and ends the stream.
+pub struct MessageStream {
+    shared: Arc<Shared>,
+    reader: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    capacity: usize,
+    state: Mutex<StreamState>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+struct Entry {
+    message: Result<XLogMessage, PgDigError>,
+    /// What to confirm once the consumer is done with the message
+    confirm_lsn: Option<u64>,
+}
+
+#[derive(Default)]
+struct StreamState {
+    queue: VecDeque<Entry>,
+    /// What to confirm when the consumer asks for its next message
+    handed_out: Option<u64>,
+    /// Everything before this has been handled, and can be confirmed to the source
+    handled: u64,
+    finished: bool,
+    cancelled: bool,
+    interrupter: Option<Interrupter>,
+    waker: Option<Waker>,
+}
+
+impl MessageStream {
+    /// Opens a source with `open` on a new thread and starts reading it.
+    ///
+    /// The source is opened on the reader thread because a live connection can't move between
+    /// threads. An error opening it comes out of the stream as its only item.
+    pub fn spawn<F>(open: F, capacity: usize) -> MessageStream
+    where
+        F: FnOnce() -> Result<Box<dyn WalSource>, PgDigError> + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            capacity: capacity.max(1),
+            state: Mutex::new(StreamState::default()),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        });
+
+        let reader_shared = shared.clone();
+        let reader = thread::spawn(move || {
+            match open() {
+                Ok(mut source) => reader_shared.read(source.as_mut()),
+                Err(e) => reader_shared.push(Err(e)),
+            }
+            reader_shared.finish();
+        });
+
+        MessageStream {
+            shared,
+            reader: Some(reader),
+        }
+    }
+
+    /// Waits for the next message. Returns None once the source has ended or the stream has
+    /// been cancelled.
+    pub fn recv(&self) -> Option<Result<XLogMessage, PgDigError>> {
+        let mut state = self.shared.lock();
+
+        loop {
+            if state.cancelled {
+                return None;
+            }
+
+            if let Some(message) = self.shared.take(&mut state) {
+                return Some(message);
+            }
+
+            if state.finished {
+                return None;
+            }
+
+            state = self.shared.not_empty.wait(state).unwrap();
+        }
+    }
+
+    /// Takes the next message if one is waiting, without blocking.
+    pub fn try_recv(&self) -> Option<Result<XLogMessage, PgDigError>> {
+        let mut state = self.shared.lock();
+
+        match state.cancelled {
+            true => None,
+            false => self.shared.take(&mut state),
+        }
+    }
+
+    /// Whether the stream has ended and every message has been taken.
+    pub fn is_finished(&self) -> bool {
+        let state = self.shared.lock();
+        state.cancelled || (state.finished && state.queue.is_empty())
+    }
+
+    /// Stops reading. A source waiting for WAL is woken up, and once the reader thread has
+    /// dropped it, a live connection is closed.
+    pub fn cancel(&self) {
+        let mut state = self.shared.lock();
+        state.cancelled = true;
+
+        if let Some(interrupter) = state.interrupter.take() {
+            interrupter.interrupt();
+        }
+
+        self.shared.wake_all(&mut state);
+    }
+}
+
+impl Iterator for MessageStream {
+    type Item = Result<XLogMessage, PgDigError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+impl Stream for MessageStream {
+    type Item = Result<XLogMessage, PgDigError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.shared.lock();
+
+        if state.cancelled {
+            return Poll::Ready(None);
+        }
+
+        if let Some(message) = self.shared.take(&mut state) {
+            return Poll::Ready(Some(message));
+        }
+
+        if state.finished {
+            return Poll::Ready(None);
+        }
+
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for MessageStream {
+    fn drop(&mut self) {
+        self.cancel();
+
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, StreamState> {
+        self.state.lock().unwrap()
+    }
+
+    /// Hands out the next queued message, marking the previous one as handled.
+    fn take(&self, state: &mut StreamState) -> Option<Result<XLogMessage, PgDigError>> {
+        if let Some(lsn) = state.handed_out.take() {
+            state.handled = state.handled.max(lsn);
+        }
+
+        let entry = state.queue.pop_front()?;
+        state.handed_out = entry.confirm_lsn;
+        self.not_full.notify_one();
+        Some(entry.message)
+    }
+
+    fn push(&self, message: Result<XLogMessage, PgDigError>) {
+        let mut state = self.lock();
+        let confirm_lsn = message.as_ref().ok().map(|message| message.header.end_lsn);
+        state.queue.push_back(Entry { message, confirm_lsn });
+        self.wake_consumer(&mut state);
+    }
+
+    /// Records a skipped record, to be confirmed along with whatever came before it.
+    fn skip(&self, lsn: u64) {
+        let mut guard = self.lock();
+        let state = &mut *guard;
+
+        let pending = match state.queue.back_mut() {
+            Some(entry) => &mut entry.confirm_lsn,
+            None => &mut state.handed_out,
+        };
+
+        match pending {
+            Some(pending) => *pending = (*pending).max(lsn),
+            None => state.handled = state.handled.max(lsn),
+        }
+    }
+
+    fn finish(&self) {
+        let mut state = self.lock();
+        state.finished = true;
+        state.interrupter = None;
+        self.wake_all(&mut state);
+    }
+
+    fn wake_consumer(&self, state: &mut StreamState) {
+        self.not_empty.notify_all();
+
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn wake_all(&self, state: &mut StreamState) {
+        self.not_full.notify_all();
+        self.wake_consumer(state);
+    }
+
+    /// The reader thread: reads `source` until it ends, fails or the stream is cancelled.
+    fn read(&self, source: &mut dyn WalSource) {
+        let mut confirmed = 0;
+
+        {
+            let mut state = self.lock();
+            if state.cancelled {
+                return;
+            }
+            state.interrupter = source.interrupter();
+        }
+
+        loop {
+            let (cancelled, handled, full) = {
+                let state = self.lock();
+                (state.cancelled, state.handled, state.queue.len() >= self.capacity)
+            };
+
+            if cancelled {
+                return;
+            }
+
+            if handled > confirmed {
+                source.confirm(handled);
+                confirmed = handled;
+            }
+
+            if full {
+                let state = self.lock();
+                if state.queue.len() >= self.capacity && !state.cancelled {
+                    drop(self.not_full.wait_timeout(state, BACKPRESSURE_WAIT).unwrap());
+                }
+
+                if let Err(e) = source.keep_alive() {
+                    return self.fail(e);
+                }
+                continue;
+            }
+
+            match source.next_message() {
+                Ok(message) => self.push(Ok(message)),
+                Err(PgDigError::EndOfStream) => return,
+                Err(e) if e.is_skippable() => {
+                    debug!("skipping record: {}", e);
+                    self.skip(source.last_record_end());
+                },
+                Err(e) => return self.fail(e),
+            }
+        }
+    }
+
+    /// Hands out an error that ends the stream, unless it was caused by cancelling.
+    fn fail(&self, e: PgDigError) {
+        if !self.lock().cancelled {
+            self.push(Err(e));
+        }
+    }
+}
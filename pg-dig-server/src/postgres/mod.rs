@@ -1,11 +1,31 @@
+#[cfg(feature = "libpq")]
 pub mod bindings;
 pub mod replication;
 pub mod common;
 pub mod xlog;
 pub mod xlog_message;
+pub mod records;
 pub mod connection;
+#[cfg(feature = "libpq")]
+pub mod pq_connection;
+#[cfg(feature = "native-client")]
+pub mod native;
+pub mod error;
+pub mod feedback;
+pub mod timeline;
+pub mod slot;
+pub mod wal_file;
+pub mod source;
+pub mod capture;
+pub mod message_stream;
+pub mod supervisor;
+pub mod activity;
+pub mod lifecycle;
+pub mod consumption;
 
+#[cfg(feature = "libpq")]
 mod pg_conn;
+#[cfg(feature = "libpq")]
 mod query;
 mod xlog_parser;
 mod platform;
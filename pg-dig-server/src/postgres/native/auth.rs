@@ -0,0 +1,124 @@
+use crate::postgres::error::PgDigError;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use pbkdf2::pbkdf2_hmac;
+use sha2::{Digest, Sha256};
+
+/// The only SASL mechanism we support. SCRAM-SHA-256-PLUS needs channel binding, and so TLS.
+pub const SCRAM_SHA_256: &str = "SCRAM-SHA-256";
+
+/// The response to an MD5 password request: "md5" then md5(md5(password + user) + salt) in hex.
+pub fn md5_password(user: &str, password: &str, salt: &[u8]) -> String {
+    let inner = hex(&Md5::digest([password.as_bytes(), user.as_bytes()].concat()));
+    let outer = hex(&Md5::digest([inner.as_bytes(), salt].concat()));
+    format!("md5{}", outer)
+}
+
+/// ScramSha256 is the client side of a SCRAM-SHA-256 exchange (RFC 5802, RFC 7677), without
+/// channel binding.
+///
+/// The password is used as given, without SASLprep. That only matters for passwords with
+/// non-ASCII characters that SASLprep would change.
+pub struct ScramSha256 {
+    password: String,
+    client_nonce: String,
+    client_first_bare: String,
+    /// The signature the server must send back, once the proof has been sent
+    server_signature: Option<[u8; 32]>,
+}
+
+impl ScramSha256 {
+    /// Starts an exchange with a random nonce.
+    ///
+    /// The user name is left empty, as libpq does: the server takes it from the startup message.
+    pub fn new(password: &str) -> Self {
+        let nonce: [u8; 18] = rand::random();
+        ScramSha256::with_nonce("", password, &STANDARD.encode(nonce))
+    }
+
+    pub fn with_nonce(user: &str, password: &str, nonce: &str) -> Self {
+        let user = user.replace('=', "=3D").replace(',', "=2C");
+
+        ScramSha256 {
+            password: password.to_string(),
+            client_nonce: nonce.to_string(),
+            client_first_bare: format!("n={},r={}", user, nonce),
+            server_signature: None,
+        }
+    }
+
+    /// The client-first-message, which starts the exchange.
+    pub fn client_first(&self) -> String {
+        format!("n,,{}", self.client_first_bare)
+    }
+
+    /// Answers the server-first-message with the client-final-message, which proves we know
+    /// the password.
+    pub fn client_final(&mut self, server_first: &str) -> Result<String, PgDigError> {
+        let nonce = attribute(server_first, 'r')?;
+        let salt = STANDARD.decode(attribute(server_first, 's')?)
+            .map_err(|e| scram_error(format!("invalid salt: {}", e)))?;
+        let iterations = attribute(server_first, 'i')?.parse::<u32>()
+            .map_err(|e| scram_error(format!("invalid iteration count: {}", e)))?;
+
+        if !nonce.starts_with(&self.client_nonce) || nonce.len() == self.client_nonce.len() {
+            return Err(scram_error(String::from("server nonce does not extend ours")));
+        }
+
+        let mut salted_password = [0u8; 32];
+        pbkdf2_hmac::<Sha256>(self.password.as_bytes(), &salt, iterations, &mut salted_password);
+
+        let client_key = hmac(&salted_password, b"Client Key");
+        let stored_key = Sha256::digest(client_key);
+        let client_final_without_proof = format!("c=biws,r={}", nonce);
+        let auth_message = format!("{},{},{}", self.client_first_bare, server_first, client_final_without_proof);
+
+        let client_signature = hmac(&stored_key, auth_message.as_bytes());
+        let proof: Vec<u8> = client_key.iter().zip(client_signature).map(|(key, signature)| key ^ signature).collect();
+
+        let server_key = hmac(&salted_password, b"Server Key");
+        self.server_signature = Some(hmac(&server_key, auth_message.as_bytes()));
+
+        Ok(format!("{},p={}", client_final_without_proof, STANDARD.encode(proof)))
+    }
+
+    /// Checks the server-final-message, which proves the server knows the password too.
+    pub fn verify_server_final(&self, server_final: &str) -> Result<(), PgDigError> {
+        if let Ok(error) = attribute(server_final, 'e') {
+            return Err(scram_error(format!("server rejected authentication: {}", error)));
+        }
+
+        let expected = self.server_signature
+            .ok_or_else(|| scram_error(String::from("server finished before we sent a proof")))?;
+        let signature = STANDARD.decode(attribute(server_final, 'v')?)
+            .map_err(|e| scram_error(format!("invalid server signature: {}", e)))?;
+
+        match signature == expected {
+            true => Ok(()),
+            false => Err(scram_error(String::from("server signature does not match"))),
+        }
+    }
+}
+
+/// Finds the value of attribute `name` in a message such as "r=...,s=...,i=4096".
+fn attribute(message: &str, name: char) -> Result<&str, PgDigError> {
+    message.split(',')
+        .find_map(|attribute| attribute.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')))
+        .ok_or_else(|| scram_error(format!("no {} attribute in {:?}", name, message)))
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn scram_error(reason: String) -> PgDigError {
+    PgDigError::Connection(format!("SCRAM authentication failed: {}", reason))
+}
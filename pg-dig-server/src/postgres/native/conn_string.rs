@@ -0,0 +1,128 @@
+use crate::postgres::error::PgDigError;
+use std::env;
+
+pub const DEFAULT_HOST: &str = "localhost";
+pub const DEFAULT_PORT: u16 = 5432;
+
+/// The settings the native client takes from a libpq style connection string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionParams {
+    /// A host name or address, or a directory holding the server's Unix socket
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: Option<String>,
+    /// Defaults to the user name on the server side
+    pub dbname: Option<String>,
+    /// "true" or "database"; physical replication works with either
+    pub replication: Option<String>,
+    pub application_name: Option<String>,
+}
+
+impl ConnectionParams {
+    /// Parses whitespace separated `key=value` pairs. Values may be single-quoted, with `\'`
+    /// and `\\` escapes inside the quotes.
+    ///
+    /// Settings left out fall back to the PGHOST, PGPORT, PGUSER, PGPASSWORD and PGDATABASE
+    /// environment variables. Unlike libpq, the default host is localhost over TCP rather than
+    /// a Unix socket. There is no TLS, so sslmode and gssencmode settings that require
+    /// encryption are refused.
+    pub fn parse(conn_string: &str) -> Result<ConnectionParams, PgDigError> {
+        if conn_string.starts_with("postgres://") || conn_string.starts_with("postgresql://") {
+            return Err(PgDigError::Connection(String::from("connection URIs are not supported, use key=value pairs")));
+        }
+
+        let mut params = ConnectionParams {
+            host: env::var("PGHOST").unwrap_or_else(|_| DEFAULT_HOST.to_string()),
+            port: DEFAULT_PORT,
+            user: env::var("PGUSER").or_else(|_| env::var("USER")).unwrap_or_else(|_| String::from("postgres")),
+            password: env::var("PGPASSWORD").ok(),
+            dbname: env::var("PGDATABASE").ok(),
+            replication: None,
+            application_name: None,
+        };
+
+        if let Ok(port) = env::var("PGPORT") {
+            params.port = parse_port(&port)?;
+        }
+
+        for (key, value) in parse_pairs(conn_string)? {
+            match key.as_str() {
+                "host" | "hostaddr" => params.host = value,
+                "port" => params.port = parse_port(&value)?,
+                "user" => params.user = value,
+                "password" => params.password = Some(value),
+                "dbname" => params.dbname = Some(value),
+                "replication" => params.replication = Some(value),
+                "application_name" => params.application_name = Some(value),
+                "sslmode" => match value.as_str() {
+                    "disable" | "allow" | "prefer" => {},
+                    _ => return Err(PgDigError::Connection(format!("sslmode={} needs TLS, which the native client does not support", value))),
+                },
+                "gssencmode" => match value.as_str() {
+                    "disable" | "prefer" => {},
+                    _ => return Err(PgDigError::Connection(format!("gssencmode={} is not supported by the native client", value))),
+                },
+                "connect_timeout" | "client_encoding" | "fallback_application_name" => {},
+                _ => return Err(PgDigError::Connection(format!("invalid connection option \"{}\"", key))),
+            }
+        }
+
+        Ok(params)
+    }
+}
+
+fn parse_pairs(conn_string: &str) -> Result<Vec<(String, String)>, PgDigError> {
+    let mut pairs = Vec::new();
+    let mut chars = conn_string.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        if chars.peek().is_none() {
+            return Ok(pairs);
+        }
+
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '=' && !c.is_whitespace()) {
+            key.push(c);
+        }
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.next() != Some('=') {
+            return Err(PgDigError::Connection(format!("missing \"=\" after \"{}\" in connection string", key)));
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut value = String::new();
+
+        if chars.next_if_eq(&'\'').is_some() {
+            loop {
+                match chars.next() {
+                    Some('\'') => break,
+                    Some('\\') => match chars.next() {
+                        Some(c) => value.push(c),
+                        None => return Err(unterminated_quote()),
+                    },
+                    Some(c) => value.push(c),
+                    None => return Err(unterminated_quote()),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+        }
+
+        pairs.push((key, value));
+    }
+}
+
+fn unterminated_quote() -> PgDigError {
+    PgDigError::Connection(String::from("unterminated quoted string in connection string"))
+}
+
+fn parse_port(port: &str) -> Result<u16, PgDigError> {
+    port.parse()
+        .map_err(|_| PgDigError::Connection(format!("invalid port number: \"{}\"", port)))
+}
@@ -0,0 +1,157 @@
+use crate::postgres::error::PgDigError;
+
+pub const PROTOCOL_VERSION_3: i32 = 196_608;
+
+/*
+    Every message after startup is framed the same way in both directions:
+
+        tag         1 byte, the message type
+        length      i32, big-endian, counting itself and the body but not the tag
+        body        length - 4 bytes
+ */
+
+/// A message from the server, without its framing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackendMessage {
+    pub tag: u8,
+    pub body: Vec<u8>,
+}
+
+/// Takes the first message out of `buffer`, if all of it has arrived.
+pub fn parse_message(buffer: &mut Vec<u8>) -> Result<Option<BackendMessage>, PgDigError> {
+    if buffer.len() < 5 {
+        return Ok(None);
+    }
+
+    let length = i32::from_be_bytes(buffer[1..5].try_into().unwrap());
+    if length < 4 {
+        return Err(PgDigError::Protocol(format!("invalid length {} for message type {}", length, buffer[0] as char)));
+    }
+
+    if buffer.len() < length as usize + 1 {
+        return Ok(None);
+    }
+
+    let tag = buffer[0];
+    let body = buffer[5..length as usize + 1].to_vec();
+    buffer.drain(..length as usize + 1);
+
+    Ok(Some(BackendMessage { tag, body }))
+}
+
+/// Frames a message to the server.
+pub fn frontend_message(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(body.len() + 5);
+    message.push(tag);
+    message.extend_from_slice(&(body.len() as i32 + 4).to_be_bytes());
+    message.extend_from_slice(body);
+    message
+}
+
+/// Builds the StartupMessage, which has no tag, from its parameters.
+pub fn startup_message(parameters: &[(&str, &str)]) -> Vec<u8> {
+    let mut body = PROTOCOL_VERSION_3.to_be_bytes().to_vec();
+
+    for (name, value) in parameters {
+        body.extend_from_slice(name.as_bytes());
+        body.push(0);
+        body.extend_from_slice(value.as_bytes());
+        body.push(0);
+    }
+    body.push(0);
+
+    let mut message = (body.len() as i32 + 4).to_be_bytes().to_vec();
+    message.extend_from_slice(&body);
+    message
+}
+
+/// Builds a Query message.
+pub fn query_message(stmt: &str) -> Result<Vec<u8>, PgDigError> {
+    if stmt.contains('\0') {
+        return Err(PgDigError::Protocol(format!("statement contains a nul byte: {}", stmt)));
+    }
+
+    Ok(frontend_message(b'Q', &[stmt.as_bytes(), b"\0"].concat()))
+}
+
+/// Reads the message text out of an ErrorResponse or NoticeResponse, as libpq words it,
+/// e.g. "ERROR:  replication slot \"x\" does not exist".
+pub fn error_message(body: &[u8]) -> String {
+    let mut severity = None;
+    let mut message = None;
+    let mut fields = MessageFields::new(body);
+
+    while let Ok(field) = fields.u8() {
+        if field == 0 {
+            break;
+        }
+
+        let value = match fields.cstr() {
+            Ok(value) => value,
+            Err(_) => break,
+        };
+
+        match field {
+            b'S' => severity = Some(value),
+            b'M' => message = Some(value),
+            _ => {},
+        }
+    }
+
+    match (severity, message) {
+        (Some(severity), Some(message)) => format!("{}:  {}", severity, message),
+        (None, Some(message)) => message,
+        _ => String::from("server sent an error without a message"),
+    }
+}
+
+/// MessageFields reads the fields of a message body in order.
+pub struct MessageFields<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> MessageFields<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        MessageFields { bytes }
+    }
+
+    pub fn u8(&mut self) -> Result<u8, PgDigError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn i16(&mut self) -> Result<i16, PgDigError> {
+        Ok(i16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    pub fn i32(&mut self) -> Result<i32, PgDigError> {
+        Ok(i32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn bytes(&mut self, length: usize) -> Result<&'a [u8], PgDigError> {
+        if self.bytes.len() < length {
+            return Err(PgDigError::Protocol(format!(
+                "message ends after {} bytes, expected {} more",
+                self.bytes.len(),
+                length
+            )));
+        }
+
+        let (bytes, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    /// Reads a nul-terminated string.
+    pub fn cstr(&mut self) -> Result<String, PgDigError> {
+        let end = self.bytes.iter().position(|byte| *byte == 0)
+            .ok_or_else(|| PgDigError::Protocol(String::from("string in message is not nul-terminated")))?;
+        let value = String::from_utf8_lossy(&self.bytes[..end]).into_owned();
+        self.bytes = &self.bytes[end + 1..];
+        Ok(value)
+    }
+
+    /// Everything not read yet.
+    pub fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.bytes)
+    }
+}
@@ -0,0 +1,461 @@
+//! A pure Rust client for the parts of the frontend/backend protocol that replication uses,
+//! so that libpq isn't needed.
+
+pub mod auth;
+pub mod conn_string;
+pub mod message;
+
+use crate::postgres::connection::{CopyData, Interrupter, ReplicationConnection, StreamEnd};
+use crate::postgres::error::PgDigError;
+use crate::postgres::native::auth::{md5_password, ScramSha256, SCRAM_SHA_256};
+use crate::postgres::native::conn_string::ConnectionParams;
+use crate::postgres::native::message::{
+    error_message, frontend_message, parse_message, query_message, startup_message, BackendMessage, MessageFields,
+};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+/* authentication request codes */
+const AUTH_OK: i32 = 0;
+const AUTH_CLEARTEXT_PASSWORD: i32 = 3;
+const AUTH_MD5_PASSWORD: i32 = 5;
+const AUTH_SASL: i32 = 10;
+const AUTH_SASL_CONTINUE: i32 = 11;
+const AUTH_SASL_FINAL: i32 = 12;
+
+/// How much is read from the socket at a time.
+const READ_SIZE: usize = 64 * 1024;
+
+/// The socket under a NativeConnection.
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Stream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.set_read_timeout(timeout),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.set_read_timeout(timeout),
+        }
+    }
+
+    fn try_clone(&self) -> std::io::Result<Stream> {
+        match self {
+            Stream::Tcp(stream) => stream.try_clone().map(Stream::Tcp),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.try_clone().map(Stream::Unix),
+        }
+    }
+
+    fn shutdown(&self) -> std::io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.shutdown(Shutdown::Both),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.shutdown(Shutdown::Both),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+/// What a simple query returned: the rows of its last result set.
+#[derive(Debug, Default)]
+struct QueryResult {
+    columns: Vec<String>,
+    rows: Vec<Vec<Option<String>>>,
+}
+
+impl QueryResult {
+    /// Reads `columns` from the single row, in the same way as `query::read_row`.
+    fn row(&self, what: &str, columns: &[&str]) -> Result<Vec<Option<String>>, PgDigError> {
+        if self.rows.len() != 1 {
+            return Err(PgDigError::Protocol(format!("{} returned {} rows, expected 1", what, self.rows.len())));
+        }
+
+        columns.iter()
+            .map(|column| match self.columns.iter().position(|name| name == column) {
+                Some(index) => Ok(self.rows[0].get(index).cloned().flatten()),
+                None => Err(PgDigError::Protocol(format!("{} returned no {} column", what, column))),
+            })
+            .collect()
+    }
+}
+
+/// How the server answered a query.
+enum Response {
+    /// The query finished and the server is ready for the next one
+    Ready(QueryResult),
+    /// The query started a COPY BOTH stream
+    CopyBoth,
+}
+
+/// NativeConnection is a replication connection that speaks the protocol itself.
+///
+/// It handles trust, password, MD5 and SCRAM-SHA-256 authentication, but not TLS.
+pub struct NativeConnection {
+    stream: Stream,
+    /// What has been read from the socket but not yet taken as messages
+    input: Vec<u8>,
+    /// The server's ParameterStatus settings, e.g. server_version
+    parameters: HashMap<String, String>,
+    /// Whether reading or writing the socket has failed, like libpq's CONNECTION_BAD
+    broken: bool,
+}
+
+impl NativeConnection {
+    pub fn connect(conn_string: &str) -> Result<NativeConnection, PgDigError> {
+        let params = ConnectionParams::parse(conn_string)?;
+        info!("connecting: {}:{} as {}", params.host, params.port, params.user);
+
+        let connection_error = |e: std::io::Error| {
+            PgDigError::Connection(format!("could not connect to {}:{}: {}", params.host, params.port, e))
+        };
+
+        let stream = match params.host.starts_with('/') {
+            #[cfg(unix)]
+            true => Stream::Unix(UnixStream::connect(format!("{}/.s.PGSQL.{}", params.host, params.port)).map_err(connection_error)?),
+            #[cfg(not(unix))]
+            true => return Err(PgDigError::Connection(String::from("Unix sockets are only supported on unix"))),
+            false => {
+                let stream = TcpStream::connect((params.host.as_str(), params.port)).map_err(connection_error)?;
+                stream.set_nodelay(true).map_err(connection_error)?;
+                Stream::Tcp(stream)
+            },
+        };
+
+        let mut conn = NativeConnection {
+            stream,
+            input: Vec::new(),
+            parameters: HashMap::new(),
+            broken: false,
+        };
+        conn.startup(&params)?;
+        Ok(conn)
+    }
+
+    /// A setting the server reported with ParameterStatus, such as server_version.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters.get(name).map(String::as_str)
+    }
+
+    fn startup(&mut self, params: &ConnectionParams) -> Result<(), PgDigError> {
+        let mut parameters = vec![("user", params.user.as_str())];
+        parameters.extend(params.dbname.as_deref().map(|dbname| ("database", dbname)));
+        parameters.extend(params.replication.as_deref().map(|replication| ("replication", replication)));
+        parameters.extend(params.application_name.as_deref().map(|name| ("application_name", name)));
+        self.send(&startup_message(&parameters))?;
+
+        let mut scram = None;
+        let mut server_verified = false;
+
+        loop {
+            let message = self.receive()?;
+
+            match message.tag {
+                b'R' => {
+                    let mut fields = MessageFields::new(&message.body);
+
+                    match fields.i32()? {
+                        AUTH_OK => {
+                            /* without SASLFinal the server never proved that it knows the password */
+                            if scram.is_some() && !server_verified {
+                                return Err(PgDigError::Connection(String::from("server finished SCRAM authentication without sending its signature")));
+                            }
+                        },
+                        AUTH_CLEARTEXT_PASSWORD => {
+                            let password = require_password(params)?;
+                            self.send(&frontend_message(b'p', &[password.as_bytes(), b"\0"].concat()))?;
+                        },
+                        AUTH_MD5_PASSWORD => {
+                            let password = md5_password(&params.user, require_password(params)?, fields.bytes(4)?);
+                            self.send(&frontend_message(b'p', &[password.as_bytes(), b"\0"].concat()))?;
+                        },
+                        AUTH_SASL => {
+                            let mut mechanisms = Vec::new();
+                            while let Ok(mechanism) = fields.cstr() {
+                                if mechanism.is_empty() {
+                                    break;
+                                }
+                                mechanisms.push(mechanism);
+                            }
+
+                            if !mechanisms.iter().any(|mechanism| mechanism == SCRAM_SHA_256) {
+                                return Err(PgDigError::Connection(format!("none of the server's SASL mechanisms are supported: {}", mechanisms.join(", "))));
+                            }
+
+                            let exchange = ScramSha256::new(require_password(params)?);
+                            let client_first = exchange.client_first();
+                            let mut body = [SCRAM_SHA_256.as_bytes(), b"\0"].concat();
+                            body.extend_from_slice(&(client_first.len() as i32).to_be_bytes());
+                            body.extend_from_slice(client_first.as_bytes());
+                            self.send(&frontend_message(b'p', &body))?;
+                            scram = Some(exchange);
+                        },
+                        AUTH_SASL_CONTINUE => {
+                            let exchange = scram.as_mut()
+                                .ok_or_else(|| PgDigError::Protocol(String::from("SASL continue without a SASL exchange")))?;
+                            let client_final = exchange.client_final(&String::from_utf8_lossy(fields.rest()))?;
+                            self.send(&frontend_message(b'p', client_final.as_bytes()))?;
+                        },
+                        AUTH_SASL_FINAL => {
+                            let exchange = scram.as_ref()
+                                .ok_or_else(|| PgDigError::Protocol(String::from("SASL final without a SASL exchange")))?;
+                            exchange.verify_server_final(&String::from_utf8_lossy(fields.rest()))?;
+                            server_verified = true;
+                        },
+                        other => return Err(PgDigError::Connection(format!("authentication method {} is not supported", other))),
+                    }
+                },
+                b'K' => {},
+                b'E' => return Err(PgDigError::Connection(error_message(&message.body))),
+                b'Z' => return Ok(()),
+                other => return Err(unexpected_message(other, "startup")),
+            }
+        }
+    }
+
+    fn send(&mut self, message: &[u8]) -> Result<(), PgDigError> {
+        let result = self.stream.write_all(message).and_then(|_| self.stream.flush());
+        self.broken |= result.is_err();
+        result.map_err(|e| PgDigError::Connection(format!("could not send data to server: {}", e)))
+    }
+
+    /// Reads whatever the server has sent, waiting up to `timeout` for it, or for as long as it
+    /// takes when None. Returns false on timeout.
+    fn fill(&mut self, timeout: Option<Duration>) -> Result<bool, PgDigError> {
+        let receive_error = |e: std::io::Error| PgDigError::Connection(format!("could not receive data from server: {}", e));
+
+        /* a zero timeout means no timeout to the socket */
+        self.stream.set_read_timeout(timeout.map(|timeout| timeout.max(Duration::from_millis(1)))).map_err(receive_error)?;
+
+        let start = self.input.len();
+        self.input.resize(start + READ_SIZE, 0);
+        let result = self.stream.read(&mut self.input[start..]);
+        self.input.truncate(start + *result.as_ref().unwrap_or(&0));
+
+        let filled = match result {
+            Ok(0) => Err(PgDigError::Connection(String::from("server closed the connection unexpectedly"))),
+            Ok(_) => Ok(true),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted) => Ok(false),
+            Err(e) => Err(receive_error(e)),
+        };
+
+        self.broken |= filled.is_err();
+        filled
+    }
+
+    /// Takes the next message that has fully arrived, dealing with the ones the server can send
+    /// at any time along the way.
+    fn try_receive(&mut self) -> Result<Option<BackendMessage>, PgDigError> {
+        while let Some(message) = parse_message(&mut self.input)? {
+            match message.tag {
+                b'N' => warn!("{}", error_message(&message.body)),
+                b'S' => {
+                    let mut fields = MessageFields::new(&message.body);
+                    let name = fields.cstr()?;
+                    let value = fields.cstr()?;
+                    self.parameters.insert(name, value);
+                },
+                _ => return Ok(Some(message)),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Waits for the next message.
+    fn receive(&mut self) -> Result<BackendMessage, PgDigError> {
+        loop {
+            if let Some(message) = self.try_receive()? {
+                return Ok(message);
+            }
+
+            self.fill(None)?;
+        }
+    }
+
+    fn query(&mut self, stmt: &str) -> Result<Response, PgDigError> {
+        info!("exec: {}", stmt);
+        self.send(&query_message(stmt)?)?;
+        self.read_response()
+    }
+
+    /// Reads the results of a query, up to ReadyForQuery or the start of a COPY BOTH.
+    fn read_response(&mut self) -> Result<Response, PgDigError> {
+        let mut result = QueryResult::default();
+        let mut error = None;
+
+        loop {
+            let message = self.receive()?;
+
+            match message.tag {
+                b'T' => {
+                    result.columns = read_row_description(&message.body)?;
+                    result.rows.clear();
+                },
+                b'D' => result.rows.push(read_data_row(&message.body)?),
+                b'C' | b'I' => debug!("command complete: {}", MessageFields::new(&message.body).cstr().unwrap_or_default()),
+                b'W' => return Ok(Response::CopyBoth),
+                b'E' => error = Some(error_message(&message.body)),
+                b'Z' => return match error {
+                    Some(error) => Err(PgDigError::Protocol(error)),
+                    None => Ok(Response::Ready(result)),
+                },
+                other => return Err(unexpected_message(other, "a query")),
+            }
+        }
+    }
+}
+
+impl ReplicationConnection for NativeConnection {
+    fn query_row(&mut self, stmt: &str, columns: &[&str]) -> Result<Vec<Option<String>>, PgDigError> {
+        match self.query(stmt)? {
+            Response::Ready(result) => result.row(stmt, columns),
+            Response::CopyBoth => Err(PgDigError::Protocol(format!("{} started a COPY", stmt))),
+        }
+    }
+
+    fn exec_command(&mut self, stmt: &str) -> Result<(), PgDigError> {
+        match self.query(stmt)? {
+            Response::Ready(_) => Ok(()),
+            Response::CopyBoth => Err(PgDigError::Protocol(format!("{} started a COPY", stmt))),
+        }
+    }
+
+    fn start_copy_both(&mut self, stmt: &str) -> Result<(), PgDigError> {
+        match self.query(stmt)? {
+            Response::CopyBoth => Ok(()),
+            Response::Ready(_) => Err(PgDigError::Protocol(format!("{} did not start a COPY", stmt))),
+        }
+    }
+
+    fn get_copy_data(&mut self) -> Result<CopyData, PgDigError> {
+        let message = match self.try_receive()? {
+            Some(message) => message,
+            None => return Ok(CopyData::Pending),
+        };
+
+        match message.tag {
+            b'd' => Ok(CopyData::Message(message.body)),
+            b'c' => Ok(CopyData::Done),
+            b'E' => Err(PgDigError::Protocol(error_message(&message.body))),
+            other => Err(unexpected_message(other, "COPY")),
+        }
+    }
+
+    fn wait_readable(&mut self, timeout: Duration) -> Result<bool, PgDigError> {
+        self.fill(Some(timeout))
+    }
+
+    fn put_copy_data(&mut self, bytes: &[u8]) -> Result<(), PgDigError> {
+        self.send(&frontend_message(b'd', bytes))
+    }
+
+    fn end_copy(&mut self) -> Result<StreamEnd, PgDigError> {
+        self.send(&frontend_message(b'c', &[]))?;
+
+        match self.read_response()? {
+            Response::Ready(result) if result.columns.is_empty() => Ok(StreamEnd::Finished),
+            Response::Ready(result) => StreamEnd::timeline_switch(&result.row("end of streaming", &["next_tli", "next_tli_startpos"])?),
+            Response::CopyBoth => Err(PgDigError::Protocol(String::from("server started another COPY after ending one"))),
+        }
+    }
+
+    fn interrupter(&self) -> Result<Interrupter, PgDigError> {
+        let stream = self.stream.try_clone()
+            .map_err(|e| PgDigError::Connection(format!("could not duplicate socket: {}", e)))?;
+
+        Ok(Interrupter::new(move || {
+            let _ = stream.shutdown();
+        }))
+    }
+
+    fn status(&self) -> String {
+        String::from(match self.broken {
+            true => "connection bad",
+            false => "connection ok",
+        })
+    }
+}
+
+impl Drop for NativeConnection {
+    fn drop(&mut self) {
+        /* Terminate; the server may already be gone */
+        let _ = self.send(&frontend_message(b'X', &[]));
+    }
+}
+
+fn require_password(params: &ConnectionParams) -> Result<&str, PgDigError> {
+    params.password.as_deref()
+        .ok_or_else(|| PgDigError::Connection(String::from("the server requires a password, but none was given")))
+}
+
+/// Reads the column names from a RowDescription.
+fn read_row_description(body: &[u8]) -> Result<Vec<String>, PgDigError> {
+    let mut fields = MessageFields::new(body);
+    let count = fields.i16()?;
+    let mut columns = Vec::with_capacity(count.max(0) as usize);
+
+    for _ in 0..count {
+        columns.push(fields.cstr()?);
+        /* table OID, column number, type OID, type size, type modifier, format */
+        fields.bytes(18)?;
+    }
+
+    Ok(columns)
+}
+
+/// Reads the values from a DataRow. NULL values come back as None.
+fn read_data_row(body: &[u8]) -> Result<Vec<Option<String>>, PgDigError> {
+    let mut fields = MessageFields::new(body);
+    let count = fields.i16()?;
+    let mut values = Vec::with_capacity(count.max(0) as usize);
+
+    for _ in 0..count {
+        values.push(match fields.i32()? {
+            -1 => None,
+            length if length < 0 => return Err(PgDigError::Protocol(format!("invalid value length {}", length))),
+            length => Some(String::from_utf8_lossy(fields.bytes(length as usize)?).into_owned()),
+        });
+    }
+
+    Ok(values)
+}
+
+fn unexpected_message(tag: u8, during: &str) -> PgDigError {
+    PgDigError::Protocol(format!("unexpected message type {} during {}", tag as char, during))
+}
@@ -16,29 +16,27 @@ use crate::postgres::bindings::{
     ExecStatusType_PGRES_FATAL_ERROR, ExecStatusType_PGRES_NONFATAL_ERROR,
     ExecStatusType_PGRES_PIPELINE_ABORTED, ExecStatusType_PGRES_PIPELINE_SYNC,
     ExecStatusType_PGRES_SINGLE_TUPLE, ExecStatusType_PGRES_TUPLES_CHUNK,
-    ExecStatusType_PGRES_TUPLES_OK, PGconn, PQgetResult, PQresultErrorMessage, PQresultStatus,
+    ExecStatusType_PGRES_TUPLES_OK, PGconn, PGresult, PQerrorMessage,
+    PQresultErrorMessage, PQresultStatus,
 };
 use std::ffi::CStr;
 
-pub unsafe fn print_status(conn: *mut PGconn) {
-    let result = PQgetResult(conn);
-    let raw_error_message = CStr::from_ptr(PQresultErrorMessage(result))
-        .to_str()
-        .expect("failed to parse error message");
-
-    let result_status = PQresultStatus(result);
+/// Returns the most recent error reported on the connection.
+pub unsafe fn conn_error_message(conn: *mut PGconn) -> String {
+    CStr::from_ptr(PQerrorMessage(conn)).to_string_lossy().trim_end().to_string()
+}
 
-    print!("result: {}", friendly_exec_status(result_status));
+/// Returns the error attached to a command result, or its status if there is none.
+pub unsafe fn result_error_message(result: *mut PGresult) -> String {
+    let error_message = CStr::from_ptr(PQresultErrorMessage(result)).to_string_lossy();
 
-    if !raw_error_message.is_empty() {
-        println!(", error: {}", raw_error_message)
+    match error_message.trim_end() {
+        "" => friendly_exec_status(PQresultStatus(result)),
+        message => message.to_string(),
     }
-
-    println!();
-    println!();
 }
 
-pub unsafe fn friendly_exec_status(exec_status_type: ExecStatusType) -> String {
+pub fn friendly_exec_status(exec_status_type: ExecStatusType) -> String {
     String::from(match exec_status_type {
         ExecStatusType_PGRES_EMPTY_QUERY => "PGRES_EMPTY_QUERY",
         ExecStatusType_PGRES_COMMAND_OK => "PGRES_COMMAND_OK",
@@ -53,11 +51,11 @@ pub unsafe fn friendly_exec_status(exec_status_type: ExecStatusType) -> String {
         ExecStatusType_PGRES_PIPELINE_SYNC => "PGRES_PIPELINE_SYNC",
         ExecStatusType_PGRES_PIPELINE_ABORTED => "PGRES_PIPELINE_ABORTED",
         ExecStatusType_PGRES_TUPLES_CHUNK => "PGRES_TUPLES_CHUNK",
-        other => panic!("invalid exec status: {}", other),
+        other => return format!("unknown exec status: {}", other),
     })
 }
 
-pub unsafe fn friendly_conn_status(conn_status_type: ConnStatusType) -> String {
+pub fn friendly_conn_status(conn_status_type: ConnStatusType) -> String {
     String::from(match conn_status_type {
         ConnStatusType_CONNECTION_OK => "connection ok",
         ConnStatusType_CONNECTION_BAD => "connection bad",
@@ -74,6 +72,6 @@ pub unsafe fn friendly_conn_status(conn_status_type: ConnStatusType) -> String {
         ConnStatusType_CONNECTION_CHECK_TARGET => "connection check target",
         ConnStatusType_CONNECTION_CHECK_STANDBY => "connection check standby",
         ConnStatusType_CONNECTION_ALLOCATED => "connection allocated",
-        other => panic!("invalid connection status: {}", other),
+        other => return format!("unknown connection status: {}", other),
     })
 }
@@ -0,0 +1,184 @@
+use crate::postgres::bindings::{
+    ConnStatusType_CONNECTION_OK, ExecStatusType_PGRES_COMMAND_OK, ExecStatusType_PGRES_COPY_BOTH,
+    ExecStatusType_PGRES_COPY_IN, ExecStatusType_PGRES_TUPLES_OK, PGconn, PQclear, PQconnectdb, PQconsumeInput,
+    PQfinish, PQflush, PQfreemem, PQgetCopyData, PQgetResult, PQputCopyData, PQputCopyEnd, PQresultStatus, PQsocket,
+    PQstatus,
+};
+use crate::postgres::connection::{CopyData, Interrupter, ReplicationConnection, StreamEnd};
+use crate::postgres::error::PgDigError;
+use crate::postgres::pg_conn::{conn_error_message, friendly_conn_status, result_error_message};
+use crate::postgres::query::{exec, exec_command, query_row, read_row};
+use std::ffi::{c_char, c_int, c_void, CString};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::time::Duration;
+use std::{ptr, slice};
+
+/// PqConnection is a replication connection made through libpq.
+pub struct PqConnection {
+    conn: *mut PGconn,
+}
+
+impl PqConnection {
+    pub fn connect(conn_string: &str) -> Result<PqConnection, PgDigError> {
+        let conn_string = CString::new(conn_string)
+            .map_err(|_| PgDigError::Connection(String::from("connection string contains a nul byte")))?;
+
+        unsafe {
+            let conn = PQconnectdb(conn_string.as_ptr());
+
+            if PQstatus(conn) != ConnStatusType_CONNECTION_OK {
+                let error_message = conn_error_message(conn);
+                PQfinish(conn);
+                return Err(PgDigError::Connection(error_message));
+            }
+
+            Ok(PqConnection { conn })
+        }
+    }
+
+    /// The libpq connection, for calling libpq directly. It stays owned by this PqConnection.
+    pub fn as_ptr(&self) -> *mut PGconn {
+        self.conn
+    }
+}
+
+impl ReplicationConnection for PqConnection {
+    fn query_row(&mut self, stmt: &str, columns: &[&str]) -> Result<Vec<Option<String>>, PgDigError> {
+        unsafe { query_row(self.conn, stmt, columns) }
+    }
+
+    fn exec_command(&mut self, stmt: &str) -> Result<(), PgDigError> {
+        unsafe { exec_command(self.conn, stmt) }
+    }
+
+    fn start_copy_both(&mut self, stmt: &str) -> Result<(), PgDigError> {
+        unsafe {
+            let result = exec(self.conn, stmt)?;
+            let status = PQresultStatus(result);
+            let error = result_error_message(result);
+            PQclear(result);
+
+            match status == ExecStatusType_PGRES_COPY_BOTH {
+                true => Ok(()),
+                false => Err(PgDigError::Protocol(error)),
+            }
+        }
+    }
+
+    fn get_copy_data(&mut self) -> Result<CopyData, PgDigError> {
+        unsafe {
+            if PQconsumeInput(self.conn) == 0 {
+                return Err(PgDigError::Connection(conn_error_message(self.conn)));
+            }
+
+            let mut buffer_ptr: *mut c_char = ptr::null_mut();
+
+            let length = match PQgetCopyData(self.conn, &mut buffer_ptr, 1) {
+                length if length > 0 => length as usize,
+                0 => return Ok(CopyData::Pending),
+                -1 => return Ok(CopyData::Done),
+                -2 => return Err(PgDigError::Connection(conn_error_message(self.conn))),
+                unknown_code => return Err(PgDigError::Protocol(format!("unknown code from PQgetCopyData: {}", unknown_code))),
+            };
+
+            // Copy the message out of libpq's buffer so that decoding never reads past its end
+            let buffer = slice::from_raw_parts(buffer_ptr as *const u8, length).to_vec();
+            PQfreemem(buffer_ptr as *mut c_void);
+            Ok(CopyData::Message(buffer))
+        }
+    }
+
+    fn wait_readable(&mut self, timeout: Duration) -> Result<bool, PgDigError> {
+        let mut poll_fd = libc::pollfd {
+            fd: unsafe { PQsocket(self.conn) },
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = timeout.as_millis().min(c_int::MAX as u128) as c_int;
+
+        match unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) } {
+            -1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => Ok(false),
+            -1 => Err(PgDigError::Connection(format!("could not wait for the server: {}", io::Error::last_os_error()))),
+            0 => Ok(false),
+            _ => Ok(true),
+        }
+    }
+
+    fn put_copy_data(&mut self, bytes: &[u8]) -> Result<(), PgDigError> {
+        unsafe {
+            if PQputCopyData(self.conn, bytes.as_ptr() as *const c_char, bytes.len() as c_int) != 1 {
+                return Err(PgDigError::Connection(conn_error_message(self.conn)));
+            }
+
+            if PQflush(self.conn) == -1 {
+                return Err(PgDigError::Connection(conn_error_message(self.conn)));
+            }
+
+            Ok(())
+        }
+    }
+
+    fn end_copy(&mut self) -> Result<StreamEnd, PgDigError> {
+        unsafe {
+            let mut result = PQgetResult(self.conn);
+
+            if PQresultStatus(result) == ExecStatusType_PGRES_COPY_IN {
+                // the server has stopped sending, end our half of the COPY too
+                PQclear(result);
+
+                if PQputCopyEnd(self.conn, ptr::null()) != 1 || PQflush(self.conn) == -1 {
+                    return Err(PgDigError::Connection(conn_error_message(self.conn)));
+                }
+
+                result = PQgetResult(self.conn);
+            }
+
+            let status = PQresultStatus(result);
+            let end = if status == ExecStatusType_PGRES_TUPLES_OK {
+                let row = read_row(result, "end of streaming", &["next_tli", "next_tli_startpos"])?;
+                StreamEnd::timeline_switch(&row)?
+            } else if status == ExecStatusType_PGRES_COMMAND_OK {
+                PQclear(result);
+                StreamEnd::Finished
+            } else {
+                let error = PgDigError::Protocol(result_error_message(result));
+                PQclear(result);
+                return Err(error);
+            };
+
+            // drain the CommandComplete that follows
+            loop {
+                let result = PQgetResult(self.conn);
+                if result.is_null() {
+                    break;
+                }
+                PQclear(result);
+            }
+
+            Ok(end)
+        }
+    }
+
+    fn interrupter(&self) -> Result<Interrupter, PgDigError> {
+        // a duplicate of libpq's socket, so it can be shut down even after libpq has closed its own
+        let socket = match unsafe { libc::dup(PQsocket(self.conn)) } {
+            -1 => return Err(PgDigError::Connection(format!("could not duplicate socket: {}", io::Error::last_os_error()))),
+            fd => unsafe { OwnedFd::from_raw_fd(fd) },
+        };
+
+        Ok(Interrupter::new(move || unsafe {
+            libc::shutdown(socket.as_raw_fd(), libc::SHUT_RDWR);
+        }))
+    }
+
+    fn status(&self) -> String {
+        friendly_conn_status(unsafe { PQstatus(self.conn) })
+    }
+}
+
+impl Drop for PqConnection {
+    fn drop(&mut self) {
+        unsafe { PQfinish(self.conn) }
+    }
+}
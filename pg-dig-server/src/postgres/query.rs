@@ -1,11 +1,71 @@
-use std::ffi::CString;
-use crate::postgres::bindings::{PGconn, PGresult, PQexec};
-use crate::postgres::pg_conn::print_status;
-
-pub unsafe fn exec(conn: *mut PGconn, stmt: &str) -> *mut PGresult {
-    println!("exec: {}", stmt);
-    let statement = CString::new(stmt).expect("failed to build statement");
-    let result = PQexec(conn, statement.as_ptr());
-    print_status(conn);
-    result
+use std::ffi::{CStr, CString};
+use crate::postgres::bindings::{
+    ExecStatusType_PGRES_COMMAND_OK, ExecStatusType_PGRES_TUPLES_OK, PGconn, PGresult, PQclear, PQexec, PQfnumber, PQgetisnull,
+    PQgetvalue, PQntuples, PQresultStatus,
+};
+use crate::postgres::error::PgDigError;
+use crate::postgres::pg_conn::result_error_message;
+
+pub unsafe fn exec(conn: *mut PGconn, stmt: &str) -> Result<*mut PGresult, PgDigError> {
+    let statement = CString::new(stmt)
+        .map_err(|_| PgDigError::Protocol(format!("statement contains a nul byte: {}", stmt)))?;
+    Ok(PQexec(conn, statement.as_ptr()))
+}
+
+/// Runs a command that returns no rows, such as DROP_REPLICATION_SLOT.
+pub unsafe fn exec_command(conn: *mut PGconn, stmt: &str) -> Result<(), PgDigError> {
+    let result = exec(conn, stmt)?;
+    let status = PQresultStatus(result);
+    let error = result_error_message(result);
+    PQclear(result);
+
+    match status == ExecStatusType_PGRES_COMMAND_OK {
+        true => Ok(()),
+        false => Err(PgDigError::Protocol(error)),
+    }
+}
+
+/// Runs a command that returns a single row, such as IDENTIFY_SYSTEM, and reads `columns` from it.
+///
+/// NULL values come back as None.
+pub unsafe fn query_row(conn: *mut PGconn, stmt: &str, columns: &[&str]) -> Result<Vec<Option<String>>, PgDigError> {
+    let result = exec(conn, stmt)?;
+    read_row(result, stmt, columns)
+}
+
+/// Reads `columns` from a result holding a single row, then frees the result.
+///
+/// `what` names the command in error messages.
+pub unsafe fn read_row(result: *mut PGresult, what: &str, columns: &[&str]) -> Result<Vec<Option<String>>, PgDigError> {
+    if PQresultStatus(result) != ExecStatusType_PGRES_TUPLES_OK {
+        let error = PgDigError::Protocol(result_error_message(result));
+        PQclear(result);
+        return Err(error);
+    }
+
+    if PQntuples(result) != 1 {
+        let error = PgDigError::Protocol(format!("{} returned {} rows, expected 1", what, PQntuples(result)));
+        PQclear(result);
+        return Err(error);
+    }
+
+    let mut values = Vec::with_capacity(columns.len());
+
+    for column in columns {
+        let name = CString::new(*column).expect("column names are static");
+        let index = PQfnumber(result, name.as_ptr());
+
+        if index < 0 {
+            PQclear(result);
+            return Err(PgDigError::Protocol(format!("{} returned no {} column", what, column)));
+        }
+
+        values.push(match PQgetisnull(result, 0, index) {
+            0 => Some(CStr::from_ptr(PQgetvalue(result, 0, index)).to_string_lossy().into_owned()),
+            _ => None,
+        });
+    }
+
+    PQclear(result);
+    Ok(values)
 }
\ No newline at end of file
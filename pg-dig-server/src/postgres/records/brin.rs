@@ -0,0 +1,105 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::records::{BlockNumber, OffsetNumber};
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+
+/* XLOG info values for the BRIN rmgr, from brin_xlog.h */
+pub const XLOG_BRIN_CREATE_INDEX: u8 = 0x00;
+pub const XLOG_BRIN_INSERT: u8 = 0x10;
+pub const XLOG_BRIN_UPDATE: u8 = 0x20;
+pub const XLOG_BRIN_SAMEPAGE_UPDATE: u8 = 0x30;
+pub const XLOG_BRIN_REVMAP_EXTEND: u8 = 0x40;
+pub const XLOG_BRIN_DESUMMARIZE: u8 = 0x50;
+
+pub const XLOG_BRIN_OPMASK: u8 = 0x70;
+/* the target page was initialized, so the record replaces its contents */
+pub const XLOG_BRIN_INIT_PAGE: u8 = 0x80;
+
+/// Names a BRIN record type the way pg_waldump does, e.g. "INSERT+INIT".
+pub fn identify(info: u8) -> &'static str {
+    let init = info & XLOG_BRIN_INIT_PAGE != 0;
+
+    match (info & XLOG_BRIN_OPMASK, init) {
+        (XLOG_BRIN_CREATE_INDEX, _) => "CREATE_INDEX",
+        (XLOG_BRIN_INSERT, false) => "INSERT",
+        (XLOG_BRIN_INSERT, true) => "INSERT+INIT",
+        (XLOG_BRIN_UPDATE, false) => "UPDATE",
+        (XLOG_BRIN_UPDATE, true) => "UPDATE+INIT",
+        (XLOG_BRIN_SAMEPAGE_UPDATE, _) => "SAMEPAGE_UPDATE",
+        (XLOG_BRIN_REVMAP_EXTEND, _) => "REVMAP_EXTEND",
+        (XLOG_BRIN_DESUMMARIZE, _) => "DESUMMARIZE",
+        _ => "UNKNOWN",
+    }
+}
+
+/// xl_brin_insert: the summary of the block range starting at `heap_block` went in at `offnum` of
+/// the regular page in block 0, and the revmap page in block 1 was pointed at it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrinInsert {
+    pub heap_block: BlockNumber,
+    pub pages_per_range: BlockNumber,
+    pub offnum: OffsetNumber,
+    pub init_page: bool,
+}
+
+/// A decoded BRIN record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BrinRecord {
+    /// xl_brin_createidx: the metapage was written for a new index
+    CreateIndex { pages_per_range: BlockNumber, version: u16 },
+    Insert(BrinInsert),
+    /// xl_brin_update: a summary grew out of its page at `old_offnum` of block 2 and was moved as
+    /// `insert` describes
+    Update { old_offnum: OffsetNumber, insert: BrinInsert },
+    /// xl_brin_samepage_update: the summary at `offnum` of block 0 was replaced in place
+    SamePageUpdate { offnum: OffsetNumber },
+    /// xl_brin_revmap_extend: the revmap grew into `target_block`, block 1, with the metapage in
+    /// block 0
+    RevmapExtend { target_block: BlockNumber },
+    /// xl_brin_desummarize: the summary of the range starting at `heap_block` was removed from the
+    /// regular page in block 1 and the revmap in block 0
+    Desummarize { pages_per_range: BlockNumber, heap_block: BlockNumber, offnum: OffsetNumber },
+}
+
+impl BrinRecord {
+    /// Decodes the main data of a BRIN record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<BrinRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+        let init_page = info & XLOG_BRIN_INIT_PAGE != 0;
+
+        Ok(match info & XLOG_BRIN_OPMASK {
+            XLOG_BRIN_CREATE_INDEX => BrinRecord::CreateIndex {
+                pages_per_range: reader.read_u32("xl_brin_createidx.pagesPerRange")?,
+                version: reader.read_u16("xl_brin_createidx.version")?,
+            },
+            XLOG_BRIN_INSERT => BrinRecord::Insert(read_insert(&mut reader, init_page)?),
+            XLOG_BRIN_UPDATE => {
+                let old_offnum = reader.read_u16("xl_brin_update.oldOffnum")?;
+                reader.skip(2, "xl_brin_update padding")?;
+
+                BrinRecord::Update { old_offnum, insert: read_insert(&mut reader, init_page)? }
+            },
+            XLOG_BRIN_SAMEPAGE_UPDATE => BrinRecord::SamePageUpdate {
+                offnum: reader.read_u16("xl_brin_samepage_update.offnum")?,
+            },
+            XLOG_BRIN_REVMAP_EXTEND => BrinRecord::RevmapExtend {
+                target_block: reader.read_u32("xl_brin_revmap_extend.targetBlk")?,
+            },
+            XLOG_BRIN_DESUMMARIZE => BrinRecord::Desummarize {
+                pages_per_range: reader.read_u32("xl_brin_desummarize.pagesPerRange")?,
+                heap_block: reader.read_u32("xl_brin_desummarize.heapBlk")?,
+                offnum: reader.read_u16("xl_brin_desummarize.regOffset")?,
+            },
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::BRIN, info }),
+        })
+    }
+}
+
+fn read_insert(reader: &mut ByteReader, init_page: bool) -> Result<BrinInsert, DecodeError> {
+    Ok(BrinInsert {
+        heap_block: reader.read_u32("xl_brin_insert.heapBlk")?,
+        pages_per_range: reader.read_u32("xl_brin_insert.pagesPerRange")?,
+        offnum: reader.read_u16("xl_brin_insert.offnum")?,
+        init_page,
+    })
+}
@@ -0,0 +1,221 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::common::transaction_id::{FullTransactionId, TransactionId};
+use crate::postgres::common::RelFileLocator;
+use crate::postgres::records::{BlockNumber, OffsetNumber};
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+
+/* XLOG info values for the Btree rmgr, from nbtxlog.h */
+pub const XLOG_BTREE_INSERT_LEAF: u8 = 0x00;
+pub const XLOG_BTREE_INSERT_UPPER: u8 = 0x10;
+pub const XLOG_BTREE_INSERT_META: u8 = 0x20;
+pub const XLOG_BTREE_SPLIT_L: u8 = 0x30;
+pub const XLOG_BTREE_SPLIT_R: u8 = 0x40;
+pub const XLOG_BTREE_INSERT_POST: u8 = 0x50;
+pub const XLOG_BTREE_DEDUP: u8 = 0x60;
+pub const XLOG_BTREE_DELETE: u8 = 0x70;
+pub const XLOG_BTREE_UNLINK_PAGE: u8 = 0x80;
+pub const XLOG_BTREE_UNLINK_PAGE_META: u8 = 0x90;
+pub const XLOG_BTREE_NEWROOT: u8 = 0xA0;
+pub const XLOG_BTREE_MARK_PAGE_HALFDEAD: u8 = 0xB0;
+pub const XLOG_BTREE_VACUUM: u8 = 0xC0;
+pub const XLOG_BTREE_REUSE_PAGE: u8 = 0xD0;
+pub const XLOG_BTREE_META_CLEANUP: u8 = 0xE0;
+
+/// Names a Btree record type the way pg_waldump does, e.g. "SPLIT_L".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        XLOG_BTREE_INSERT_LEAF => "INSERT_LEAF",
+        XLOG_BTREE_INSERT_UPPER => "INSERT_UPPER",
+        XLOG_BTREE_INSERT_META => "INSERT_META",
+        XLOG_BTREE_SPLIT_L => "SPLIT_L",
+        XLOG_BTREE_SPLIT_R => "SPLIT_R",
+        XLOG_BTREE_INSERT_POST => "INSERT_POST",
+        XLOG_BTREE_DEDUP => "DEDUP",
+        XLOG_BTREE_DELETE => "DELETE",
+        XLOG_BTREE_UNLINK_PAGE => "UNLINK_PAGE",
+        XLOG_BTREE_UNLINK_PAGE_META => "UNLINK_PAGE_META",
+        XLOG_BTREE_NEWROOT => "NEWROOT",
+        XLOG_BTREE_MARK_PAGE_HALFDEAD => "MARK_PAGE_HALFDEAD",
+        XLOG_BTREE_VACUUM => "VACUUM",
+        XLOG_BTREE_REUSE_PAGE => "REUSE_PAGE",
+        XLOG_BTREE_META_CLEANUP => "META_CLEANUP",
+        _ => "UNKNOWN",
+    }
+}
+
+/// xl_btree_split: block 0 was split, moving the items from `first_right_offnum` on to the new
+/// right page in block 1.
+///
+/// Block 2 is the old right sibling, when there is one, and block 3 the child whose split is
+/// completed by inserting the downlink.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BtreeSplit {
+    /// Tree level of the page being split, 0 for leaves
+    pub level: u32,
+    pub first_right_offnum: OffsetNumber,
+    pub new_item_offnum: OffsetNumber,
+    /// Offset into the posting list the new item split, 0 when it did not land in one
+    pub posting_offset: u16,
+}
+
+/// xl_btree_delete: index tuples on block 0 were deleted by a simple or bottom-up deletion pass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BtreeDelete {
+    pub snapshot_conflict_horizon: TransactionId,
+    pub ndeleted: u16,
+    /// Posting list tuples that only lost some of their heap TIDs
+    pub nupdated: u16,
+    pub is_catalog_rel: bool,
+}
+
+/// xl_btree_mark_page_halfdead: the first phase of deleting the empty leaf page in block 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BtreeMarkPageHalfdead {
+    /// The downlink being removed from the parent in block 1
+    pub parent_offnum: OffsetNumber,
+    pub leaf_block: BlockNumber,
+    pub left_block: BlockNumber,
+    pub right_block: BlockNumber,
+    pub top_parent: BlockNumber,
+}
+
+/// xl_btree_unlink_page: block 2 was unlinked from its siblings in blocks 0 and 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BtreeUnlinkPage {
+    pub left_sibling: BlockNumber,
+    pub right_sibling: BlockNumber,
+    pub level: u32,
+    /// The page can be recycled once no snapshot could still see this transaction
+    pub safe_xid: FullTransactionId,
+    pub leaf_left_sibling: BlockNumber,
+    pub leaf_right_sibling: BlockNumber,
+    pub leaf_top_parent: BlockNumber,
+}
+
+/// xl_btree_reuse_page: a deleted page is about to be recycled; only used for recovery conflicts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BtreeReusePage {
+    pub locator: RelFileLocator,
+    pub block: BlockNumber,
+    pub snapshot_conflict_horizon: FullTransactionId,
+    pub is_catalog_rel: bool,
+}
+
+/// A decoded Btree record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BtreeRecord {
+    /// xl_btree_insert: a tuple was inserted at `offnum` of the leaf page in block 0
+    InsertLeaf { offnum: OffsetNumber },
+    /// A downlink was inserted at `offnum` of an internal page, finishing the split of block 1
+    InsertUpper { offnum: OffsetNumber },
+    /// As InsertUpper, and the metapage in block 2 was updated too
+    InsertMeta { offnum: OffsetNumber },
+    /// A tuple was inserted at `offnum` of a leaf by splitting a posting list
+    InsertPost { offnum: OffsetNumber },
+    /// The new item went to the left half
+    SplitLeft(BtreeSplit),
+    /// The new item went to the right half
+    SplitRight(BtreeSplit),
+    /// xl_btree_dedup: duplicates on block 0 were merged into `nintervals` posting lists
+    Dedup { nintervals: u16 },
+    Delete(BtreeDelete),
+    /// xl_btree_vacuum: VACUUM removed or updated index tuples on block 0
+    Vacuum { ndeleted: u16, nupdated: u16 },
+    MarkPageHalfdead(BtreeMarkPageHalfdead),
+    UnlinkPage(BtreeUnlinkPage),
+    /// As UnlinkPage, and the metapage in block 4 was updated too
+    UnlinkPageMeta(BtreeUnlinkPage),
+    /// xl_btree_newroot: the tree grew, `root_block` is the new root at `level`
+    NewRoot { root_block: BlockNumber, level: u32 },
+    ReusePage(BtreeReusePage),
+    /// The metapage in block 0 was updated after VACUUM
+    MetaCleanup,
+}
+
+impl BtreeRecord {
+    /// Decodes the main data of a Btree record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<BtreeRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            XLOG_BTREE_INSERT_LEAF => BtreeRecord::InsertLeaf { offnum: reader.read_u16("xl_btree_insert.offnum")? },
+            XLOG_BTREE_INSERT_UPPER => BtreeRecord::InsertUpper { offnum: reader.read_u16("xl_btree_insert.offnum")? },
+            XLOG_BTREE_INSERT_META => BtreeRecord::InsertMeta { offnum: reader.read_u16("xl_btree_insert.offnum")? },
+            XLOG_BTREE_INSERT_POST => BtreeRecord::InsertPost { offnum: reader.read_u16("xl_btree_insert.offnum")? },
+            XLOG_BTREE_SPLIT_L => BtreeRecord::SplitLeft(read_split(&mut reader)?),
+            XLOG_BTREE_SPLIT_R => BtreeRecord::SplitRight(read_split(&mut reader)?),
+            XLOG_BTREE_DEDUP => BtreeRecord::Dedup {
+                nintervals: reader.read_u16("xl_btree_dedup.nintervals")?,
+            },
+            XLOG_BTREE_DELETE => BtreeRecord::Delete(BtreeDelete {
+                snapshot_conflict_horizon: reader.read::<TransactionId>("xl_btree_delete.snapshotConflictHorizon")?,
+                ndeleted: reader.read_u16("xl_btree_delete.ndeleted")?,
+                nupdated: reader.read_u16("xl_btree_delete.nupdated")?,
+                is_catalog_rel: reader.read_u8("xl_btree_delete.isCatalogRel")? != 0,
+            }),
+            XLOG_BTREE_VACUUM => BtreeRecord::Vacuum {
+                ndeleted: reader.read_u16("xl_btree_vacuum.ndeleted")?,
+                nupdated: reader.read_u16("xl_btree_vacuum.nupdated")?,
+            },
+            XLOG_BTREE_MARK_PAGE_HALFDEAD => {
+                let parent_offnum = reader.read_u16("xl_btree_mark_page_halfdead.poffset")?;
+                reader.skip(2, "xl_btree_mark_page_halfdead padding")?;
+
+                BtreeRecord::MarkPageHalfdead(BtreeMarkPageHalfdead {
+                    parent_offnum,
+                    leaf_block: reader.read_u32("xl_btree_mark_page_halfdead.leafblk")?,
+                    left_block: reader.read_u32("xl_btree_mark_page_halfdead.leftblk")?,
+                    right_block: reader.read_u32("xl_btree_mark_page_halfdead.rightblk")?,
+                    top_parent: reader.read_u32("xl_btree_mark_page_halfdead.topparent")?,
+                })
+            },
+            XLOG_BTREE_UNLINK_PAGE => BtreeRecord::UnlinkPage(read_unlink_page(&mut reader)?),
+            XLOG_BTREE_UNLINK_PAGE_META => BtreeRecord::UnlinkPageMeta(read_unlink_page(&mut reader)?),
+            XLOG_BTREE_NEWROOT => BtreeRecord::NewRoot {
+                root_block: reader.read_u32("xl_btree_newroot.rootblk")?,
+                level: reader.read_u32("xl_btree_newroot.level")?,
+            },
+            XLOG_BTREE_REUSE_PAGE => {
+                let locator = reader.read::<RelFileLocator>("xl_btree_reuse_page.locator")?;
+                let block = reader.read_u32("xl_btree_reuse_page.block")?;
+
+                BtreeRecord::ReusePage(BtreeReusePage {
+                    locator,
+                    block,
+                    snapshot_conflict_horizon: reader.read::<FullTransactionId>("xl_btree_reuse_page.snapshotConflictHorizon")?,
+                    is_catalog_rel: reader.read_u8("xl_btree_reuse_page.isCatalogRel")? != 0,
+                })
+            },
+            XLOG_BTREE_META_CLEANUP => BtreeRecord::MetaCleanup,
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::Btree, info }),
+        })
+    }
+}
+
+fn read_split(reader: &mut ByteReader) -> Result<BtreeSplit, DecodeError> {
+    Ok(BtreeSplit {
+        level: reader.read_u32("xl_btree_split.level")?,
+        first_right_offnum: reader.read_u16("xl_btree_split.firstrightoff")?,
+        new_item_offnum: reader.read_u16("xl_btree_split.newitemoff")?,
+        posting_offset: reader.read_u16("xl_btree_split.postingoff")?,
+    })
+}
+
+fn read_unlink_page(reader: &mut ByteReader) -> Result<BtreeUnlinkPage, DecodeError> {
+    let left_sibling = reader.read_u32("xl_btree_unlink_page.leftsib")?;
+    let right_sibling = reader.read_u32("xl_btree_unlink_page.rightsib")?;
+    let level = reader.read_u32("xl_btree_unlink_page.level")?;
+    /* the FullTransactionId is 8-byte aligned */
+    reader.skip(4, "xl_btree_unlink_page padding")?;
+
+    Ok(BtreeUnlinkPage {
+        left_sibling,
+        right_sibling,
+        level,
+        safe_xid: reader.read::<FullTransactionId>("xl_btree_unlink_page.safexid")?,
+        leaf_left_sibling: reader.read_u32("xl_btree_unlink_page.leafleftsib")?,
+        leaf_right_sibling: reader.read_u32("xl_btree_unlink_page.leafrightsib")?,
+        leaf_top_parent: reader.read_u32("xl_btree_unlink_page.leaftopparent")?,
+    })
+}
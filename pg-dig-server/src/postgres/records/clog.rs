@@ -0,0 +1,48 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::common::transaction_id::TransactionId;
+use crate::postgres::records::Oid;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+
+/* XLOG info values for the CLOG rmgr, from clog.h */
+pub const CLOG_ZEROPAGE: u8 = 0x00;
+pub const CLOG_TRUNCATE: u8 = 0x10;
+
+/* two status bits per transaction on a BLCKSZ page */
+pub const CLOG_XACTS_PER_PAGE: u32 = 8192 * 4;
+
+/// Names a CLOG record type the way pg_waldump does, e.g. "ZEROPAGE".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        CLOG_ZEROPAGE => "ZEROPAGE",
+        CLOG_TRUNCATE => "TRUNCATE",
+        _ => "UNKNOWN",
+    }
+}
+
+/// A decoded CLOG record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClogRecord {
+    /// A page of pg_xact was zeroed for the next CLOG_XACTS_PER_PAGE transactions
+    ZeroPage { page: i32 },
+    /// xl_clog_truncate: pages before `page` were removed, as no table holds xids older than
+    /// `oldest_xact` any more
+    Truncate { page: i32, oldest_xact: TransactionId, oldest_xact_db: Oid },
+}
+
+impl ClogRecord {
+    /// Decodes the main data of a CLOG record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<ClogRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            CLOG_ZEROPAGE => ClogRecord::ZeroPage { page: reader.read::<i32>("pageno")? },
+            CLOG_TRUNCATE => ClogRecord::Truncate {
+                page: reader.read::<i32>("xl_clog_truncate.pageno")?,
+                oldest_xact: reader.read::<TransactionId>("xl_clog_truncate.oldestXact")?,
+                oldest_xact_db: reader.read_u32("xl_clog_truncate.oldestXactDb")?,
+            },
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::CLOG, info }),
+        })
+    }
+}
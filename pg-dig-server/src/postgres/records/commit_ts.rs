@@ -0,0 +1,43 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::common::transaction_id::TransactionId;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+
+/* XLOG info values for the CommitTs rmgr, from commit_ts.h */
+pub const COMMIT_TS_ZEROPAGE: u8 = 0x00;
+pub const COMMIT_TS_TRUNCATE: u8 = 0x10;
+
+/// Names a CommitTs record type the way pg_waldump does, e.g. "TRUNCATE".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        COMMIT_TS_ZEROPAGE => "ZEROPAGE",
+        COMMIT_TS_TRUNCATE => "TRUNCATE",
+        _ => "UNKNOWN",
+    }
+}
+
+/// A decoded CommitTs record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommitTsRecord {
+    /// A page of pg_commit_ts was zeroed
+    ZeroPage { page: i32 },
+    /// xl_commit_ts_truncate: pages before `page` were removed, keeping timestamps from
+    /// `oldest_xid` on
+    Truncate { page: i32, oldest_xid: TransactionId },
+}
+
+impl CommitTsRecord {
+    /// Decodes the main data of a CommitTs record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<CommitTsRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            COMMIT_TS_ZEROPAGE => CommitTsRecord::ZeroPage { page: reader.read::<i32>("pageno")? },
+            COMMIT_TS_TRUNCATE => CommitTsRecord::Truncate {
+                page: reader.read::<i32>("xl_commit_ts_truncate.pageno")?,
+                oldest_xid: reader.read::<TransactionId>("xl_commit_ts_truncate.oldestXid")?,
+            },
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::CommitTs, info }),
+        })
+    }
+}
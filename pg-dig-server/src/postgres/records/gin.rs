@@ -0,0 +1,215 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::common::transaction_id::TransactionId;
+use crate::postgres::common::RelFileLocator;
+use crate::postgres::records::{BlockNumber, OffsetNumber};
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+use bitflags::bitflags;
+
+/* XLOG info values for the Gin rmgr, from ginxlog.h */
+pub const XLOG_GIN_CREATE_PTREE: u8 = 0x10;
+pub const XLOG_GIN_INSERT: u8 = 0x20;
+pub const XLOG_GIN_SPLIT: u8 = 0x30;
+pub const XLOG_GIN_VACUUM_PAGE: u8 = 0x50;
+pub const XLOG_GIN_DELETE_PAGE: u8 = 0x70;
+pub const XLOG_GIN_UPDATE_META_PAGE: u8 = 0x80;
+pub const XLOG_GIN_VACUUM_DATA_LEAF_PAGE: u8 = 0x90;
+pub const XLOG_GIN_INSERT_LISTPAGE: u8 = 0xA0;
+pub const XLOG_GIN_DELETE_LISTPAGE: u8 = 0xB0;
+
+/// Names a Gin record type the way pg_waldump does, e.g. "UPDATE_META_PAGE".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        XLOG_GIN_CREATE_PTREE => "CREATE_PTREE",
+        XLOG_GIN_INSERT => "INSERT",
+        XLOG_GIN_SPLIT => "SPLIT",
+        XLOG_GIN_VACUUM_PAGE => "VACUUM_PAGE",
+        XLOG_GIN_DELETE_PAGE => "DELETE_PAGE",
+        XLOG_GIN_UPDATE_META_PAGE => "UPDATE_META_PAGE",
+        XLOG_GIN_VACUUM_DATA_LEAF_PAGE => "VACUUM_DATA_LEAF_PAGE",
+        XLOG_GIN_INSERT_LISTPAGE => "INSERT_LISTPAGE",
+        XLOG_GIN_DELETE_LISTPAGE => "DELETE_LISTPAGE",
+        _ => "UNKNOWN",
+    }
+}
+
+bitflags! {
+    /* ginxlogInsert and ginxlogSplit flags */
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct GinInsertFlags: u16 {
+        /* a posting tree page rather than an entry tree page */
+        const GIN_INSERT_ISDATA = 0x01;
+        const GIN_INSERT_ISLEAF = 0x02;
+        const GIN_SPLIT_ROOT    = 0x04;
+    }
+}
+
+/// GinMetaPageData: the metapage, block 0 of every GIN index, as it was left by the record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GinMetaPage {
+    /// First and last page of the pending list, where fastupdate inserts go before cleanup
+    pub head: BlockNumber,
+    pub tail: BlockNumber,
+    pub tail_free_size: u32,
+    pub n_pending_pages: BlockNumber,
+    pub n_pending_heap_tuples: i64,
+    pub n_total_pages: BlockNumber,
+    pub n_entry_pages: BlockNumber,
+    pub n_data_pages: BlockNumber,
+    pub n_entries: i64,
+    pub gin_version: i32,
+}
+
+/// ginxlogSplit: the page in block 0 was split, with block 1 the new right page.
+///
+/// When the root was split, block 2 is the new root and blocks 0 and 1 are both new pages. Block 3
+/// is the child whose split the downlink insertion completes, on non-leaf pages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GinSplit {
+    pub locator: RelFileLocator,
+    /// The right link of the new right page
+    pub right_link: BlockNumber,
+    pub left_child: BlockNumber,
+    pub right_child: BlockNumber,
+    pub flags: GinInsertFlags,
+}
+
+/// ginxlogUpdateMeta: the metapage in block 0 was updated, usually because `ntuples` index tuples
+/// were appended to the pending-list tail page in block 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GinUpdateMeta {
+    pub locator: RelFileLocator,
+    pub metadata: GinMetaPage,
+    /// The old tail, when a new list page was linked after it
+    pub prev_tail: BlockNumber,
+    pub new_right_link: BlockNumber,
+    pub ntuples: i32,
+}
+
+/// A decoded Gin record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GinRecord {
+    /// ginxlogCreatePostingTree: a posting tree was started with `size` bytes of posting lists
+    CreatePostingTree { size: u32 },
+    /// ginxlogInsert: an entry or item went into the page in block 0
+    ///
+    /// On non-leaf pages it is the downlink finishing the split of `children`, held in block 1.
+    Insert { flags: GinInsertFlags, children: Option<(BlockNumber, BlockNumber)> },
+    Split(GinSplit),
+    /// The page in block 0 was rewritten by VACUUM, as a full-page image
+    VacuumPage,
+    /// ginxlogDeletePage: the empty posting tree page in block 0 was unlinked from its left sibling
+    /// in block 2 and its parent in block 1
+    DeletePage { parent_offnum: OffsetNumber, right_link: BlockNumber, delete_xid: TransactionId },
+    /// A fast-update insert into the pending list
+    UpdateMetaPage(GinUpdateMeta),
+    /// VACUUM removed items from the posting tree leaf in block 0
+    VacuumDataLeafPage,
+    /// ginxlogInsertListPage: the pending-list page in block 0 was filled with `ntuples` tuples
+    InsertListPage { right_link: BlockNumber, ntuples: i32 },
+    /// ginxlogDeleteListPages: pending-list cleanup moved the first `ndeleted` list pages into the
+    /// main index and removed them
+    DeleteListPages { metadata: GinMetaPage, ndeleted: i32 },
+}
+
+impl GinRecord {
+    /// Decodes the main data of a Gin record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<GinRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            XLOG_GIN_CREATE_PTREE => GinRecord::CreatePostingTree { size: reader.read_u32("ginxlogCreatePostingTree.size")? },
+            XLOG_GIN_INSERT => {
+                let flags = GinInsertFlags::from_bits_retain(reader.read_u16("ginxlogInsert.flags")?);
+                let children = match flags.contains(GinInsertFlags::GIN_INSERT_ISLEAF) {
+                    true => None,
+                    false => Some((
+                        read_block_id(&mut reader, "ginxlogInsert.leftChildBlkno")?,
+                        read_block_id(&mut reader, "ginxlogInsert.rightChildBlkno")?,
+                    )),
+                };
+
+                GinRecord::Insert { flags, children }
+            },
+            XLOG_GIN_SPLIT => GinRecord::Split(GinSplit {
+                locator: reader.read::<RelFileLocator>("ginxlogSplit.locator")?,
+                right_link: reader.read_u32("ginxlogSplit.rrlink")?,
+                left_child: reader.read_u32("ginxlogSplit.leftChildBlkno")?,
+                right_child: reader.read_u32("ginxlogSplit.rightChildBlkno")?,
+                flags: GinInsertFlags::from_bits_retain(reader.read_u16("ginxlogSplit.flags")?),
+            }),
+            XLOG_GIN_VACUUM_PAGE => GinRecord::VacuumPage,
+            XLOG_GIN_DELETE_PAGE => {
+                let parent_offnum = reader.read_u16("ginxlogDeletePage.parentOffset")?;
+                reader.skip(2, "ginxlogDeletePage padding")?;
+
+                GinRecord::DeletePage {
+                    parent_offnum,
+                    right_link: reader.read_u32("ginxlogDeletePage.rightLink")?,
+                    delete_xid: reader.read::<TransactionId>("ginxlogDeletePage.deleteXid")?,
+                }
+            },
+            XLOG_GIN_UPDATE_META_PAGE => {
+                let locator = reader.read::<RelFileLocator>("ginxlogUpdateMeta.locator")?;
+                /* the metapage contents are 8-byte aligned */
+                reader.skip(4, "ginxlogUpdateMeta padding")?;
+
+                GinRecord::UpdateMetaPage(GinUpdateMeta {
+                    locator,
+                    metadata: read_meta_page(&mut reader)?,
+                    prev_tail: reader.read_u32("ginxlogUpdateMeta.prevTail")?,
+                    new_right_link: reader.read_u32("ginxlogUpdateMeta.newRightlink")?,
+                    ntuples: reader.read::<i32>("ginxlogUpdateMeta.ntuples")?,
+                })
+            },
+            XLOG_GIN_VACUUM_DATA_LEAF_PAGE => GinRecord::VacuumDataLeafPage,
+            XLOG_GIN_INSERT_LISTPAGE => GinRecord::InsertListPage {
+                right_link: reader.read_u32("ginxlogInsertListPage.rightlink")?,
+                ntuples: reader.read::<i32>("ginxlogInsertListPage.ntuples")?,
+            },
+            XLOG_GIN_DELETE_LISTPAGE => GinRecord::DeleteListPages {
+                metadata: read_meta_page(&mut reader)?,
+                ndeleted: reader.read::<i32>("ginxlogDeleteListPages.ndeleted")?,
+            },
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::Gin, info }),
+        })
+    }
+}
+
+/* BlockIdData: a block number stored as two 16-bit halves */
+fn read_block_id(reader: &mut ByteReader, field: &'static str) -> Result<BlockNumber, DecodeError> {
+    let hi = reader.read_u16(field)? as u32;
+    let lo = reader.read_u16(field)? as u32;
+
+    Ok(hi << 16 | lo)
+}
+
+fn read_meta_page(reader: &mut ByteReader) -> Result<GinMetaPage, DecodeError> {
+    let head = reader.read_u32("GinMetaPageData.head")?;
+    let tail = reader.read_u32("GinMetaPageData.tail")?;
+    let tail_free_size = reader.read_u32("GinMetaPageData.tailFreeSize")?;
+    let n_pending_pages = reader.read_u32("GinMetaPageData.nPendingPages")?;
+    let n_pending_heap_tuples = reader.read::<i64>("GinMetaPageData.nPendingHeapTuples")?;
+    let n_total_pages = reader.read_u32("GinMetaPageData.nTotalPages")?;
+    let n_entry_pages = reader.read_u32("GinMetaPageData.nEntryPages")?;
+    let n_data_pages = reader.read_u32("GinMetaPageData.nDataPages")?;
+    /* nEntries is 8-byte aligned */
+    reader.skip(4, "GinMetaPageData padding")?;
+    let n_entries = reader.read::<i64>("GinMetaPageData.nEntries")?;
+    let gin_version = reader.read::<i32>("GinMetaPageData.ginVersion")?;
+    /* the struct is padded out to a multiple of 8 */
+    reader.skip(4, "GinMetaPageData padding")?;
+
+    Ok(GinMetaPage {
+        head,
+        tail,
+        tail_free_size,
+        n_pending_pages,
+        n_pending_heap_tuples,
+        n_total_pages,
+        n_entry_pages,
+        n_data_pages,
+        n_entries,
+        gin_version,
+    })
+}
@@ -0,0 +1,143 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::common::transaction_id::{FullTransactionId, TransactionId};
+use crate::postgres::common::RelFileLocator;
+use crate::postgres::records::{BlockNumber, OffsetNumber};
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+use crate::postgres::xlog_parser::DecodedBlock;
+
+/* XLOG info values for the Gist rmgr, from gistxlog.h */
+pub const XLOG_GIST_PAGE_UPDATE: u8 = 0x00;
+pub const XLOG_GIST_DELETE: u8 = 0x10;
+pub const XLOG_GIST_PAGE_REUSE: u8 = 0x20;
+pub const XLOG_GIST_PAGE_SPLIT: u8 = 0x30;
+pub const XLOG_GIST_PAGE_DELETE: u8 = 0x60;
+pub const XLOG_GIST_ASSIGN_LSN: u8 = 0x70;
+
+/// Names a Gist record type the way pg_waldump does, e.g. "PAGE_SPLIT".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        XLOG_GIST_PAGE_UPDATE => "PAGE_UPDATE",
+        XLOG_GIST_DELETE => "DELETE",
+        XLOG_GIST_PAGE_REUSE => "PAGE_REUSE",
+        XLOG_GIST_PAGE_SPLIT => "PAGE_SPLIT",
+        XLOG_GIST_PAGE_DELETE => "PAGE_DELETE",
+        XLOG_GIST_ASSIGN_LSN => "ASSIGN_LSN",
+        _ => "UNKNOWN",
+    }
+}
+
+/// gistxlogDelete: dead index tuples on block 0 were removed by a scan that found them killed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GistDelete {
+    pub snapshot_conflict_horizon: TransactionId,
+    pub ndeleted: u16,
+    pub is_catalog_rel: bool,
+}
+
+/// gistxlogPageReuse: a deleted page is about to be recycled; only used for recovery conflicts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GistPageReuse {
+    pub locator: RelFileLocator,
+    pub block: BlockNumber,
+    pub snapshot_conflict_horizon: FullTransactionId,
+    pub is_catalog_rel: bool,
+}
+
+/// One of the pages a GiST page was split into, with the number of tuples it holds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GistSplitPage {
+    pub block: BlockNumber,
+    pub ntuples: i32,
+}
+
+/// gistxlogPageSplit: a page was split into `pages`, the first of which takes over its block.
+///
+/// Block 0, when present, is the child whose incomplete split the new downlinks finish.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GistPageSplit {
+    /// The original page's right link, now the last new page's
+    pub orig_right_link: BlockNumber,
+    pub orig_nsn: u64,
+    pub orig_leaf: bool,
+    /// The new pages' downlinks are not in the parent yet
+    pub mark_follow_right: bool,
+    pub pages: Vec<GistSplitPage>,
+}
+
+/// A decoded Gist record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GistRecord {
+    /// gistxlogPageUpdate: tuples were replaced on the page in block 0; block 1, when present, is
+    /// the child whose split this finishes
+    PageUpdate { ndeleted: u16, ninserted: u16 },
+    Delete(GistDelete),
+    PageReuse(GistPageReuse),
+    PageSplit(GistPageSplit),
+    /// gistxlogPageDelete: the empty leaf in block 0 was deleted and its downlink at
+    /// `downlink_offnum` removed from the parent in block 1
+    PageDelete { delete_xid: FullTransactionId, downlink_offnum: OffsetNumber },
+    /// An empty record written only to advance the LSN of an unlogged page
+    AssignLsn,
+}
+
+impl GistRecord {
+    /// Decodes the main data of a Gist record with rmgr info bits `info`, and the page sizes of a
+    /// split from its block data.
+    pub(crate) fn decode(info: u8, main_data: &[u8], blocks: &[DecodedBlock]) -> Result<GistRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            XLOG_GIST_PAGE_UPDATE => GistRecord::PageUpdate {
+                ndeleted: reader.read_u16("gistxlogPageUpdate.ntodelete")?,
+                ninserted: reader.read_u16("gistxlogPageUpdate.ntoinsert")?,
+            },
+            XLOG_GIST_DELETE => GistRecord::Delete(GistDelete {
+                snapshot_conflict_horizon: reader.read::<TransactionId>("gistxlogDelete.snapshotConflictHorizon")?,
+                ndeleted: reader.read_u16("gistxlogDelete.ntodelete")?,
+                is_catalog_rel: reader.read_u8("gistxlogDelete.isCatalogRel")? != 0,
+            }),
+            XLOG_GIST_PAGE_REUSE => {
+                let locator = reader.read::<RelFileLocator>("gistxlogPageReuse.locator")?;
+                let block = reader.read_u32("gistxlogPageReuse.block")?;
+
+                GistRecord::PageReuse(GistPageReuse {
+                    locator,
+                    block,
+                    snapshot_conflict_horizon: reader.read::<FullTransactionId>("gistxlogPageReuse.snapshotConflictHorizon")?,
+                    is_catalog_rel: reader.read_u8("gistxlogPageReuse.isCatalogRel")? != 0,
+                })
+            },
+            XLOG_GIST_PAGE_SPLIT => {
+                let orig_right_link = reader.read_u32("gistxlogPageSplit.origrlink")?;
+                /* the NSN is 8-byte aligned */
+                reader.skip(4, "gistxlogPageSplit padding")?;
+                let orig_nsn = reader.read_u64("gistxlogPageSplit.orignsn")?;
+                let orig_leaf = reader.read_u8("gistxlogPageSplit.origleaf")? != 0;
+                reader.skip(1, "gistxlogPageSplit padding")?;
+                let npage = reader.read_u16("gistxlogPageSplit.npage")?;
+                let mark_follow_right = reader.read_u8("gistxlogPageSplit.markfollowright")? != 0;
+
+                /* the new pages are block ids 1 to npage, each starting with its tuple count */
+                let pages = blocks
+                    .iter()
+                    .filter(|block| (1..=npage).contains(&(block.header.id as u16)))
+                    .map(|block| {
+                        Ok(GistSplitPage {
+                            block: block.header.block_number,
+                            ntuples: ByteReader::new(block.data).read::<i32>("gistxlogPage.num")?,
+                        })
+                    })
+                    .collect::<Result<_, DecodeError>>()?;
+
+                GistRecord::PageSplit(GistPageSplit { orig_right_link, orig_nsn, orig_leaf, mark_follow_right, pages })
+            },
+            XLOG_GIST_PAGE_DELETE => GistRecord::PageDelete {
+                delete_xid: reader.read::<FullTransactionId>("gistxlogPageDelete.deleteXid")?,
+                downlink_offnum: reader.read_u16("gistxlogPageDelete.downlinkOffset")?,
+            },
+            XLOG_GIST_ASSIGN_LSN => GistRecord::AssignLsn,
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::Gist, info }),
+        })
+    }
+}
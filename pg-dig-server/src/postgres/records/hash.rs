@@ -0,0 +1,181 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::common::transaction_id::TransactionId;
+use crate::postgres::records::{BlockNumber, OffsetNumber, Oid};
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+use bitflags::bitflags;
+
+/* XLOG info values for the Hash rmgr, from hash_xlog.h */
+pub const XLOG_HASH_INIT_META_PAGE: u8 = 0x00;
+pub const XLOG_HASH_INIT_BITMAP_PAGE: u8 = 0x10;
+pub const XLOG_HASH_INSERT: u8 = 0x20;
+pub const XLOG_HASH_ADD_OVFL_PAGE: u8 = 0x30;
+pub const XLOG_HASH_SPLIT_ALLOCATE_PAGE: u8 = 0x40;
+pub const XLOG_HASH_SPLIT_PAGE: u8 = 0x50;
+pub const XLOG_HASH_SPLIT_COMPLETE: u8 = 0x60;
+pub const XLOG_HASH_MOVE_PAGE_CONTENTS: u8 = 0x70;
+pub const XLOG_HASH_SQUEEZE_PAGE: u8 = 0x80;
+pub const XLOG_HASH_DELETE: u8 = 0x90;
+pub const XLOG_HASH_SPLIT_CLEANUP: u8 = 0xA0;
+pub const XLOG_HASH_UPDATE_META_PAGE: u8 = 0xB0;
+pub const XLOG_HASH_VACUUM_ONE_PAGE: u8 = 0xC0;
+
+/// Names a Hash record type the way pg_waldump does, e.g. "SQUEEZE_PAGE".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        XLOG_HASH_INIT_META_PAGE => "INIT_META_PAGE",
+        XLOG_HASH_INIT_BITMAP_PAGE => "INIT_BITMAP_PAGE",
+        XLOG_HASH_INSERT => "INSERT",
+        XLOG_HASH_ADD_OVFL_PAGE => "ADD_OVFL_PAGE",
+        XLOG_HASH_SPLIT_ALLOCATE_PAGE => "SPLIT_ALLOCATE_PAGE",
+        XLOG_HASH_SPLIT_PAGE => "SPLIT_PAGE",
+        XLOG_HASH_SPLIT_COMPLETE => "SPLIT_COMPLETE",
+        XLOG_HASH_MOVE_PAGE_CONTENTS => "MOVE_PAGE_CONTENTS",
+        XLOG_HASH_SQUEEZE_PAGE => "SQUEEZE_PAGE",
+        XLOG_HASH_DELETE => "DELETE",
+        XLOG_HASH_SPLIT_CLEANUP => "SPLIT_CLEANUP",
+        XLOG_HASH_UPDATE_META_PAGE => "UPDATE_META_PAGE",
+        XLOG_HASH_VACUUM_ONE_PAGE => "VACUUM_ONE_PAGE",
+        _ => "UNKNOWN",
+    }
+}
+
+bitflags! {
+    /* xl_hash_split_allocate_page flags: which metapage fields the split changed */
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct HashSplitMetaFlags: u8 {
+        const XLH_SPLIT_META_UPDATE_MASKS      = 0x01;
+        const XLH_SPLIT_META_UPDATE_SPLITPOINT = 0x02;
+    }
+}
+
+/// xl_hash_init_meta_page: the metapage in block 0 of a new or truncated index was initialized.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashInitMetaPage {
+    /// The estimated number of tuples the index was sized for
+    pub num_tuples: f64,
+    pub proc_id: Oid,
+    pub fill_factor: u16,
+}
+
+/// xl_hash_split_allocate_page: bucket `new_bucket` was allocated in block 1 to split the old
+/// bucket in block 0, and the metapage in block 2 updated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashSplitAllocatePage {
+    pub new_bucket: u32,
+    /// LH_BUCKET_* flags of the old and new primary bucket pages
+    pub old_bucket_flag: u16,
+    pub new_bucket_flag: u16,
+    pub flags: HashSplitMetaFlags,
+}
+
+/// xl_hash_squeeze_page: `ntuples` tuples were moved off the overflow page in block 2 into the
+/// write page in block 1, and the emptied page was freed.
+///
+/// Block 0 is the primary bucket page, blocks 3 and 4 the freed page's neighbours, block 5 the
+/// bitmap page and block 6 the metapage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashSqueezePage {
+    pub prev_block: BlockNumber,
+    pub next_block: BlockNumber,
+    pub ntuples: u16,
+    pub is_primary_bucket_same_write: bool,
+    pub is_prev_bucket_same_write: bool,
+}
+
+/// xl_hash_vacuum_one_page: dead tuples on the bucket page in block 0 were removed to make room
+/// for an insert, with the metapage in block 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashVacuumOnePage {
+    pub snapshot_conflict_horizon: TransactionId,
+    pub ndeleted: u16,
+    pub is_catalog_rel: bool,
+}
+
+/// A decoded Hash record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HashRecord {
+    InitMetaPage(HashInitMetaPage),
+    /// xl_hash_init_bitmap_page: a bitmap page of `bitmap_size` bytes was created in block 0
+    InitBitmapPage { bitmap_size: u16 },
+    /// xl_hash_insert: a tuple went in at `offnum` of the bucket page in block 0, with the metapage
+    /// tuple count in block 1
+    Insert { offnum: OffsetNumber },
+    /// xl_hash_add_ovfl_page: an overflow page was added in block 0 after the bucket's old tail in
+    /// block 1; blocks 2 to 4 are the bitmap pages and metapage
+    AddOverflowPage { bitmap_size: u16, bitmap_page_found: bool },
+    SplitAllocatePage(HashSplitAllocatePage),
+    /// A page of the new bucket filled during a split
+    SplitPage,
+    /// xl_hash_split_complete: the split of the bucket in block 0 into block 1 finished
+    SplitComplete { old_bucket_flag: u16, new_bucket_flag: u16 },
+    /// xl_hash_move_page_contents: `ntuples` tuples moved from block 2 to block 1 while squeezing
+    MovePageContents { ntuples: u16, is_primary_bucket_same_write: bool },
+    SqueezePage(HashSqueezePage),
+    /// xl_hash_delete: VACUUM removed tuples from the bucket page in block 1
+    Delete { clear_dead_marking: bool, is_primary_bucket_page: bool },
+    /// Tuples moved out by a split were removed from the old bucket in block 0
+    SplitCleanup,
+    /// xl_hash_update_meta_page: VACUUM updated the tuple count in the metapage
+    UpdateMetaPage { ntuples: f64 },
+    VacuumOnePage(HashVacuumOnePage),
+}
+
+impl HashRecord {
+    /// Decodes the main data of a Hash record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<HashRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            XLOG_HASH_INIT_META_PAGE => HashRecord::InitMetaPage(HashInitMetaPage {
+                num_tuples: reader.read::<f64>("xl_hash_init_meta_page.num_tuples")?,
+                proc_id: reader.read_u32("xl_hash_init_meta_page.procid")?,
+                fill_factor: reader.read_u16("xl_hash_init_meta_page.ffactor")?,
+            }),
+            XLOG_HASH_INIT_BITMAP_PAGE => HashRecord::InitBitmapPage {
+                bitmap_size: reader.read_u16("xl_hash_init_bitmap_page.bmsize")?,
+            },
+            XLOG_HASH_INSERT => HashRecord::Insert { offnum: reader.read_u16("xl_hash_insert.offnum")? },
+            XLOG_HASH_ADD_OVFL_PAGE => HashRecord::AddOverflowPage {
+                bitmap_size: reader.read_u16("xl_hash_add_ovfl_page.bmsize")?,
+                bitmap_page_found: reader.read_u8("xl_hash_add_ovfl_page.bmpage_found")? != 0,
+            },
+            XLOG_HASH_SPLIT_ALLOCATE_PAGE => HashRecord::SplitAllocatePage(HashSplitAllocatePage {
+                new_bucket: reader.read_u32("xl_hash_split_allocate_page.new_bucket")?,
+                old_bucket_flag: reader.read_u16("xl_hash_split_allocate_page.old_bucket_flag")?,
+                new_bucket_flag: reader.read_u16("xl_hash_split_allocate_page.new_bucket_flag")?,
+                flags: HashSplitMetaFlags::from_bits_retain(reader.read_u8("xl_hash_split_allocate_page.flags")?),
+            }),
+            XLOG_HASH_SPLIT_PAGE => HashRecord::SplitPage,
+            XLOG_HASH_SPLIT_COMPLETE => HashRecord::SplitComplete {
+                old_bucket_flag: reader.read_u16("xl_hash_split_complete.old_bucket_flag")?,
+                new_bucket_flag: reader.read_u16("xl_hash_split_complete.new_bucket_flag")?,
+            },
+            XLOG_HASH_MOVE_PAGE_CONTENTS => HashRecord::MovePageContents {
+                ntuples: reader.read_u16("xl_hash_move_page_contents.ntups")?,
+                is_primary_bucket_same_write: reader.read_u8("xl_hash_move_page_contents.is_prim_bucket_same_wrt")? != 0,
+            },
+            XLOG_HASH_SQUEEZE_PAGE => HashRecord::SqueezePage(HashSqueezePage {
+                prev_block: reader.read_u32("xl_hash_squeeze_page.prevblkno")?,
+                next_block: reader.read_u32("xl_hash_squeeze_page.nextblkno")?,
+                ntuples: reader.read_u16("xl_hash_squeeze_page.ntups")?,
+                is_primary_bucket_same_write: reader.read_u8("xl_hash_squeeze_page.is_prim_bucket_same_wrt")? != 0,
+                is_prev_bucket_same_write: reader.read_u8("xl_hash_squeeze_page.is_prev_bucket_same_wrt")? != 0,
+            }),
+            XLOG_HASH_DELETE => HashRecord::Delete {
+                clear_dead_marking: reader.read_u8("xl_hash_delete.clear_dead_marking")? != 0,
+                is_primary_bucket_page: reader.read_u8("xl_hash_delete.is_primary_bucket_page")? != 0,
+            },
+            XLOG_HASH_SPLIT_CLEANUP => HashRecord::SplitCleanup,
+            XLOG_HASH_UPDATE_META_PAGE => HashRecord::UpdateMetaPage {
+                ntuples: reader.read::<f64>("xl_hash_update_meta_page.ntuples")?,
+            },
+            XLOG_HASH_VACUUM_ONE_PAGE => HashRecord::VacuumOnePage(HashVacuumOnePage {
+                snapshot_conflict_horizon: reader.read::<TransactionId>("xl_hash_vacuum_one_page.snapshotConflictHorizon")?,
+                ndeleted: reader.read_u16("xl_hash_vacuum_one_page.ntuples")?,
+                is_catalog_rel: reader.read_u8("xl_hash_vacuum_one_page.isCatalogRel")? != 0,
+            }),
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::Hash, info }),
+        })
+    }
+}
@@ -0,0 +1,222 @@
+use crate::postgres::common::transaction_id::TransactionId;
+use crate::postgres::records::{OffsetNumber, Oid};
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+use bitflags::bitflags;
+
+/* XLOG info values for the Heap rmgr, from heapam_xlog.h */
+pub const XLOG_HEAP_INSERT: u8 = 0x00;
+pub const XLOG_HEAP_DELETE: u8 = 0x10;
+pub const XLOG_HEAP_UPDATE: u8 = 0x20;
+pub const XLOG_HEAP_TRUNCATE: u8 = 0x30;
+pub const XLOG_HEAP_HOT_UPDATE: u8 = 0x40;
+pub const XLOG_HEAP_CONFIRM: u8 = 0x50;
+pub const XLOG_HEAP_LOCK: u8 = 0x60;
+pub const XLOG_HEAP_INPLACE: u8 = 0x70;
+pub const XLOG_HEAP_OPMASK: u8 = 0x70;
+/* the page is initialized from scratch; only with INSERT, UPDATE and HOT_UPDATE */
+pub const XLOG_HEAP_INIT_PAGE: u8 = 0x80;
+
+/// Names a Heap record type the way pg_waldump does, e.g. "HOT_UPDATE+INIT".
+pub fn identify(info: u8) -> &'static str {
+    let init = info & XLOG_HEAP_INIT_PAGE != 0;
+
+    match (info & XLOG_HEAP_OPMASK, init) {
+        (XLOG_HEAP_INSERT, false) => "INSERT",
+        (XLOG_HEAP_INSERT, true) => "INSERT+INIT",
+        (XLOG_HEAP_DELETE, _) => "DELETE",
+        (XLOG_HEAP_UPDATE, false) => "UPDATE",
+        (XLOG_HEAP_UPDATE, true) => "UPDATE+INIT",
+        (XLOG_HEAP_TRUNCATE, _) => "TRUNCATE",
+        (XLOG_HEAP_HOT_UPDATE, false) => "HOT_UPDATE",
+        (XLOG_HEAP_HOT_UPDATE, true) => "HOT_UPDATE+INIT",
+        (XLOG_HEAP_CONFIRM, _) => "CONFIRM",
+        (XLOG_HEAP_LOCK, _) => "LOCK",
+        _ => "INPLACE",
+    }
+}
+
+bitflags! {
+    /* xl_heap_insert and xl_heap_multi_insert flags */
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct HeapInsertFlags: u8 {
+        const XLH_INSERT_ALL_VISIBLE_CLEARED  = 1 << 0;
+        const XLH_INSERT_LAST_IN_MULTI        = 1 << 1;
+        const XLH_INSERT_IS_SPECULATIVE       = 1 << 2;
+        const XLH_INSERT_CONTAINS_NEW_TUPLE   = 1 << 3;
+        const XLH_INSERT_ON_TOAST_RELATION    = 1 << 4;
+        const XLH_INSERT_ALL_FROZEN_SET       = 1 << 5;
+    }
+
+    /* xl_heap_update flags */
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct HeapUpdateFlags: u8 {
+        const XLH_UPDATE_OLD_ALL_VISIBLE_CLEARED  = 1 << 0;
+        const XLH_UPDATE_NEW_ALL_VISIBLE_CLEARED  = 1 << 1;
+        const XLH_UPDATE_CONTAINS_OLD_TUPLE       = 1 << 2;
+        const XLH_UPDATE_CONTAINS_OLD_KEY         = 1 << 3;
+        const XLH_UPDATE_CONTAINS_NEW_TUPLE       = 1 << 4;
+        const XLH_UPDATE_PREFIX_FROM_OLD          = 1 << 5;
+        const XLH_UPDATE_SUFFIX_FROM_OLD          = 1 << 6;
+    }
+
+    /* xl_heap_delete flags */
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct HeapDeleteFlags: u8 {
+        const XLH_DELETE_ALL_VISIBLE_CLEARED  = 1 << 0;
+        const XLH_DELETE_CONTAINS_OLD_TUPLE   = 1 << 1;
+        const XLH_DELETE_CONTAINS_OLD_KEY     = 1 << 2;
+        const XLH_DELETE_IS_SUPER             = 1 << 3;
+        const XLH_DELETE_IS_PARTITION_MOVE    = 1 << 4;
+    }
+
+    /* xl_heap_truncate flags */
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct HeapTruncateFlags: u8 {
+        const XLH_TRUNCATE_CASCADE       = 1 << 0;
+        const XLH_TRUNCATE_RESTART_SEQS  = 1 << 1;
+    }
+
+    /* xl_heap_lock and xl_heap_lock_updated flags */
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct HeapLockFlags: u8 {
+        const XLH_LOCK_ALL_FROZEN_CLEARED = 0x01;
+    }
+
+    /* infobits_set: the infomask bits of the tuple's new xmax */
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct InfoBits: u8 {
+        const XLHL_XMAX_IS_MULTI      = 0x01;
+        const XLHL_XMAX_LOCK_ONLY     = 0x02;
+        const XLHL_XMAX_EXCL_LOCK     = 0x04;
+        const XLHL_XMAX_KEYSHR_LOCK   = 0x08;
+        const XLHL_KEYS_UPDATED       = 0x10;
+    }
+}
+
+/// xl_heap_insert: a tuple was inserted at `offnum` of block 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapInsert {
+    pub offnum: OffsetNumber,
+    pub flags: HeapInsertFlags,
+    /// The page was empty and initialized by this record
+    pub init_page: bool,
+}
+
+/// xl_heap_delete: the tuple at `offnum` of block 0 was deleted by `xmax`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapDelete {
+    pub xmax: TransactionId,
+    pub offnum: OffsetNumber,
+    pub infobits_set: InfoBits,
+    pub flags: HeapDeleteFlags,
+}
+
+/// xl_heap_update: the tuple at `old_offnum` was replaced by a new version at `new_offnum`.
+///
+/// The new tuple is in block 0, and the old one in block 1 when it is on another page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapUpdate {
+    pub old_xmax: TransactionId,
+    pub old_offnum: OffsetNumber,
+    pub old_infobits_set: InfoBits,
+    pub flags: HeapUpdateFlags,
+    pub new_xmax: TransactionId,
+    pub new_offnum: OffsetNumber,
+    pub init_page: bool,
+}
+
+/// xl_heap_truncate: TRUNCATE of `relids` in database `db_id`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapTruncate {
+    pub db_id: Oid,
+    pub flags: HeapTruncateFlags,
+    pub relids: Vec<Oid>,
+}
+
+/// xl_heap_lock: the tuple at `offnum` of block 0 was locked by `xmax`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapLock {
+    pub xmax: TransactionId,
+    pub offnum: OffsetNumber,
+    pub infobits_set: InfoBits,
+    pub flags: HeapLockFlags,
+}
+
+/// A decoded Heap record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeapRecord {
+    Insert(HeapInsert),
+    Delete(HeapDelete),
+    Update(HeapUpdate),
+    /// An update that kept the new version on the same page without new index entries
+    HotUpdate(HeapUpdate),
+    Truncate(HeapTruncate),
+    /// xl_heap_confirm: a speculative insertion at `offnum` was confirmed
+    Confirm { offnum: OffsetNumber },
+    Lock(HeapLock),
+    /// xl_heap_inplace: the tuple at `offnum` was overwritten in place, e.g. by VACUUM's
+    /// pg_class updates
+    Inplace { offnum: OffsetNumber },
+}
+
+impl HeapRecord {
+    /// Decodes the main data of a Heap record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<HeapRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+        let init_page = info & XLOG_HEAP_INIT_PAGE != 0;
+
+        Ok(match info & XLOG_HEAP_OPMASK {
+            XLOG_HEAP_INSERT => HeapRecord::Insert(HeapInsert {
+                offnum: reader.read_u16("xl_heap_insert.offnum")?,
+                flags: HeapInsertFlags::from_bits_retain(reader.read_u8("xl_heap_insert.flags")?),
+                init_page,
+            }),
+            XLOG_HEAP_DELETE => HeapRecord::Delete(HeapDelete {
+                xmax: reader.read::<TransactionId>("xl_heap_delete.xmax")?,
+                offnum: reader.read_u16("xl_heap_delete.offnum")?,
+                infobits_set: InfoBits::from_bits_retain(reader.read_u8("xl_heap_delete.infobits_set")?),
+                flags: HeapDeleteFlags::from_bits_retain(reader.read_u8("xl_heap_delete.flags")?),
+            }),
+            XLOG_HEAP_UPDATE => HeapRecord::Update(read_update(&mut reader, init_page)?),
+            XLOG_HEAP_HOT_UPDATE => HeapRecord::HotUpdate(read_update(&mut reader, init_page)?),
+            XLOG_HEAP_TRUNCATE => {
+                let db_id = reader.read_u32("xl_heap_truncate.dbId")?;
+                let nrelids = reader.read_u32("xl_heap_truncate.nrelids")?;
+                let flags = HeapTruncateFlags::from_bits_retain(reader.read_u8("xl_heap_truncate.flags")?);
+                /* relids is Oid-aligned */
+                reader.skip(3, "xl_heap_truncate padding")?;
+
+                let relids = (0..nrelids)
+                    .map(|_| reader.read_u32("xl_heap_truncate.relids"))
+                    .collect::<Result<_, _>>()?;
+
+                HeapRecord::Truncate(HeapTruncate { db_id, flags, relids })
+            },
+            XLOG_HEAP_CONFIRM => HeapRecord::Confirm {
+                offnum: reader.read_u16("xl_heap_confirm.offnum")?,
+            },
+            XLOG_HEAP_LOCK => HeapRecord::Lock(HeapLock {
+                xmax: reader.read::<TransactionId>("xl_heap_lock.xmax")?,
+                offnum: reader.read_u16("xl_heap_lock.offnum")?,
+                infobits_set: InfoBits::from_bits_retain(reader.read_u8("xl_heap_lock.infobits_set")?),
+                flags: HeapLockFlags::from_bits_retain(reader.read_u8("xl_heap_lock.flags")?),
+            }),
+            _ => HeapRecord::Inplace {
+                offnum: reader.read_u16("xl_heap_inplace.offnum")?,
+            },
+        })
+    }
+}
+
+fn read_update(reader: &mut ByteReader, init_page: bool) -> Result<HeapUpdate, DecodeError> {
+    Ok(HeapUpdate {
+        old_xmax: reader.read::<TransactionId>("xl_heap_update.old_xmax")?,
+        old_offnum: reader.read_u16("xl_heap_update.old_offnum")?,
+        old_infobits_set: InfoBits::from_bits_retain(reader.read_u8("xl_heap_update.old_infobits_set")?),
+        flags: HeapUpdateFlags::from_bits_retain(reader.read_u8("xl_heap_update.flags")?),
+        new_xmax: reader.read::<TransactionId>("xl_heap_update.new_xmax")?,
+        new_offnum: reader.read_u16("xl_heap_update.new_offnum")?,
+        init_page,
+    })
+}
@@ -0,0 +1,202 @@
+use crate::postgres::common::transaction_id::TransactionId;
+use crate::postgres::common::RelFileLocator;
+use crate::postgres::records::heap::{HeapInsertFlags, HeapLock, HeapLockFlags, InfoBits, XLOG_HEAP_INIT_PAGE, XLOG_HEAP_OPMASK};
+use crate::postgres::records::{ItemPointer, OffsetNumber, Oid};
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+use crate::postgres::xlog_parser::DecodedBlock;
+use bitflags::bitflags;
+
+/* XLOG info values for the Heap2 rmgr, from heapam_xlog.h */
+pub const XLOG_HEAP2_REWRITE: u8 = 0x00;
+pub const XLOG_HEAP2_PRUNE: u8 = 0x10;
+pub const XLOG_HEAP2_VACUUM: u8 = 0x20;
+pub const XLOG_HEAP2_FREEZE_PAGE: u8 = 0x30;
+pub const XLOG_HEAP2_VISIBLE: u8 = 0x40;
+pub const XLOG_HEAP2_MULTI_INSERT: u8 = 0x50;
+pub const XLOG_HEAP2_LOCK_UPDATED: u8 = 0x60;
+pub const XLOG_HEAP2_NEW_CID: u8 = 0x70;
+
+/// Names a Heap2 record type the way pg_waldump does, e.g. "MULTI_INSERT+INIT".
+pub fn identify(info: u8) -> &'static str {
+    let init = info & XLOG_HEAP_INIT_PAGE != 0;
+
+    match (info & XLOG_HEAP_OPMASK, init) {
+        (XLOG_HEAP2_REWRITE, _) => "REWRITE",
+        (XLOG_HEAP2_PRUNE, _) => "PRUNE",
+        (XLOG_HEAP2_VACUUM, _) => "VACUUM",
+        (XLOG_HEAP2_FREEZE_PAGE, _) => "FREEZE_PAGE",
+        (XLOG_HEAP2_VISIBLE, _) => "VISIBLE",
+        (XLOG_HEAP2_MULTI_INSERT, false) => "MULTI_INSERT",
+        (XLOG_HEAP2_MULTI_INSERT, true) => "MULTI_INSERT+INIT",
+        (XLOG_HEAP2_LOCK_UPDATED, _) => "LOCK_UPDATED",
+        _ => "NEW_CID",
+    }
+}
+
+bitflags! {
+    /* xl_heap_visible flags, the visibility map bits being set */
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct VisibilityMapFlags: u8 {
+        const VISIBILITYMAP_ALL_VISIBLE     = 0x01;
+        const VISIBILITYMAP_ALL_FROZEN      = 0x02;
+        const VISIBILITYMAP_XLOG_CATALOG_REL = 0x04;
+    }
+}
+
+/// xl_heap_rewrite_mapping: logical rewrite mappings written during CLUSTER or VACUUM FULL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapRewriteMapping {
+    pub mapped_xid: TransactionId,
+    pub mapped_db: Oid,
+    pub mapped_rel: Oid,
+    pub offset: i64,
+    pub num_mappings: u32,
+    pub start_lsn: u64,
+}
+
+/// xl_heap_prune: HOT chains on block 0 were pruned.
+///
+/// The offsets themselves are in the block data; only how many there are of each kind is kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapPrune {
+    pub snapshot_conflict_horizon: TransactionId,
+    pub nredirected: u16,
+    pub ndead: u16,
+    pub nunused: u16,
+    pub is_catalog_rel: bool,
+}
+
+/// xl_heap_freeze_page: tuples on block 0 were frozen, following `nplans` freeze plans.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapFreezePage {
+    pub snapshot_conflict_horizon: TransactionId,
+    pub nplans: u16,
+    pub is_catalog_rel: bool,
+}
+
+/// xl_heap_visible: block 1 was marked in the visibility map held in block 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapVisible {
+    pub snapshot_conflict_horizon: TransactionId,
+    pub flags: VisibilityMapFlags,
+}
+
+/// xl_heap_multi_insert: `ntuples` tuples were inserted into block 0, e.g. by COPY.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapMultiInsert {
+    pub flags: HeapInsertFlags,
+    pub ntuples: u16,
+    /// Where the tuples went; empty when the page was initialized, as they then fill it in order
+    pub offsets: Vec<OffsetNumber>,
+    pub init_page: bool,
+}
+
+/// xl_heap_new_cid: the command ids of a catalog tuple, for logical decoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapNewCid {
+    pub top_xid: TransactionId,
+    pub cmin: u32,
+    pub cmax: u32,
+    pub combocid: u32,
+    pub target_locator: RelFileLocator,
+    pub target_tid: ItemPointer,
+}
+
+/// A decoded Heap2 record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Heap2Record {
+    Rewrite(HeapRewriteMapping),
+    Prune(HeapPrune),
+    /// xl_heap_vacuum: `nunused` dead line pointers on block 0 were marked unused
+    Vacuum { nunused: u16 },
+    FreezePage(HeapFreezePage),
+    Visible(HeapVisible),
+    MultiInsert(HeapMultiInsert),
+    /// xl_heap_lock_updated: a later version of an updated tuple was locked
+    LockUpdated(HeapLock),
+    NewCid(HeapNewCid),
+}
+
+impl Heap2Record {
+    /// Decodes a Heap2 record with rmgr info bits `info`. Pruning counts come from block 0's data.
+    pub(crate) fn decode(info: u8, main_data: &[u8], blocks: &[DecodedBlock]) -> Result<Heap2Record, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info & XLOG_HEAP_OPMASK {
+            XLOG_HEAP2_REWRITE => {
+                let mapped_xid = reader.read::<TransactionId>("xl_heap_rewrite_mapping.mapped_xid")?;
+                let mapped_db = reader.read_u32("xl_heap_rewrite_mapping.mapped_db")?;
+                let mapped_rel = reader.read_u32("xl_heap_rewrite_mapping.mapped_rel")?;
+                reader.skip(4, "xl_heap_rewrite_mapping padding")?;
+                let offset = reader.read::<i64>("xl_heap_rewrite_mapping.offset")?;
+                let num_mappings = reader.read_u32("xl_heap_rewrite_mapping.num_mappings")?;
+                reader.skip(4, "xl_heap_rewrite_mapping padding")?;
+                let start_lsn = reader.read_u64("xl_heap_rewrite_mapping.start_lsn")?;
+
+                Heap2Record::Rewrite(HeapRewriteMapping { mapped_xid, mapped_db, mapped_rel, offset, num_mappings, start_lsn })
+            },
+            XLOG_HEAP2_PRUNE => {
+                let snapshot_conflict_horizon = reader.read::<TransactionId>("xl_heap_prune.snapshotConflictHorizon")?;
+                let nredirected = reader.read_u16("xl_heap_prune.nredirected")?;
+                let ndead = reader.read_u16("xl_heap_prune.ndead")?;
+                let is_catalog_rel = reader.read_u8("xl_heap_prune.isCatalogRel")? != 0;
+
+                /* block 0's data is the redirected pairs, then the dead offsets, then the unused ones */
+                let mut offsets = ByteReader::new(blocks.first().map_or(&[], |block| block.data));
+                let listed = 2 * nredirected as usize + ndead as usize;
+                offsets.skip(listed * size_of::<OffsetNumber>(), "xl_heap_prune redirected and dead offsets")?;
+
+                Heap2Record::Prune(HeapPrune {
+                    snapshot_conflict_horizon,
+                    nredirected,
+                    ndead,
+                    nunused: (offsets.remaining() / size_of::<OffsetNumber>()) as u16,
+                    is_catalog_rel,
+                })
+            },
+            XLOG_HEAP2_VACUUM => Heap2Record::Vacuum {
+                nunused: reader.read_u16("xl_heap_vacuum.nunused")?,
+            },
+            XLOG_HEAP2_FREEZE_PAGE => Heap2Record::FreezePage(HeapFreezePage {
+                snapshot_conflict_horizon: reader.read::<TransactionId>("xl_heap_freeze_page.snapshotConflictHorizon")?,
+                nplans: reader.read_u16("xl_heap_freeze_page.nplans")?,
+                is_catalog_rel: reader.read_u8("xl_heap_freeze_page.isCatalogRel")? != 0,
+            }),
+            XLOG_HEAP2_VISIBLE => Heap2Record::Visible(HeapVisible {
+                snapshot_conflict_horizon: reader.read::<TransactionId>("xl_heap_visible.snapshotConflictHorizon")?,
+                flags: VisibilityMapFlags::from_bits_retain(reader.read_u8("xl_heap_visible.flags")?),
+            }),
+            XLOG_HEAP2_MULTI_INSERT => {
+                let init_page = info & XLOG_HEAP_INIT_PAGE != 0;
+                let flags = HeapInsertFlags::from_bits_retain(reader.read_u8("xl_heap_multi_insert.flags")?);
+                reader.skip(1, "xl_heap_multi_insert padding")?;
+                let ntuples = reader.read_u16("xl_heap_multi_insert.ntuples")?;
+
+                /* the offsets are left out when the page is initialized */
+                let offsets = match init_page {
+                    true => Vec::new(),
+                    false => (0..ntuples)
+                        .map(|_| reader.read_u16("xl_heap_multi_insert.offsets"))
+                        .collect::<Result<_, _>>()?,
+                };
+
+                Heap2Record::MultiInsert(HeapMultiInsert { flags, ntuples, offsets, init_page })
+            },
+            XLOG_HEAP2_LOCK_UPDATED => Heap2Record::LockUpdated(HeapLock {
+                xmax: reader.read::<TransactionId>("xl_heap_lock_updated.xmax")?,
+                offnum: reader.read_u16("xl_heap_lock_updated.offnum")?,
+                infobits_set: InfoBits::from_bits_retain(reader.read_u8("xl_heap_lock_updated.infobits_set")?),
+                flags: HeapLockFlags::from_bits_retain(reader.read_u8("xl_heap_lock_updated.flags")?),
+            }),
+            _ => Heap2Record::NewCid(HeapNewCid {
+                top_xid: reader.read::<TransactionId>("xl_heap_new_cid.top_xid")?,
+                cmin: reader.read_u32("xl_heap_new_cid.cmin")?,
+                cmax: reader.read_u32("xl_heap_new_cid.cmax")?,
+                combocid: reader.read_u32("xl_heap_new_cid.combocid")?,
+                target_locator: reader.read::<RelFileLocator>("xl_heap_new_cid.target_locator")?,
+                target_tid: ItemPointer::read(&mut reader, "xl_heap_new_cid.target_tid")?,
+            }),
+        })
+    }
+}
@@ -0,0 +1,125 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::common::transaction_id::TransactionId;
+use crate::postgres::records::Oid;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+
+/* XLOG info values for the MultiXact rmgr, from multixact.h */
+pub const XLOG_MULTIXACT_ZERO_OFF_PAGE: u8 = 0x00;
+pub const XLOG_MULTIXACT_ZERO_MEM_PAGE: u8 = 0x10;
+pub const XLOG_MULTIXACT_CREATE_ID: u8 = 0x20;
+pub const XLOG_MULTIXACT_TRUNCATE_ID: u8 = 0x30;
+
+/// MultiXactId: a set of transactions locking or updating the same tuple, numbered like xids.
+pub type MultiXactId = u32;
+/// MultiXactOffset: where a multixact's members start in pg_multixact/members.
+pub type MultiXactOffset = u32;
+
+/// Names a MultiXact record type the way pg_waldump does, e.g. "CREATE_ID".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        XLOG_MULTIXACT_ZERO_OFF_PAGE => "ZERO_OFF_PAGE",
+        XLOG_MULTIXACT_ZERO_MEM_PAGE => "ZERO_MEM_PAGE",
+        XLOG_MULTIXACT_CREATE_ID => "CREATE_ID",
+        XLOG_MULTIXACT_TRUNCATE_ID => "TRUNCATE_ID",
+        _ => "UNKNOWN",
+    }
+}
+
+/// MultiXactStatus: how a member holds the tuple.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MultiXactStatus {
+    ForKeyShare,
+    ForShare,
+    ForNoKeyUpdate,
+    ForUpdate,
+    NoKeyUpdate,
+    Update,
+    Unknown(u32),
+}
+
+impl MultiXactStatus {
+    fn from_u32(status: u32) -> MultiXactStatus {
+        match status {
+            0x00 => MultiXactStatus::ForKeyShare,
+            0x01 => MultiXactStatus::ForShare,
+            0x02 => MultiXactStatus::ForNoKeyUpdate,
+            0x03 => MultiXactStatus::ForUpdate,
+            0x04 => MultiXactStatus::NoKeyUpdate,
+            0x05 => MultiXactStatus::Update,
+            other => MultiXactStatus::Unknown(other),
+        }
+    }
+}
+
+/// MultiXactMember: one of the transactions in a multixact.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MultiXactMember {
+    pub xid: TransactionId,
+    pub status: MultiXactStatus,
+}
+
+/// xl_multixact_create: multixact `mid` was assigned, its members stored from `offset` on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiXactCreate {
+    pub mid: MultiXactId,
+    pub offset: MultiXactOffset,
+    pub members: Vec<MultiXactMember>,
+}
+
+/// xl_multixact_truncate: multixacts before `end_trunc_off` and their members were removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiXactTruncate {
+    pub oldest_multi_db: Oid,
+    pub start_trunc_off: MultiXactId,
+    /// The oldest multixact left
+    pub end_trunc_off: MultiXactId,
+    pub start_trunc_memb: MultiXactOffset,
+    pub end_trunc_memb: MultiXactOffset,
+}
+
+/// A decoded MultiXact record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MultiXactRecord {
+    /// A new page of pg_multixact/offsets was zeroed
+    ZeroOffsetPage { page: i32 },
+    /// A new page of pg_multixact/members was zeroed
+    ZeroMemberPage { page: i32 },
+    Create(MultiXactCreate),
+    Truncate(MultiXactTruncate),
+}
+
+impl MultiXactRecord {
+    /// Decodes the main data of a MultiXact record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<MultiXactRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            XLOG_MULTIXACT_ZERO_OFF_PAGE => MultiXactRecord::ZeroOffsetPage { page: reader.read::<i32>("pageno")? },
+            XLOG_MULTIXACT_ZERO_MEM_PAGE => MultiXactRecord::ZeroMemberPage { page: reader.read::<i32>("pageno")? },
+            XLOG_MULTIXACT_CREATE_ID => {
+                let mid = reader.read_u32("xl_multixact_create.mid")?;
+                let offset = reader.read_u32("xl_multixact_create.moff")?;
+                let nmembers = reader.read::<i32>("xl_multixact_create.nmembers")?;
+                let members = (0..nmembers)
+                    .map(|_| {
+                        Ok(MultiXactMember {
+                            xid: reader.read::<TransactionId>("xl_multixact_create.members")?,
+                            status: MultiXactStatus::from_u32(reader.read_u32("xl_multixact_create.members")?),
+                        })
+                    })
+                    .collect::<Result<_, DecodeError>>()?;
+
+                MultiXactRecord::Create(MultiXactCreate { mid, offset, members })
+            },
+            XLOG_MULTIXACT_TRUNCATE_ID => MultiXactRecord::Truncate(MultiXactTruncate {
+                oldest_multi_db: reader.read_u32("xl_multixact_truncate.oldestMultiDB")?,
+                start_trunc_off: reader.read_u32("xl_multixact_truncate.startTruncOff")?,
+                end_trunc_off: reader.read_u32("xl_multixact_truncate.endTruncOff")?,
+                start_trunc_memb: reader.read_u32("xl_multixact_truncate.startTruncMemb")?,
+                end_trunc_memb: reader.read_u32("xl_multixact_truncate.endTruncMemb")?,
+            }),
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::MultiXact, info }),
+        })
+    }
+}
@@ -0,0 +1,59 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::records::Oid;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+
+/* XLOG info values for the RelMap rmgr, from relmapper.h */
+pub const XLOG_RELMAP_UPDATE: u8 = 0x00;
+
+/// Names a RelMap record type the way pg_waldump does, e.g. "UPDATE".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        XLOG_RELMAP_UPDATE => "UPDATE",
+        _ => "UNKNOWN",
+    }
+}
+
+/// RelMapping: the file number a mapped catalog, e.g. pg_class, is stored under.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RelMapping {
+    pub oid: Oid,
+    pub file_number: Oid,
+}
+
+/// A decoded RelMap record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RelMapRecord {
+    /// xl_relmap_update: the relation map of database `db_id` was rewritten, e.g. by VACUUM FULL
+    /// of a mapped catalog; `db_id` is 0 for the shared map
+    Update { db_id: Oid, ts_id: Oid, mappings: Vec<RelMapping> },
+}
+
+impl RelMapRecord {
+    /// Decodes the main data of a RelMap record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<RelMapRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            XLOG_RELMAP_UPDATE => {
+                let db_id = reader.read_u32("xl_relmap_update.dbid")?;
+                let ts_id = reader.read_u32("xl_relmap_update.tsid")?;
+                reader.skip(4, "xl_relmap_update.nbytes")?;
+                /* the RelMapFile follows, its mappings after the magic number */
+                reader.skip(4, "RelMapFile.magic")?;
+                let num_mappings = reader.read::<i32>("RelMapFile.num_mappings")?;
+                let mappings = (0..num_mappings)
+                    .map(|_| {
+                        Ok(RelMapping {
+                            oid: reader.read_u32("RelMapFile.mappings")?,
+                            file_number: reader.read_u32("RelMapFile.mappings")?,
+                        })
+                    })
+                    .collect::<Result<_, DecodeError>>()?;
+
+                RelMapRecord::Update { db_id, ts_id, mappings }
+            },
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::RelMap, info }),
+        })
+    }
+}
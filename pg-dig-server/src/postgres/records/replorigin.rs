@@ -0,0 +1,48 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+
+/* XLOG info values for the ReplicationOrigin rmgr, from origin.h */
+pub const XLOG_REPLORIGIN_SET: u8 = 0x00;
+pub const XLOG_REPLORIGIN_DROP: u8 = 0x10;
+
+/// RepOriginId: a replication origin, as numbered in pg_replication_origin.
+pub type RepOriginId = u16;
+
+/// Names a ReplicationOrigin record type the way pg_waldump does, e.g. "SET".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        XLOG_REPLORIGIN_SET => "SET",
+        XLOG_REPLORIGIN_DROP => "DROP",
+        _ => "UNKNOWN",
+    }
+}
+
+/// A decoded ReplicationOrigin record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplicationOriginRecord {
+    /// xl_replorigin_set: the origin's progress was set to `remote_lsn`, e.g. by
+    /// pg_replication_origin_advance()
+    Set { remote_lsn: u64, node_id: RepOriginId, force: bool },
+    /// xl_replorigin_drop
+    Drop { node_id: RepOriginId },
+}
+
+impl ReplicationOriginRecord {
+    /// Decodes the main data of a ReplicationOrigin record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<ReplicationOriginRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            XLOG_REPLORIGIN_SET => ReplicationOriginRecord::Set {
+                remote_lsn: reader.read_u64("xl_replorigin_set.remote_lsn")?,
+                node_id: reader.read_u16("xl_replorigin_set.node_id")?,
+                force: reader.read_u8("xl_replorigin_set.force")? != 0,
+            },
+            XLOG_REPLORIGIN_DROP => ReplicationOriginRecord::Drop {
+                node_id: reader.read_u16("xl_replorigin_drop.node_id")?,
+            },
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::ReplicationOrigin, info }),
+        })
+    }
+}
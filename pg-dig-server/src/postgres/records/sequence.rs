@@ -0,0 +1,54 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::common::RelFileLocator;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+
+/* XLOG info values for the Sequence rmgr, from sequence.h */
+pub const XLOG_SEQ_LOG: u8 = 0x00;
+
+/* offsetof(HeapTupleHeaderData, t_hoff) */
+const TUPLE_HOFF_OFFSET: usize = 22;
+
+/// Names a Sequence record type the way pg_waldump does, e.g. "LOG".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        XLOG_SEQ_LOG => "LOG",
+        _ => "UNKNOWN",
+    }
+}
+
+/// A decoded Sequence record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SequenceRecord {
+    /// xl_seq_rec: the sequence page in block 0 was rewritten with a new FormData_pg_sequence_data
+    /// tuple.
+    ///
+    /// nextval() logs ahead, so `last_value` is the value `log_cnt` calls from now, not the one
+    /// just handed out.
+    Log { locator: RelFileLocator, last_value: i64, log_cnt: i64, is_called: bool },
+}
+
+impl SequenceRecord {
+    /// Decodes the main data of a Sequence record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<SequenceRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            XLOG_SEQ_LOG => {
+                let locator = reader.read::<RelFileLocator>("xl_seq_rec.locator")?;
+                /* the tuple follows, its data starting t_hoff bytes into it */
+                reader.skip(TUPLE_HOFF_OFFSET, "HeapTupleHeaderData")?;
+                let t_hoff = reader.read_u8("HeapTupleHeaderData.t_hoff")? as usize;
+                reader.skip(t_hoff.saturating_sub(TUPLE_HOFF_OFFSET + 1), "HeapTupleHeaderData")?;
+
+                SequenceRecord::Log {
+                    locator,
+                    last_value: reader.read::<i64>("FormData_pg_sequence_data.last_value")?,
+                    log_cnt: reader.read::<i64>("FormData_pg_sequence_data.log_cnt")?,
+                    is_called: reader.read_u8("FormData_pg_sequence_data.is_called")? != 0,
+                }
+            },
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::Sequence, info }),
+        })
+    }
+}
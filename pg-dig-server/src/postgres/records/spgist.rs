@@ -0,0 +1,183 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::common::transaction_id::TransactionId;
+use crate::postgres::records::OffsetNumber;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+
+/* XLOG info values for the SPGist rmgr, from spgxlog.h */
+pub const XLOG_SPGIST_ADD_LEAF: u8 = 0x10;
+pub const XLOG_SPGIST_MOVE_LEAFS: u8 = 0x20;
+pub const XLOG_SPGIST_ADD_NODE: u8 = 0x30;
+pub const XLOG_SPGIST_SPLIT_TUPLE: u8 = 0x40;
+pub const XLOG_SPGIST_PICKSPLIT: u8 = 0x50;
+pub const XLOG_SPGIST_VACUUM_LEAF: u8 = 0x60;
+pub const XLOG_SPGIST_VACUUM_ROOT: u8 = 0x70;
+pub const XLOG_SPGIST_VACUUM_REDIRECT: u8 = 0x80;
+
+/// Names an SPGist record type the way pg_waldump does, e.g. "PICKSPLIT".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        XLOG_SPGIST_ADD_LEAF => "ADD_LEAF",
+        XLOG_SPGIST_MOVE_LEAFS => "MOVE_LEAFS",
+        XLOG_SPGIST_ADD_NODE => "ADD_NODE",
+        XLOG_SPGIST_SPLIT_TUPLE => "SPLIT_TUPLE",
+        XLOG_SPGIST_PICKSPLIT => "PICKSPLIT",
+        XLOG_SPGIST_VACUUM_LEAF => "VACUUM_LEAF",
+        XLOG_SPGIST_VACUUM_ROOT => "VACUUM_ROOT",
+        XLOG_SPGIST_VACUUM_REDIRECT => "VACUUM_REDIRECT",
+        _ => "UNKNOWN",
+    }
+}
+
+/// spgxlogAddLeaf: a leaf tuple was added at `offnum_leaf` of the page in block 0.
+///
+/// Block 1, when present, is the parent whose downlink at `offnum_parent` now points at it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpgAddLeaf {
+    pub new_page: bool,
+    /// The page is in the tree for null keys
+    pub stores_nulls: bool,
+    pub offnum_leaf: OffsetNumber,
+    /// The head of the chain the tuple was linked into, if any
+    pub offnum_head_leaf: OffsetNumber,
+    pub offnum_parent: OffsetNumber,
+    pub node_i: u16,
+}
+
+/// spgxlogMoveLeafs: `nmoves` leaf tuples moved from block 0 to block 1 to make room for a new
+/// one, and the parent downlink in block 2 was updated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpgMoveLeafs {
+    pub nmoves: u16,
+    pub new_page: bool,
+    /// The moved tuples became dead tuples rather than redirects
+    pub replace_dead: bool,
+    pub stores_nulls: bool,
+    pub offnum_parent: OffsetNumber,
+    pub node_i: u16,
+}
+
+/// spgxlogPickSplit: a full leaf page was split by the opclass picksplit function.
+///
+/// Block 0 is the source page, block 1 the new leaf page, block 2 the inner page the new inner
+/// tuple went to and block 3 the parent, when it is not block 2.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpgPickSplit {
+    pub is_root_split: bool,
+    pub ndelete: u16,
+    pub ninsert: u16,
+    pub init_src: bool,
+    pub init_dest: bool,
+    pub offnum_inner: OffsetNumber,
+    pub init_inner: bool,
+    pub stores_nulls: bool,
+    pub inner_is_parent: bool,
+    pub offnum_parent: OffsetNumber,
+    pub node_i: u16,
+}
+
+/// A decoded SPGist record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpgistRecord {
+    AddLeaf(SpgAddLeaf),
+    MoveLeafs(SpgMoveLeafs),
+    /// spgxlogAddNode: a node was added to the inner tuple at `offnum` of block 0, moving it to
+    /// `offnum_new` of block 1 when it no longer fit
+    AddNode { offnum: OffsetNumber, offnum_new: OffsetNumber, new_page: bool },
+    /// spgxlogSplitTuple: an inner tuple in block 0 was split into a prefix and a postfix, the
+    /// postfix going to block 1 unless `postfix_block_same`
+    SplitTuple { offnum_prefix: OffsetNumber, offnum_postfix: OffsetNumber, new_page: bool, postfix_block_same: bool },
+    PickSplit(SpgPickSplit),
+    /// spgxlogVacuumLeaf: VACUUM cleaned the leaf page in block 0
+    VacuumLeaf { ndead: u16, nplaceholder: u16, nmove: u16, nchain: u16 },
+    /// spgxlogVacuumRoot: VACUUM removed `ndeleted` tuples from the root leaf page in block 0
+    VacuumRoot { ndeleted: u16 },
+    /// spgxlogVacuumRedirect: redirects on block 0 no snapshot can follow any more were turned
+    /// into placeholders
+    VacuumRedirect { nto_placeholder: u16, first_placeholder: OffsetNumber, snapshot_conflict_horizon: TransactionId },
+}
+
+impl SpgistRecord {
+    /// Decodes the main data of an SPGist record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<SpgistRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            XLOG_SPGIST_ADD_LEAF => SpgistRecord::AddLeaf(SpgAddLeaf {
+                new_page: reader.read_u8("spgxlogAddLeaf.newPage")? != 0,
+                stores_nulls: reader.read_u8("spgxlogAddLeaf.storesNulls")? != 0,
+                offnum_leaf: reader.read_u16("spgxlogAddLeaf.offnumLeaf")?,
+                offnum_head_leaf: reader.read_u16("spgxlogAddLeaf.offnumHeadLeaf")?,
+                offnum_parent: reader.read_u16("spgxlogAddLeaf.offnumParent")?,
+                node_i: reader.read_u16("spgxlogAddLeaf.nodeI")?,
+            }),
+            XLOG_SPGIST_MOVE_LEAFS => {
+                let nmoves = reader.read_u16("spgxlogMoveLeafs.nMoves")?;
+                let new_page = reader.read_u8("spgxlogMoveLeafs.newPage")? != 0;
+                let replace_dead = reader.read_u8("spgxlogMoveLeafs.replaceDead")? != 0;
+                let stores_nulls = reader.read_u8("spgxlogMoveLeafs.storesNulls")? != 0;
+                reader.skip(1, "spgxlogMoveLeafs padding")?;
+
+                SpgistRecord::MoveLeafs(SpgMoveLeafs {
+                    nmoves,
+                    new_page,
+                    replace_dead,
+                    stores_nulls,
+                    offnum_parent: reader.read_u16("spgxlogMoveLeafs.offnumParent")?,
+                    node_i: reader.read_u16("spgxlogMoveLeafs.nodeI")?,
+                })
+            },
+            XLOG_SPGIST_ADD_NODE => SpgistRecord::AddNode {
+                offnum: reader.read_u16("spgxlogAddNode.offnum")?,
+                offnum_new: reader.read_u16("spgxlogAddNode.offnumNew")?,
+                new_page: reader.read_u8("spgxlogAddNode.newPage")? != 0,
+            },
+            XLOG_SPGIST_SPLIT_TUPLE => SpgistRecord::SplitTuple {
+                offnum_prefix: reader.read_u16("spgxlogSplitTuple.offnumPrefix")?,
+                offnum_postfix: reader.read_u16("spgxlogSplitTuple.offnumPostfix")?,
+                new_page: reader.read_u8("spgxlogSplitTuple.newPage")? != 0,
+                postfix_block_same: reader.read_u8("spgxlogSplitTuple.postfixBlkSame")? != 0,
+            },
+            XLOG_SPGIST_PICKSPLIT => {
+                let is_root_split = reader.read_u8("spgxlogPickSplit.isRootSplit")? != 0;
+                reader.skip(1, "spgxlogPickSplit padding")?;
+                let ndelete = reader.read_u16("spgxlogPickSplit.nDelete")?;
+                let ninsert = reader.read_u16("spgxlogPickSplit.nInsert")?;
+                let init_src = reader.read_u8("spgxlogPickSplit.initSrc")? != 0;
+                let init_dest = reader.read_u8("spgxlogPickSplit.initDest")? != 0;
+                let offnum_inner = reader.read_u16("spgxlogPickSplit.offnumInner")?;
+                let init_inner = reader.read_u8("spgxlogPickSplit.initInner")? != 0;
+                let stores_nulls = reader.read_u8("spgxlogPickSplit.storesNulls")? != 0;
+                let inner_is_parent = reader.read_u8("spgxlogPickSplit.innerIsParent")? != 0;
+                reader.skip(1, "spgxlogPickSplit padding")?;
+
+                SpgistRecord::PickSplit(SpgPickSplit {
+                    is_root_split,
+                    ndelete,
+                    ninsert,
+                    init_src,
+                    init_dest,
+                    offnum_inner,
+                    init_inner,
+                    stores_nulls,
+                    inner_is_parent,
+                    offnum_parent: reader.read_u16("spgxlogPickSplit.offnumParent")?,
+                    node_i: reader.read_u16("spgxlogPickSplit.nodeI")?,
+                })
+            },
+            XLOG_SPGIST_VACUUM_LEAF => SpgistRecord::VacuumLeaf {
+                ndead: reader.read_u16("spgxlogVacuumLeaf.nDead")?,
+                nplaceholder: reader.read_u16("spgxlogVacuumLeaf.nPlaceholder")?,
+                nmove: reader.read_u16("spgxlogVacuumLeaf.nMove")?,
+                nchain: reader.read_u16("spgxlogVacuumLeaf.nChain")?,
+            },
+            XLOG_SPGIST_VACUUM_ROOT => SpgistRecord::VacuumRoot { ndeleted: reader.read_u16("spgxlogVacuumRoot.nDelete")? },
+            XLOG_SPGIST_VACUUM_REDIRECT => SpgistRecord::VacuumRedirect {
+                nto_placeholder: reader.read_u16("spgxlogVacuumRedirect.nToPlaceholder")?,
+                first_placeholder: reader.read_u16("spgxlogVacuumRedirect.firstPlaceholder")?,
+                snapshot_conflict_horizon: reader.read::<TransactionId>("spgxlogVacuumRedirect.snapshotConflictHorizon")?,
+            },
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::SPGist, info }),
+        })
+    }
+}
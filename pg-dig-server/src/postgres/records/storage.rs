@@ -0,0 +1,77 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::common::RelFileLocator;
+use crate::postgres::records::BlockNumber;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+use bitflags::bitflags;
+
+/* XLOG info values for the Storage rmgr, from storage_xlog.h */
+pub const XLOG_SMGR_CREATE: u8 = 0x10;
+pub const XLOG_SMGR_TRUNCATE: u8 = 0x20;
+
+/// ForkNumber: which file of a relation, 0 being the main fork.
+pub type ForkNumber = i32;
+
+pub const MAIN_FORKNUM: ForkNumber = 0;
+pub const FSM_FORKNUM: ForkNumber = 1;
+pub const VISIBILITYMAP_FORKNUM: ForkNumber = 2;
+pub const INIT_FORKNUM: ForkNumber = 3;
+
+/// Names a fork the way relation file names suffix it, with "main" for the main fork.
+pub fn fork_name(fork: ForkNumber) -> &'static str {
+    match fork {
+        MAIN_FORKNUM => "main",
+        FSM_FORKNUM => "fsm",
+        VISIBILITYMAP_FORKNUM => "vm",
+        INIT_FORKNUM => "init",
+        _ => "unknown",
+    }
+}
+
+/// Names a Storage record type the way pg_waldump does, e.g. "TRUNCATE".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        XLOG_SMGR_CREATE => "CREATE",
+        XLOG_SMGR_TRUNCATE => "TRUNCATE",
+        _ => "UNKNOWN",
+    }
+}
+
+bitflags! {
+    /* xl_smgr_truncate flags: which forks were truncated */
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct SmgrTruncateFlags: i32 {
+        const SMGR_TRUNCATE_HEAP = 0x0001;
+        const SMGR_TRUNCATE_VM   = 0x0002;
+        const SMGR_TRUNCATE_FSM  = 0x0004;
+    }
+}
+
+/// A decoded Storage record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageRecord {
+    /// xl_smgr_create: a relation fork's file was created
+    Create { locator: RelFileLocator, fork: ForkNumber },
+    /// xl_smgr_truncate: the relation was cut down to `block` blocks
+    Truncate { block: BlockNumber, locator: RelFileLocator, flags: SmgrTruncateFlags },
+}
+
+impl StorageRecord {
+    /// Decodes the main data of a Storage record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<StorageRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            XLOG_SMGR_CREATE => StorageRecord::Create {
+                locator: reader.read::<RelFileLocator>("xl_smgr_create.rlocator")?,
+                fork: reader.read::<ForkNumber>("xl_smgr_create.forkNum")?,
+            },
+            XLOG_SMGR_TRUNCATE => StorageRecord::Truncate {
+                block: reader.read_u32("xl_smgr_truncate.blkno")?,
+                locator: reader.read::<RelFileLocator>("xl_smgr_truncate.rlocator")?,
+                flags: SmgrTruncateFlags::from_bits_retain(reader.read::<i32>("xl_smgr_truncate.flags")?),
+            },
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::Storage, info }),
+        })
+    }
+}
@@ -0,0 +1,44 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::records::Oid;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+
+/* XLOG info values for the Tablespace rmgr, from tablespace.h */
+pub const XLOG_TBLSPC_CREATE: u8 = 0x00;
+pub const XLOG_TBLSPC_DROP: u8 = 0x10;
+
+/// Names a Tablespace record type the way pg_waldump does, e.g. "CREATE".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        XLOG_TBLSPC_CREATE => "CREATE",
+        XLOG_TBLSPC_DROP => "DROP",
+        _ => "UNKNOWN",
+    }
+}
+
+/// A decoded Tablespace record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TablespaceRecord {
+    /// xl_tblspc_create_rec: the tablespace was created at `path`
+    Create { ts_id: Oid, path: String },
+    /// xl_tblspc_drop_rec
+    Drop { ts_id: Oid },
+}
+
+impl TablespaceRecord {
+    /// Decodes the main data of a Tablespace record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<TablespaceRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            XLOG_TBLSPC_CREATE => TablespaceRecord::Create {
+                ts_id: reader.read_u32("xl_tblspc_create_rec.ts_id")?,
+                path: reader.read_c_string("xl_tblspc_create_rec.ts_path")?,
+            },
+            XLOG_TBLSPC_DROP => TablespaceRecord::Drop {
+                ts_id: reader.read_u32("xl_tblspc_drop_rec.ts_id")?,
+            },
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::Tablespace, info }),
+        })
+    }
+}
@@ -0,0 +1,274 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::common::timestamp::TimestampTz;
+use crate::postgres::common::transaction_id::TransactionId;
+use crate::postgres::common::RelFileLocator;
+use crate::postgres::records::Oid;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+use bitflags::bitflags;
+
+/* XLOG info values for the Transaction rmgr, from xact.h */
+pub const XLOG_XACT_COMMIT: u8 = 0x00;
+pub const XLOG_XACT_PREPARE: u8 = 0x10;
+pub const XLOG_XACT_ABORT: u8 = 0x20;
+pub const XLOG_XACT_COMMIT_PREPARED: u8 = 0x30;
+pub const XLOG_XACT_ABORT_PREPARED: u8 = 0x40;
+pub const XLOG_XACT_ASSIGNMENT: u8 = 0x50;
+pub const XLOG_XACT_INVALIDATIONS: u8 = 0x60;
+pub const XLOG_XACT_OPMASK: u8 = 0x70;
+/* an xl_xact_xinfo follows the fixed part of a commit or abort */
+pub const XLOG_XACT_HAS_INFO: u8 = 0x80;
+
+/* sizeof(SharedInvalidationMessage) */
+const INVALIDATION_MESSAGE_SIZE: usize = 16;
+/* sizeof(xl_xact_stats_item) */
+const STATS_ITEM_SIZE: usize = 12;
+/* sizeof(xl_xact_prepare), the fixed part of a PREPARE record */
+const PREPARE_HEADER_SIZE: usize = 72;
+
+/// Names a Transaction record type the way pg_waldump does, e.g. "COMMIT_PREPARED".
+pub fn identify(info: u8) -> &'static str {
+    match info & XLOG_XACT_OPMASK {
+        XLOG_XACT_COMMIT => "COMMIT",
+        XLOG_XACT_PREPARE => "PREPARE",
+        XLOG_XACT_ABORT => "ABORT",
+        XLOG_XACT_COMMIT_PREPARED => "COMMIT_PREPARED",
+        XLOG_XACT_ABORT_PREPARED => "ABORT_PREPARED",
+        XLOG_XACT_ASSIGNMENT => "ASSIGNMENT",
+        XLOG_XACT_INVALIDATIONS => "INVALIDATION",
+        _ => "UNKNOWN",
+    }
+}
+
+bitflags! {
+    /* xl_xact_xinfo: which sub-structures follow, and how the commit completes */
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct XactInfo: u32 {
+        const XACT_XINFO_HAS_DBINFO             = 1 << 0;
+        const XACT_XINFO_HAS_SUBXACTS           = 1 << 1;
+        const XACT_XINFO_HAS_RELFILELOCATORS    = 1 << 2;
+        const XACT_XINFO_HAS_INVALS             = 1 << 3;
+        const XACT_XINFO_HAS_TWOPHASE           = 1 << 4;
+        const XACT_XINFO_HAS_ORIGIN             = 1 << 5;
+        const XACT_XINFO_HAS_AE_LOCKS           = 1 << 6;
+        const XACT_XINFO_HAS_GID                = 1 << 7;
+        const XACT_XINFO_HAS_DROPPED_STATS      = 1 << 8;
+        const XACT_COMPLETION_APPLY_FEEDBACK    = 1 << 29;
+        const XACT_COMPLETION_UPDATE_RELCACHE_FILE = 1 << 30;
+        const XACT_COMPLETION_FORCE_SYNC_COMMIT = 1 << 31;
+    }
+}
+
+/// xl_xact_origin: the replication origin a commit was replayed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XactOrigin {
+    pub lsn: u64,
+    pub timestamp: TimestampTz,
+}
+
+/// xl_xact_commit or xl_xact_abort with the sub-structures its xinfo announces.
+///
+/// Aborts share the layout but never carry invalidations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XactCompletion {
+    pub xact_time: TimestampTz,
+    pub xinfo: XactInfo,
+    /// The database and its tablespace, or InvalidOid without XACT_XINFO_HAS_DBINFO
+    pub db_id: Oid,
+    pub ts_id: Oid,
+    pub subxacts: Vec<TransactionId>,
+    /// Relations whose files are removed now that the transaction is over
+    pub dropped_relations: Vec<RelFileLocator>,
+    pub dropped_stats: usize,
+    pub invalidations: usize,
+    /// The prepared transaction this finishes, for COMMIT_PREPARED and ABORT_PREPARED
+    pub twophase_xid: Option<TransactionId>,
+    pub gid: Option<String>,
+    pub origin: Option<XactOrigin>,
+}
+
+/// xl_xact_prepare: the transaction `xid` was prepared for two-phase commit as `gid`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XactPrepare {
+    pub xid: TransactionId,
+    pub database: Oid,
+    pub prepared_at: TimestampTz,
+    pub owner: Oid,
+    pub nsubxacts: i32,
+    pub ncommitrels: i32,
+    pub nabortrels: i32,
+    pub ninvalmsgs: i32,
+    pub gid: String,
+}
+
+/// A decoded Transaction record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XactRecord {
+    Commit(XactCompletion),
+    Prepare(XactPrepare),
+    Abort(XactCompletion),
+    CommitPrepared(XactCompletion),
+    AbortPrepared(XactCompletion),
+    /// xl_xact_assignment: subtransactions were assigned to `xtop`, logged every
+    /// PGPROC_MAX_CACHED_SUBXIDS of them so a standby can track them
+    Assignment { xtop: TransactionId, subxacts: Vec<TransactionId> },
+    /// xl_xact_invals: catalog invalidations logged mid-transaction for logical decoding
+    Invalidations { nmsgs: usize },
+}
+
+impl XactRecord {
+    /// Decodes the main data of a Transaction record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<XactRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+        let has_info = info & XLOG_XACT_HAS_INFO != 0;
+
+        Ok(match info & XLOG_XACT_OPMASK {
+            XLOG_XACT_COMMIT => XactRecord::Commit(read_completion(&mut reader, has_info, true)?),
+            XLOG_XACT_PREPARE => XactRecord::Prepare(read_prepare(&mut reader)?),
+            XLOG_XACT_ABORT => XactRecord::Abort(read_completion(&mut reader, has_info, false)?),
+            XLOG_XACT_COMMIT_PREPARED => XactRecord::CommitPrepared(read_completion(&mut reader, has_info, true)?),
+            XLOG_XACT_ABORT_PREPARED => XactRecord::AbortPrepared(read_completion(&mut reader, has_info, false)?),
+            XLOG_XACT_ASSIGNMENT => {
+                let xtop = reader.read::<TransactionId>("xl_xact_assignment.xtop")?;
+                let nsubxacts = reader.read_u32("xl_xact_assignment.nsubxacts")?;
+                let subxacts = read_xids(&mut reader, nsubxacts, "xl_xact_assignment.xsub")?;
+
+                XactRecord::Assignment { xtop, subxacts }
+            },
+            XLOG_XACT_INVALIDATIONS => {
+                let nmsgs = reader.read_u32("xl_xact_invals.nmsgs")? as usize;
+                reader.skip(nmsgs * INVALIDATION_MESSAGE_SIZE, "xl_xact_invals.msgs")?;
+
+                XactRecord::Invalidations { nmsgs }
+            },
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::Transaction, info }),
+        })
+    }
+
+    /// When the transaction committed, aborted or was prepared.
+    pub fn xact_time(&self) -> Option<TimestampTz> {
+        match self {
+            XactRecord::Commit(completion)
+            | XactRecord::Abort(completion)
+            | XactRecord::CommitPrepared(completion)
+            | XactRecord::AbortPrepared(completion) => Some(completion.xact_time),
+            XactRecord::Prepare(prepare) => Some(prepare.prepared_at),
+            _ => None,
+        }
+    }
+
+    /// The transactions this record ends, given the `xl_xid` of its header: the top-level
+    /// transaction, or the prepared one, followed by its subtransactions.
+    ///
+    /// Heap changes logged under any of these ids share this record's outcome.
+    pub fn completed_xids(&self, xl_xid: TransactionId) -> Vec<TransactionId> {
+        match self {
+            XactRecord::Commit(completion)
+            | XactRecord::Abort(completion)
+            | XactRecord::CommitPrepared(completion)
+            | XactRecord::AbortPrepared(completion) => {
+                let top = completion.twophase_xid.unwrap_or(xl_xid);
+                std::iter::once(top).chain(completion.subxacts.iter().copied()).collect()
+            },
+            _ => Vec::new(),
+        }
+    }
+}
+
+/* ParseCommitRecord and ParseAbortRecord: the sub-structures follow in xinfo bit order, except
+ * that dropped stats come before invalidations */
+fn read_completion(reader: &mut ByteReader, has_info: bool, is_commit: bool) -> Result<XactCompletion, DecodeError> {
+    let xact_time = reader.read::<TimestampTz>("xl_xact_commit.xact_time")?;
+    let xinfo = match has_info {
+        true => XactInfo::from_bits_retain(reader.read_u32("xl_xact_xinfo.xinfo")?),
+        false => XactInfo::empty(),
+    };
+
+    let mut completion = XactCompletion {
+        xact_time,
+        xinfo,
+        db_id: 0,
+        ts_id: 0,
+        subxacts: Vec::new(),
+        dropped_relations: Vec::new(),
+        dropped_stats: 0,
+        invalidations: 0,
+        twophase_xid: None,
+        gid: None,
+        origin: None,
+    };
+
+    if xinfo.contains(XactInfo::XACT_XINFO_HAS_DBINFO) {
+        completion.db_id = reader.read_u32("xl_xact_dbinfo.dbId")?;
+        completion.ts_id = reader.read_u32("xl_xact_dbinfo.tsId")?;
+    }
+    if xinfo.contains(XactInfo::XACT_XINFO_HAS_SUBXACTS) {
+        let nsubxacts = reader.read_u32("xl_xact_subxacts.nsubxacts")?;
+        completion.subxacts = read_xids(reader, nsubxacts, "xl_xact_subxacts.subxacts")?;
+    }
+    if xinfo.contains(XactInfo::XACT_XINFO_HAS_RELFILELOCATORS) {
+        let nrels = reader.read_u32("xl_xact_relfilelocators.nrels")?;
+        completion.dropped_relations = (0..nrels)
+            .map(|_| reader.read::<RelFileLocator>("xl_xact_relfilelocators.xlocators"))
+            .collect::<Result<_, _>>()?;
+    }
+    if xinfo.contains(XactInfo::XACT_XINFO_HAS_DROPPED_STATS) {
+        completion.dropped_stats = reader.read_u32("xl_xact_stats_items.nitems")? as usize;
+        reader.skip(completion.dropped_stats * STATS_ITEM_SIZE, "xl_xact_stats_items.items")?;
+    }
+    if is_commit && xinfo.contains(XactInfo::XACT_XINFO_HAS_INVALS) {
+        completion.invalidations = reader.read_u32("xl_xact_invals.nmsgs")? as usize;
+        reader.skip(completion.invalidations * INVALIDATION_MESSAGE_SIZE, "xl_xact_invals.msgs")?;
+    }
+    if xinfo.contains(XactInfo::XACT_XINFO_HAS_TWOPHASE) {
+        completion.twophase_xid = Some(reader.read::<TransactionId>("xl_xact_twophase.xid")?);
+        if xinfo.contains(XactInfo::XACT_XINFO_HAS_GID) {
+            completion.gid = Some(reader.read_c_string("twophase_gid")?);
+        }
+    }
+    /* the origin is not aligned, as it may follow the GID */
+    if xinfo.contains(XactInfo::XACT_XINFO_HAS_ORIGIN) {
+        completion.origin = Some(XactOrigin {
+            lsn: reader.read_u64("xl_xact_origin.origin_lsn")?,
+            timestamp: reader.read::<TimestampTz>("xl_xact_origin.origin_timestamp")?,
+        });
+    }
+
+    Ok(completion)
+}
+
+fn read_prepare(reader: &mut ByteReader) -> Result<XactPrepare, DecodeError> {
+    reader.skip(8, "xl_xact_prepare.magic and total_len")?;
+    let xid = reader.read::<TransactionId>("xl_xact_prepare.xid")?;
+    let database = reader.read_u32("xl_xact_prepare.database")?;
+    let prepared_at = reader.read::<TimestampTz>("xl_xact_prepare.prepared_at")?;
+    let owner = reader.read_u32("xl_xact_prepare.owner")?;
+    let nsubxacts = reader.read::<i32>("xl_xact_prepare.nsubxacts")?;
+    let ncommitrels = reader.read::<i32>("xl_xact_prepare.ncommitrels")?;
+    let nabortrels = reader.read::<i32>("xl_xact_prepare.nabortrels")?;
+    reader.skip(8, "xl_xact_prepare.ncommitstats and nabortstats")?;
+    let ninvalmsgs = reader.read::<i32>("xl_xact_prepare.ninvalmsgs")?;
+    reader.skip(2, "xl_xact_prepare.initfileinval")?;
+    let gidlen = reader.read_u16("xl_xact_prepare.gidlen")? as usize;
+    reader.skip(PREPARE_HEADER_SIZE - reader.offset(), "xl_xact_prepare.origin")?;
+
+    /* gidlen counts the terminating NUL */
+    let gid = reader.read_bytes(gidlen, "xl_xact_prepare gid")?;
+    let gid = gid.split(|byte| *byte == 0).next().unwrap_or_default();
+
+    Ok(XactPrepare {
+        xid,
+        database,
+        prepared_at,
+        owner,
+        nsubxacts,
+        ncommitrels,
+        nabortrels,
+        ninvalmsgs,
+        gid: String::from_utf8_lossy(gid).into_owned(),
+    })
+}
+
+fn read_xids(reader: &mut ByteReader, count: u32, field: &'static str) -> Result<Vec<TransactionId>, DecodeError> {
+    (0..count).map(|_| reader.read::<TransactionId>(field)).collect()
+}
@@ -0,0 +1,206 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::common::timestamp::TimestampTz;
+use crate::postgres::common::transaction_id::{FullTransactionId, TransactionId};
+use crate::postgres::records::Oid;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
+
+/* XLOG info values for XLOG rmgr, from pg_control.h */
+pub const XLOG_CHECKPOINT_SHUTDOWN: u8 = 0x00;
+pub const XLOG_CHECKPOINT_ONLINE: u8 = 0x10;
+pub const XLOG_NOOP: u8 = 0x20;
+pub const XLOG_NEXTOID: u8 = 0x30;
+pub const XLOG_SWITCH: u8 = 0x40;
+pub const XLOG_BACKUP_END: u8 = 0x50;
+pub const XLOG_PARAMETER_CHANGE: u8 = 0x60;
+pub const XLOG_RESTORE_POINT: u8 = 0x70;
+pub const XLOG_FPW_CHANGE: u8 = 0x80;
+pub const XLOG_END_OF_RECOVERY: u8 = 0x90;
+pub const XLOG_FPI_FOR_HINT: u8 = 0xA0;
+pub const XLOG_FPI: u8 = 0xB0;
+pub const XLOG_OVERWRITE_CONTRECORD: u8 = 0xD0;
+
+/* MAXFNAMELEN, the size of xl_restore_point.rp_name */
+const RESTORE_POINT_NAME_LEN: usize = 64;
+
+/// Names an XLOG record type the way pg_waldump does, e.g. "CHECKPOINT_ONLINE".
+pub fn identify(info: u8) -> &'static str {
+    match info {
+        XLOG_CHECKPOINT_SHUTDOWN => "CHECKPOINT_SHUTDOWN",
+        XLOG_CHECKPOINT_ONLINE => "CHECKPOINT_ONLINE",
+        XLOG_NOOP => "NOOP",
+        XLOG_NEXTOID => "NEXTOID",
+        XLOG_SWITCH => "SWITCH",
+        XLOG_BACKUP_END => "BACKUP_END",
+        XLOG_PARAMETER_CHANGE => "PARAMETER_CHANGE",
+        XLOG_RESTORE_POINT => "RESTORE_POINT",
+        XLOG_FPW_CHANGE => "FPW_CHANGE",
+        XLOG_END_OF_RECOVERY => "END_OF_RECOVERY",
+        XLOG_FPI_FOR_HINT => "FPI_FOR_HINT",
+        XLOG_FPI => "FPI",
+        XLOG_OVERWRITE_CONTRECORD => "OVERWRITE_CONTRECORD",
+        _ => "UNKNOWN",
+    }
+}
+
+/// CheckPoint: the control file contents a checkpoint record carries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckPoint {
+    /// Where replay starts from if this checkpoint is used for recovery
+    pub redo: u64,
+    pub this_timeline: u32,
+    pub prev_timeline: u32,
+    pub full_page_writes: bool,
+    pub next_xid: FullTransactionId,
+    pub next_oid: Oid,
+    pub next_multi: u32,
+    pub next_multi_offset: u32,
+    pub oldest_xid: TransactionId,
+    pub oldest_xid_db: Oid,
+    pub oldest_multi: u32,
+    pub oldest_multi_db: Oid,
+    /// Unix time the checkpoint was taken at, in seconds
+    pub time: i64,
+    pub oldest_commit_ts_xid: TransactionId,
+    pub newest_commit_ts_xid: TransactionId,
+    pub oldest_active_xid: TransactionId,
+}
+
+/// xl_parameter_change: settings a standby needs to match changed on the primary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterChange {
+    pub max_connections: i32,
+    pub max_worker_processes: i32,
+    pub max_wal_senders: i32,
+    pub max_prepared_xacts: i32,
+    pub max_locks_per_xact: i32,
+    /// 0 for minimal, 1 for replica, 2 for logical
+    pub wal_level: i32,
+    pub wal_log_hints: bool,
+    pub track_commit_timestamp: bool,
+}
+
+/// xl_end_of_recovery: recovery ended and a new timeline began.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndOfRecovery {
+    pub end_time: TimestampTz,
+    pub this_timeline: u32,
+    pub prev_timeline: u32,
+    pub wal_level: i32,
+}
+
+/// A decoded XLOG record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XLogRecord {
+    CheckpointShutdown(CheckPoint),
+    CheckpointOnline(CheckPoint),
+    Noop,
+    NextOid { next_oid: Oid },
+    /// The rest of the segment is unused; the next record starts in the next segment
+    Switch,
+    /// The end of an online backup that began at `start_lsn`
+    BackupEnd { start_lsn: u64 },
+    ParameterChange(ParameterChange),
+    RestorePoint { time: TimestampTz, name: String },
+    FpwChange { full_page_writes: bool },
+    EndOfRecovery(EndOfRecovery),
+    /// A full-page image taken because a hint bit was set, with checksums or wal_log_hints on
+    FpiForHint,
+    /// Full-page images of the referenced blocks, e.g. from CREATE INDEX or CREATE DATABASE
+    Fpi,
+    /// A missing continuation record at `overwritten_lsn` was skipped
+    OverwriteContrecord { overwritten_lsn: u64, overwrite_time: TimestampTz },
+}
+
+impl XLogRecord {
+    /// Decodes the main data of an XLOG record with rmgr info bits `info`.
+    pub fn decode(info: u8, main_data: &[u8]) -> Result<XLogRecord, DecodeError> {
+        let mut reader = ByteReader::new(main_data);
+
+        Ok(match info {
+            XLOG_CHECKPOINT_SHUTDOWN => XLogRecord::CheckpointShutdown(read_checkpoint(&mut reader)?),
+            XLOG_CHECKPOINT_ONLINE => XLogRecord::CheckpointOnline(read_checkpoint(&mut reader)?),
+            XLOG_NOOP => XLogRecord::Noop,
+            XLOG_NEXTOID => XLogRecord::NextOid { next_oid: reader.read_u32("nextOid")? },
+            XLOG_SWITCH => XLogRecord::Switch,
+            XLOG_BACKUP_END => XLogRecord::BackupEnd { start_lsn: reader.read_u64("startpoint")? },
+            XLOG_PARAMETER_CHANGE => XLogRecord::ParameterChange(ParameterChange {
+                max_connections: reader.read::<i32>("xl_parameter_change.MaxConnections")?,
+                max_worker_processes: reader.read::<i32>("xl_parameter_change.max_worker_processes")?,
+                max_wal_senders: reader.read::<i32>("xl_parameter_change.max_wal_senders")?,
+                max_prepared_xacts: reader.read::<i32>("xl_parameter_change.max_prepared_xacts")?,
+                max_locks_per_xact: reader.read::<i32>("xl_parameter_change.max_locks_per_xact")?,
+                wal_level: reader.read::<i32>("xl_parameter_change.wal_level")?,
+                wal_log_hints: reader.read_u8("xl_parameter_change.wal_log_hints")? != 0,
+                track_commit_timestamp: reader.read_u8("xl_parameter_change.track_commit_timestamp")? != 0,
+            }),
+            XLOG_RESTORE_POINT => {
+                let time = reader.read::<TimestampTz>("xl_restore_point.rp_time")?;
+                let name = reader.read_bytes(RESTORE_POINT_NAME_LEN, "xl_restore_point.rp_name")?;
+                let name = name.split(|byte| *byte == 0).next().unwrap_or_default();
+
+                XLogRecord::RestorePoint { time, name: String::from_utf8_lossy(name).into_owned() }
+            },
+            XLOG_FPW_CHANGE => XLogRecord::FpwChange { full_page_writes: reader.read_u8("fpw")? != 0 },
+            XLOG_END_OF_RECOVERY => XLogRecord::EndOfRecovery(EndOfRecovery {
+                end_time: reader.read::<TimestampTz>("xl_end_of_recovery.end_time")?,
+                this_timeline: reader.read_u32("xl_end_of_recovery.ThisTimeLineID")?,
+                prev_timeline: reader.read_u32("xl_end_of_recovery.PrevTimeLineID")?,
+                wal_level: reader.read::<i32>("xl_end_of_recovery.wal_level")?,
+            }),
+            XLOG_FPI_FOR_HINT => XLogRecord::FpiForHint,
+            XLOG_FPI => XLogRecord::Fpi,
+            XLOG_OVERWRITE_CONTRECORD => XLogRecord::OverwriteContrecord {
+                overwritten_lsn: reader.read_u64("xl_overwrite_contrecord.overwritten_lsn")?,
+                overwrite_time: reader.read::<TimestampTz>("xl_overwrite_contrecord.overwrite_time")?,
+            },
+            _ => return Err(DecodeError::UnknownRecordType { rmgr: ResourceManager::XLOG, info }),
+        })
+    }
+
+    /// The checkpoint contents, for either kind of checkpoint record.
+    pub fn checkpoint(&self) -> Option<&CheckPoint> {
+        match self {
+            XLogRecord::CheckpointShutdown(checkpoint) | XLogRecord::CheckpointOnline(checkpoint) => Some(checkpoint),
+            _ => None,
+        }
+    }
+}
+
+fn read_checkpoint(reader: &mut ByteReader) -> Result<CheckPoint, DecodeError> {
+    let redo = reader.read_u64("CheckPoint.redo")?;
+    let this_timeline = reader.read_u32("CheckPoint.ThisTimeLineID")?;
+    let prev_timeline = reader.read_u32("CheckPoint.PrevTimeLineID")?;
+    let full_page_writes = reader.read_u8("CheckPoint.fullPageWrites")? != 0;
+    /* nextXid is 8-byte aligned */
+    reader.skip(7, "CheckPoint padding")?;
+    let next_xid = reader.read::<FullTransactionId>("CheckPoint.nextXid")?;
+    let next_oid = reader.read_u32("CheckPoint.nextOid")?;
+    let next_multi = reader.read_u32("CheckPoint.nextMulti")?;
+    let next_multi_offset = reader.read_u32("CheckPoint.nextMultiOffset")?;
+    let oldest_xid = reader.read::<TransactionId>("CheckPoint.oldestXid")?;
+    let oldest_xid_db = reader.read_u32("CheckPoint.oldestXidDB")?;
+    let oldest_multi = reader.read_u32("CheckPoint.oldestMulti")?;
+    let oldest_multi_db = reader.read_u32("CheckPoint.oldestMultiDB")?;
+    /* time is 8-byte aligned */
+    reader.skip(4, "CheckPoint padding")?;
+
+    Ok(CheckPoint {
+        redo,
+        this_timeline,
+        prev_timeline,
+        full_page_writes,
+        next_xid,
+        next_oid,
+        next_multi,
+        next_multi_offset,
+        oldest_xid,
+        oldest_xid_db,
+        oldest_multi,
+        oldest_multi_db,
+        time: reader.read::<i64>("CheckPoint.time")?,
+        oldest_commit_ts_xid: reader.read::<TransactionId>("CheckPoint.oldestCommitTsXid")?,
+        newest_commit_ts_xid: reader.read::<TransactionId>("CheckPoint.newestCommitTsXid")?,
+        oldest_active_xid: reader.read::<TransactionId>("CheckPoint.oldestActiveXid")?,
+    })
+}
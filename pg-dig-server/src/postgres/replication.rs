@@ -1,60 +1,330 @@
-#![allow(non_camel_case_types)]
-#![allow(non_snake_case)]
-#![allow(non_upper_case_globals)]
+use crate::config::{Config, StartPosition};
+use crate::postgres::capture::CaptureWriter;
+use crate::postgres::common::lsn::Lsn;
+use crate::postgres::common::timestamp;
+use crate::postgres::connection::{connect, CopyData, Interrupter, ReplicationConnection, StreamEnd};
+use crate::postgres::error::PgDigError;
+use crate::postgres::feedback::{FeedbackTracker, PrimaryKeepalive, StandbyStatusUpdate};
+use crate::postgres::slot::{ensure_slot, ReplicationSlot};
+use crate::postgres::source::WalSource;
+use crate::postgres::timeline::{identify_system, timeline_history, SystemIdentification, TimelineHistory};
+use crate::postgres::xlog::constants::XLOG_BLCKSZ;
+use crate::postgres::xlog::reassembler::{ReassembledRecord, RecordReassembler};
+use crate::postgres::xlog_message::{XLogMessage, XLogMessageHeader};
+use log::{debug, info, warn};
+use std::fs::File;
+use std::io::BufWriter;
+use std::time::Instant;
 
-use crate::postgres::bindings::*;
-use crate::postgres::pg_conn::friendly_exec_status;
-use crate::postgres::query::exec;
-use crate::postgres::xlog_message::XLogMessage;
-use std::ffi::{c_char, CStr};
+/// Works out the LSN that `config.start_position` refers to.
+pub fn resolve_start_lsn(config: &Config, system: &SystemIdentification, slot: &ReplicationSlot) -> Result<u64, PgDigError> {
+    match config.start_position {
+        StartPosition::Lsn(lsn) => Ok(lsn),
+        StartPosition::CurrentInsert => Ok(system.xlogpos),
+        StartPosition::SlotRestartLsn => slot.restart_lsn
+            .ok_or_else(|| PgDigError::Config(format!("slot {} has no restart_lsn", slot.name))),
+    }
+}
 
-const replication_slot_name: &str = "physical";
-const start_lsn: &str = "0/1000000";
+/// Picks the timeline to stream `lsn` from, checking that the server actually has it there.
+///
+/// When no timeline is configured, it is the one `lsn` was written on according to `history`.
+pub fn choose_timeline(
+    lsn: u64,
+    requested: Option<u32>,
+    system: &SystemIdentification,
+    history: &TimelineHistory,
+) -> Result<u32, PgDigError> {
+    if lsn > system.xlogpos {
+        return Err(PgDigError::Config(format!(
+            "start LSN {} is ahead of the server's WAL position {}",
+            Lsn::from_u64(lsn),
+            Lsn::from_u64(system.xlogpos)
+        )));
+    }
 
-pub unsafe fn start_replication(conn: *mut PGconn) -> Result<(), String> {
-    let stmt = format!("START_REPLICATION SLOT {} PHYSICAL {}", replication_slot_name, start_lsn);
+    let timeline = requested.unwrap_or_else(|| history.timeline_containing(lsn, system.timeline));
+
+    if timeline == system.timeline {
+        return Ok(timeline);
+    }
 
-    let result = exec(conn, stmt.as_str());
+    if timeline > system.timeline {
+        return Err(PgDigError::Config(format!(
+            "timeline {} is ahead of the server's timeline {}",
+            timeline, system.timeline
+        )));
+    }
 
-    match PQresultStatus(result) {
-        ExecStatusType_PGRES_COPY_BOTH => Ok(()),
-        other => Err(friendly_exec_status(other)),
+    match history.switchpoint(timeline) {
+        Some(switchpoint) if lsn < switchpoint => Ok(timeline),
+        Some(switchpoint) => Err(PgDigError::Config(format!(
+            "timeline {} ended at {}, before start LSN {}",
+            timeline,
+            Lsn::from_u64(switchpoint),
+            Lsn::from_u64(lsn)
+        ))),
+        None => Err(PgDigError::Config(format!(
+            "timeline {} is not in the history of timeline {}",
+            timeline, system.timeline
+        ))),
     }
 }
 
-pub unsafe fn read_message(conn: *mut PGconn) -> Result<XLogMessage, String> {
-    let mut buffer= vec![0; 1024];
-    let mut buffer_ptr: *mut c_char = buffer.as_mut_ptr();
+/// Starts streaming WAL with the slot, start position and timeline in `config`.
+///
+/// The server is identified first, so the start position can be checked against its WAL and
+/// timeline history, and the slot is created if it is missing. Streaming begins at the start of
+/// the page holding the requested LSN, so the page header can tell us whether a record is already
+/// in progress there.
+pub fn start_replication(conn: &mut dyn ReplicationConnection, config: &Config) -> Result<ReplicationState, PgDigError> {
+    let system = identify_system(conn)?;
+    let history = match system.timeline {
+        1 => TimelineHistory::default(),
+        timeline => timeline_history(conn, timeline)?,
+    };
+
+    let slot = ensure_slot(conn, config)?;
+    let lsn = resolve_start_lsn(config, &system, &slot)?;
+    let timeline = choose_timeline(lsn, config.timeline, &system, &history)?;
+    let page_start = lsn - lsn % XLOG_BLCKSZ as u64;
+
+    let capture = match &config.capture_path {
+        Some(path) => Some(CaptureWriter::create(path, &system)?),
+        None => None,
+    };
+
+    stream_from(conn, &config.slot_name, page_start, timeline)?;
+
+    Ok(ReplicationState {
+        reassembler: RecordReassembler::new(),
+        feedback: FeedbackTracker::new(config.status_interval),
+        send_time: 0,
+        last_record_end: 0,
+        start_lsn: lsn,
+        slot_name: config.slot_name.clone(),
+        system,
+        timeline,
+        capture,
+    })
+}
+
+fn stream_from(conn: &mut dyn ReplicationConnection, slot_name: &str, lsn: u64, timeline: u32) -> Result<(), PgDigError> {
+    let stmt = format!(
+        "START_REPLICATION SLOT {} PHYSICAL {} TIMELINE {}",
+        slot_name,
+        Lsn::from_u64(lsn),
+        timeline
+    );
+
+    conn.start_copy_both(stmt.as_str())
+}
+
+/// ReplicationState holds what `read_message` carries over between calls.
+pub struct ReplicationState {
+    reassembler: RecordReassembler,
+    feedback: FeedbackTracker,
+    send_time: u64,
+    last_record_end: u64,
+    start_lsn: u64,
+    slot_name: String,
+    system: SystemIdentification,
+    timeline: u32,
+    capture: Option<CaptureWriter<BufWriter<File>>>,
+}
+
+impl ReplicationState {
+    /// Marks everything before `lsn` as handled, letting the server recycle that WAL.
+    pub fn confirm(&mut self, lsn: u64) {
+        self.feedback.confirm(lsn);
+    }
+
+    /// The end of the last record returned by `read_message`, including skipped ones.
+    pub fn last_record_end(&self) -> u64 {
+        self.last_record_end
+    }
+
+    /// The LSN streaming was asked to start from, before rounding down to its page.
+    pub fn start_lsn(&self) -> u64 {
+        self.start_lsn
+    }
+
+    /// What IDENTIFY_SYSTEM reported when streaming started.
+    pub fn system(&self) -> &SystemIdentification {
+        &self.system
+    }
+
+    /// The timeline being streamed.
+    pub fn timeline(&self) -> u32 {
+        self.timeline
+    }
+}
+
+fn send_status_update(conn: &mut dyn ReplicationConnection, update: &StandbyStatusUpdate) -> Result<(), PgDigError> {
+    conn.put_copy_data(&update.to_bytes())
+}
+
+/// Sends the periodic status update if it is due, flushing the capture along with it.
+fn send_status_update_if_due(conn: &mut dyn ReplicationConnection, state: &mut ReplicationState) -> Result<(), PgDigError> {
+    let now = Instant::now();
+
+    if state.feedback.is_due(now) {
+        send_status_update(conn, &state.feedback.status_update(now))?;
+
+        if let Some(capture) = state.capture.as_mut() {
+            capture.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the header of an XLogData message body and hands its WAL to `reassembler`.
+pub(crate) fn push_xlog_data(reassembler: &mut RecordReassembler, body: &[u8]) -> Result<XLogMessageHeader, PgDigError> {
+    let header = XLogMessageHeader::from_bytes(body)?;
+    reassembler.push(header.start_lsn, &body[size_of::<XLogMessageHeader>()..]);
+    Ok(header)
+}
+
+/// Turns a reassembled record into a message.
+pub(crate) fn decode_record(record: ReassembledRecord, send_time: u64) -> Result<XLogMessage, PgDigError> {
+    let header = XLogMessageHeader {
+        start_lsn: record.start_lsn,
+        end_lsn: record.end_lsn,
+        send_time,
+    };
+    Ok(XLogMessage::from_record(header, &record.bytes)?)
+}
 
+/// Reads the next WAL record from the replication stream.
+///
+/// A single XLogData message can hold many records, and a record can be split over several
+/// messages, so records are reassembled in `state` and handed out one at a time, starting with
+/// the first at or after the requested start LSN. Status updates are sent from here too,
+/// periodically and whenever the server asks for one. When the server ends the stream because
+/// its timeline was switched away from, streaming carries on from the start of the next timeline.
+///
+/// While there is nothing to read it waits on the connection's socket, waking up in time to
+/// send the next periodic status update.
+///
+/// `conn` must be a connection that `start_replication` has put into COPY BOTH mode.
+pub fn read_message(conn: &mut dyn ReplicationConnection, state: &mut ReplicationState) -> Result<XLogMessage, PgDigError> {
     loop {
-        if PQconsumeInput(conn) == 0 {
-            return Err(String::from("failed to consume input"))
+        send_status_update_if_due(conn, state)?;
+
+        if let Some(record) = state.reassembler.next_record() {
+            let record = record?;
+            state.last_record_end = record.end_lsn;
+
+            /* streaming began at the start of the page, before the LSN that was asked for */
+            if record.start_lsn < state.start_lsn {
+                continue;
+            }
+            return decode_record(record, state.send_time);
         }
 
-        // Handle errors
-        match PQgetCopyData(conn, &mut buffer_ptr, 0) {
-            length if length > 0 => {},
-            -1 => return Err(String::from("end of stream")),
-            -2 => {
-                let error = PQerrorMessage(conn);
-                let error_message = format!("replication failed!. Reason: {}", CStr::from_ptr(error).to_str().expect("failed to parse error message"));
-                return Err(String::from(error_message));
+        let buffer = match conn.get_copy_data()? {
+            CopyData::Message(buffer) => buffer,
+            CopyData::Pending => {
+                conn.wait_readable(state.feedback.time_until_due(Instant::now()))?;
+                continue;
+            },
+            CopyData::Done => match conn.end_copy()? {
+                StreamEnd::TimelineSwitch { timeline, start_lsn } => {
+                    info!("timeline {} ended, following timeline {} from {}", state.timeline, timeline, Lsn::from_u64(start_lsn));
+                    stream_from(conn, &state.slot_name, start_lsn, timeline)?;
+                    state.timeline = timeline;
+                    state.reassembler.reset(start_lsn);
+                    continue;
+                },
+                StreamEnd::Finished => return Err(PgDigError::EndOfStream),
             },
-            unknown_code => panic!("unknown code from PQgetCopyData: {}", unknown_code)
         };
 
+        if let Some(capture) = state.capture.as_mut() {
+            capture.write_message(timestamp::now(), &buffer)?;
+        }
+
         // Handle message
-        match *buffer_ptr as u8 as char {
-            'w' => match XLogMessage::from_ptr(buffer_ptr.add(1) as *const u8) {
-                Ok(result) => {
-                    return Ok(result)
-                },
-                Err(_)=> {  }
-            } ,
-            'k' => println!("*keep-alive*"),
-            record_code => return Err(String::from(format!("unexpected record type: {}", record_code)))
+        match buffer.first() {
+            Some(b'w') => {
+                let header = push_xlog_data(&mut state.reassembler, &buffer[1..])?;
+                state.send_time = header.send_time;
+
+                if let Some(received) = state.reassembler.next_lsn() {
+                    state.feedback.received(received);
+                }
+            },
+            Some(b'k') => {
+                debug!("keepalive");
+                let keepalive = PrimaryKeepalive::from_bytes(&buffer[1..])?;
+
+                if keepalive.reply_requested {
+                    send_status_update(conn, &state.feedback.status_update(Instant::now()))?;
+                }
+            },
+            Some(record_code) => {
+                return Err(PgDigError::Protocol(format!("unexpected record type: {}", *record_code as char)))
+            },
+            None => return Err(PgDigError::Protocol(String::from("empty CopyData message"))),
         };
     }
 }
 
+/// LiveSource streams WAL from a server over physical replication.
+pub struct LiveSource {
+    conn: Box<dyn ReplicationConnection>,
+    state: ReplicationState,
+}
+
+impl LiveSource {
+    /// Connects to the server in `config` and starts streaming.
+    pub fn connect(config: &Config) -> Result<LiveSource, PgDigError> {
+        let mut conn = connect(&config.connection_string)?;
+        let state = start_replication(conn.as_mut(), config)?;
+        Ok(LiveSource { conn, state })
+    }
 
+    pub fn state(&self) -> &ReplicationState {
+        &self.state
+    }
+
+    /// Describes the state of the connection, e.g. "connection ok".
+    pub fn connection_status(&self) -> String {
+        self.conn.status()
+    }
+
+    /// Hands over the capture being written, so another connection can carry on with it.
+    pub fn take_capture(&mut self) -> Option<CaptureWriter<BufWriter<File>>> {
+        self.state.capture.take()
+    }
+
+    /// Writes the messages of this connection to `capture`, after those already in it.
+    pub fn resume_capture(&mut self, capture: CaptureWriter<BufWriter<File>>) {
+        self.state.capture = Some(capture);
+    }
+}
+
+impl WalSource for LiveSource {
+    fn next_message(&mut self) -> Result<XLogMessage, PgDigError> {
+        read_message(self.conn.as_mut(), &mut self.state)
+    }
+
+    fn confirm(&mut self, lsn: u64) {
+        self.state.confirm(lsn);
+    }
+
+    fn last_record_end(&self) -> u64 {
+        self.state.last_record_end()
+    }
+
+    fn keep_alive(&mut self) -> Result<(), PgDigError> {
+        send_status_update_if_due(self.conn.as_mut(), &mut self.state)
+    }
+
+    fn interrupter(&self) -> Option<Interrupter> {
+        self.conn.interrupter()
+            .map_err(|e| warn!("replication can't be interrupted: {}", e))
+            .ok()
+    }
+}
@@ -0,0 +1,87 @@
+use crate::config::Config;
+use crate::postgres::connection::ReplicationConnection;
+use crate::postgres::error::PgDigError;
+use crate::postgres::timeline::{parse_column, parse_lsn_column};
+use log::info;
+
+/// A physical replication slot, as READ_REPLICATION_SLOT or CREATE_REPLICATION_SLOT report it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicationSlot {
+    pub name: String,
+    /// The oldest WAL the slot holds back, or None if it has never reserved any
+    pub restart_lsn: Option<u64>,
+    /// The timeline of `restart_lsn`
+    pub restart_tli: Option<u32>,
+    /// Temporary slots are dropped by the server when the connection closes
+    pub temporary: bool,
+}
+
+/// Reads a slot with READ_REPLICATION_SLOT. Returns None if there is no slot called `name`.
+///
+/// `conn` must not be streaming.
+pub fn read_replication_slot(conn: &mut dyn ReplicationConnection, name: &str) -> Result<Option<ReplicationSlot>, PgDigError> {
+    let stmt = format!("READ_REPLICATION_SLOT {}", name);
+    let row = conn.query_row(stmt.as_str(), &["slot_type", "restart_lsn", "restart_tli"])?;
+
+    match row[0].as_deref() {
+        None => Ok(None),
+        Some("physical") => Ok(Some(ReplicationSlot {
+            name: name.to_string(),
+            restart_lsn: row[1].as_ref().map(|_| parse_lsn_column(&row[1], "restart_lsn")).transpose()?,
+            restart_tli: row[2].as_ref().map(|_| parse_column(&row[2], "restart_tli")).transpose()?,
+            temporary: false,
+        })),
+        Some(slot_type) => Err(PgDigError::Config(format!("slot {} is a {} slot, not a physical one", name, slot_type))),
+    }
+}
+
+/// Creates a physical slot that reserves WAL straight away.
+///
+/// `conn` must not be streaming.
+pub fn create_replication_slot(conn: &mut dyn ReplicationConnection, name: &str, temporary: bool) -> Result<ReplicationSlot, PgDigError> {
+    let stmt = format!(
+        "CREATE_REPLICATION_SLOT {}{} PHYSICAL RESERVE_WAL",
+        name,
+        if temporary { " TEMPORARY" } else { "" }
+    );
+    let row = conn.query_row(stmt.as_str(), &["consistent_point"])?;
+
+    Ok(ReplicationSlot {
+        name: name.to_string(),
+        restart_lsn: Some(parse_lsn_column(&row[0], "consistent_point")?),
+        restart_tli: None,
+        temporary,
+    })
+}
+
+/// Drops a slot. The slot must not be in use.
+///
+/// `conn` must not be streaming.
+pub fn drop_replication_slot(conn: &mut dyn ReplicationConnection, name: &str) -> Result<(), PgDigError> {
+    let stmt = format!("DROP_REPLICATION_SLOT {}", name);
+    conn.exec_command(stmt.as_str())
+}
+
+/// Gets the slot `config` asks for, creating it when it is missing and allowed to.
+///
+/// In temporary mode a fresh slot is always created, and the server drops it as soon as the
+/// connection closes, so nothing is left behind holding WAL on exit.
+///
+/// `conn` must not be streaming.
+pub fn ensure_slot(conn: &mut dyn ReplicationConnection, config: &Config) -> Result<ReplicationSlot, PgDigError> {
+    if config.temporary_slot {
+        return create_replication_slot(conn, &config.slot_name, true);
+    }
+
+    match read_replication_slot(conn, &config.slot_name)? {
+        Some(slot) => Ok(slot),
+        None if config.create_slot => {
+            info!("creating replication slot {}", config.slot_name);
+            create_replication_slot(conn, &config.slot_name, false)
+        },
+        None => Err(PgDigError::Config(format!(
+            "replication slot {} does not exist and creating it is turned off",
+            config.slot_name
+        ))),
+    }
+}
@@ -0,0 +1,106 @@
+use crate::config::Config;
+use crate::postgres::capture::ReplaySource;
+use crate::postgres::connection::Interrupter;
+use crate::postgres::error::PgDigError;
+use crate::postgres::replication::LiveSource;
+use crate::postgres::supervisor::{StateListener, SupervisedSource};
+use crate::postgres::wal_file::WalFileReader;
+use crate::postgres::xlog_message::XLogMessage;
+use log::debug;
+
+/// WalSource is anywhere WAL records can be read from: a live server, segment files or a
+/// recording.
+///
+/// Consumers should normally go through `messages`, which skips records that can't be decoded
+/// and confirms each message once the next one is asked for.
+pub trait WalSource {
+    /// Reads the next record. Returns `EndOfStream` when the source has no more WAL.
+    fn next_message(&mut self) -> Result<XLogMessage, PgDigError>;
+
+    /// Marks everything before `lsn` as handled. Sources that hold WAL back for us, like a
+    /// replication slot, can then release it.
+    fn confirm(&mut self, _lsn: u64) {}
+
+    /// The end of the last record read, including ones that failed to decode.
+    fn last_record_end(&self) -> u64;
+
+    /// Called now and then while the consumer is too busy to ask for messages, so that a live
+    /// connection can keep reporting progress and isn't timed out by the server.
+    fn keep_alive(&mut self) -> Result<(), PgDigError> {
+        Ok(())
+    }
+
+    /// Something another thread can use to wake the source while it waits for WAL, for sources
+    /// that can block for a long time.
+    fn interrupter(&self) -> Option<Interrupter> {
+        None
+    }
+
+    fn messages(&mut self) -> Messages<'_>
+    where
+        Self: Sized,
+    {
+        Messages::new(self)
+    }
+}
+
+/// Opens the source `config` points at: a capture to replay, WAL files, or else the server.
+///
+/// A server connection is supervised unless reconnecting is turned off, and `listener` hears
+/// about its connection state.
+pub fn open_source(config: &Config, listener: StateListener) -> Result<Box<dyn WalSource>, PgDigError> {
+    if let Some(replay_path) = &config.replay_path {
+        return Ok(Box::new(ReplaySource::open(replay_path, config.replay_speed)?));
+    }
+
+    match (&config.wal_path, config.reconnect) {
+        (Some(wal_path), _) => Ok(Box::new(WalFileReader::open(wal_path, config.segment_size)?)),
+        (None, true) => Ok(Box::new(SupervisedSource::connect(config, listener)?)),
+        (None, false) => Ok(Box::new(LiveSource::connect(config)?)),
+    }
+}
+
+/// Messages iterates over the decodable records of a source until it ends.
+///
+/// Skippable errors are passed over. Any other error is returned once, after which the
+/// iterator should be dropped.
+pub struct Messages<'a> {
+    source: &'a mut dyn WalSource,
+    /// The end of the message handed out last, confirmed when the next one is requested
+    unconfirmed: Option<u64>,
+}
+
+impl<'a> Messages<'a> {
+    pub fn new(source: &'a mut dyn WalSource) -> Self {
+        Messages {
+            source,
+            unconfirmed: None,
+        }
+    }
+}
+
+impl Iterator for Messages<'_> {
+    type Item = Result<XLogMessage, PgDigError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(lsn) = self.unconfirmed.take() {
+            self.source.confirm(lsn);
+        }
+
+        loop {
+            match self.source.next_message() {
+                Ok(message) => {
+                    self.unconfirmed = Some(message.header.end_lsn);
+                    return Some(Ok(message));
+                },
+                Err(PgDigError::EndOfStream) => return None,
+                Err(e) if e.is_skippable() => {
+                    debug!("skipping record: {}", e);
+                    let lsn = self.source.last_record_end();
+                    self.source.confirm(lsn);
+                },
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use crate::config::{Config, StartPosition};
+use crate::postgres::capture::CaptureWriter;
+use crate::postgres::common::lsn::Lsn;
+use crate::postgres::connection::Interrupter;
+use crate::postgres::error::PgDigError;
+use crate::postgres::replication::LiveSource;
+use crate::postgres::source::WalSource;
+use crate::postgres::xlog_message::XLogMessage;
+use log::{info, warn};
+use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// How long to wait before the first reconnect attempt.
+pub const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// The longest wait between reconnect attempts, unless configured otherwise.
+pub const DEFAULT_MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Where a supervised connection is at.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    /// Connecting to the server. `attempt` is 0 for the first connection, then counts
+    /// reconnect attempts since streaming last worked.
+    Connecting { attempt: u32 },
+    /// Streaming from `start_lsn`. `status` is what the connection says about itself,
+    /// e.g. "connection ok".
+    Connected { status: String, start_lsn: u64 },
+    /// The connection was lost or could not be made. `status` is what the lost connection said
+    /// about itself, if there was one, and `retry_in` is when the next attempt is made.
+    Disconnected { status: Option<String>, error: String, retry_in: Option<Duration> },
+}
+
+impl fmt::Display for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionState::Connecting { attempt: 0 } => write!(f, "connecting"),
+            ConnectionState::Connecting { attempt } => write!(f, "reconnecting, attempt {}", attempt),
+            ConnectionState::Connected { status, start_lsn } => {
+                write!(f, "{}, streaming from {}", status, Lsn::from_u64(*start_lsn))
+            },
+            ConnectionState::Disconnected { status, error, retry_in } => {
+                write!(f, "{}: {}", status.as_deref().unwrap_or("disconnected"), error)?;
+
+                match retry_in {
+                    Some(delay) => write!(f, ", retrying in {:.1}s", delay.as_secs_f32()),
+                    None => Ok(()),
+                }
+            },
+        }
+    }
+}
+
+/// Called with every change of connection state, e.g. to show it in the UI.
+pub type StateListener = Box<dyn FnMut(&ConnectionState) + Send>;
+
+/// Backoff works out how long to wait between reconnect attempts: the wait doubles after each
+/// attempt, up to `max`.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    attempts: u32,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Backoff { initial, max, attempts: 0 }
+    }
+
+    /// The wait before the next attempt, counting it as made.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.initial.saturating_mul(1 << self.attempts.min(16)).min(self.max);
+        self.attempts += 1;
+        delay
+    }
+
+    /// How many attempts have been made since the last reset.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Starts over from the initial wait, once a connection has worked.
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+    }
+}
+
+/// Whether reconnecting could get streaming going again after this error. Only a lost or
+/// refused connection and the end of the stream are; an error the server reports for a command,
+/// such as a missing slot or timeline, would only come back on the next connection.
+fn is_recoverable(error: &PgDigError) -> bool {
+    matches!(error, PgDigError::Connection(_) | PgDigError::EndOfStream)
+}
+
+fn cancelled() -> PgDigError {
+    PgDigError::Connection(String::from("replication was cancelled"))
+}
+
+/// What the supervisor shares with its Interrupter.
+#[derive(Default)]
+struct Shared {
+    state: Mutex<InterruptState>,
+    wake: Condvar,
+}
+
+#[derive(Default)]
+struct InterruptState {
+    cancelled: bool,
+    /// Interrupts the current connection
+    connection: Option<Interrupter>,
+}
+
+/// SupervisedSource streams from a server like LiveSource, but reconnects when the connection
+/// is lost.
+///
+/// Streaming restarts from the last confirmed LSN, so nothing the consumer hasn't finished with
+/// is lost. Records that were already handed out before the connection dropped are passed over
+/// when they come round again. A capture carries on across connections in the same file.
+/// Attempts are spaced out with exponential backoff, and every change of state goes to the log
+/// and to the listener.
+///
+/// Only the first connection is not retried: if it fails, the configuration is most likely
+/// wrong and the error is returned from `connect`.
+pub struct SupervisedSource {
+    config: Config,
+    source: Option<LiveSource>,
+    backoff: Backoff,
+    /// When the next reconnect attempt is due
+    retry_in: Duration,
+    /// Everything before this has been handled, and streaming resumes from here
+    resume_lsn: u64,
+    /// The end of the last record handed out, including skipped ones
+    delivered: u64,
+    /// The capture of a lost connection, waiting for the next one
+    capture: Option<CaptureWriter<BufWriter<File>>>,
+    listener: StateListener,
+    shared: Arc<Shared>,
+}
+
+impl SupervisedSource {
+    /// Connects to the server in `config` and starts streaming.
+    pub fn connect(config: &Config, listener: StateListener) -> Result<SupervisedSource, PgDigError> {
+        let mut supervisor = SupervisedSource {
+            config: config.clone(),
+            source: None,
+            backoff: Backoff::new(INITIAL_RECONNECT_DELAY, config.max_reconnect_delay),
+            retry_in: Duration::ZERO,
+            resume_lsn: 0,
+            delivered: 0,
+            capture: None,
+            listener,
+            shared: Arc::default(),
+        };
+
+        supervisor.report(ConnectionState::Connecting { attempt: 0 });
+
+        let source = match LiveSource::connect(config) {
+            Ok(source) => source,
+            Err(e) => {
+                supervisor.report(ConnectionState::Disconnected { status: None, error: e.to_string(), retry_in: None });
+                return Err(e);
+            },
+        };
+
+        supervisor.resume_lsn = source.state().start_lsn();
+        supervisor.attach(source)?;
+        Ok(supervisor)
+    }
+
+    fn report(&mut self, state: ConnectionState) {
+        match state {
+            ConnectionState::Disconnected { .. } => warn!("{}", state),
+            _ => info!("{}", state),
+        }
+
+        (self.listener)(&state);
+    }
+
+    /// Makes `source` the current connection, unless the supervisor has been cancelled.
+    fn attach(&mut self, source: LiveSource) -> Result<(), PgDigError> {
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            if state.cancelled {
+                return Err(cancelled());
+            }
+
+            state.connection = source.interrupter();
+        }
+
+        self.report(ConnectionState::Connected {
+            status: source.connection_status(),
+            start_lsn: source.state().start_lsn(),
+        });
+        self.source = Some(source);
+        Ok(())
+    }
+
+    /// Drops the current connection after `error`, and schedules the first attempt to replace it.
+    fn lose(&mut self, error: PgDigError) {
+        let status = self.source.take().map(|mut source| {
+            self.capture = source.take_capture();
+            source.connection_status()
+        });
+        self.shared.state.lock().unwrap().connection = None;
+
+        self.retry_in = self.backoff.next_delay();
+        self.report(ConnectionState::Disconnected {
+            status,
+            error: error.to_string(),
+            retry_in: Some(self.retry_in),
+        });
+    }
+
+    /// Waits out the backoff and tries to connect once. Only fails if the error can't be
+    /// recovered from, or the supervisor has been cancelled.
+    fn reconnect(&mut self) -> Result<(), PgDigError> {
+        {
+            let state = self.shared.state.lock().unwrap();
+            let (state, _) = self.shared.wake
+                .wait_timeout_while(state, self.retry_in, |state| !state.cancelled)
+                .unwrap();
+
+            if state.cancelled {
+                return Err(cancelled());
+            }
+        }
+
+        self.report(ConnectionState::Connecting { attempt: self.backoff.attempts() });
+
+        /* the capture file is already open, creating it again would truncate it */
+        let config = Config {
+            start_position: StartPosition::Lsn(self.resume_lsn),
+            timeline: None,
+            capture_path: None,
+            ..self.config.clone()
+        };
+
+        match LiveSource::connect(&config) {
+            Ok(mut source) => {
+                if let Some(capture) = self.capture.take() {
+                    source.resume_capture(capture);
+                }
+                self.attach(source)
+            },
+            Err(e) if is_recoverable(&e) => {
+                self.retry_in = self.backoff.next_delay();
+                self.report(ConnectionState::Disconnected { status: None, error: e.to_string(), retry_in: Some(self.retry_in) });
+                Ok(())
+            },
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl WalSource for SupervisedSource {
+    fn next_message(&mut self) -> Result<XLogMessage, PgDigError> {
+        loop {
+            let source = match self.source.as_mut() {
+                Some(source) => source,
+                None => {
+                    self.reconnect()?;
+                    continue;
+                },
+            };
+
+            match source.next_message() {
+                // handed out before the connection was lost
+                Ok(message) if message.header.end_lsn <= self.delivered => {},
+                Ok(message) => {
+                    self.delivered = message.header.end_lsn;
+                    self.backoff.reset();
+                    return Ok(message);
+                },
+                Err(e) if e.is_skippable() => {
+                    let end_lsn = source.last_record_end();
+                    if end_lsn > self.delivered {
+                        self.delivered = end_lsn;
+                        return Err(e);
+                    }
+                },
+                Err(e) if is_recoverable(&e) => self.lose(e),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn confirm(&mut self, lsn: u64) {
+        self.resume_lsn = self.resume_lsn.max(lsn);
+
+        if let Some(source) = self.source.as_mut() {
+            source.confirm(lsn);
+        }
+    }
+
+    fn last_record_end(&self) -> u64 {
+        self.delivered
+    }
+
+    fn keep_alive(&mut self) -> Result<(), PgDigError> {
+        let result = match self.source.as_mut() {
+            Some(source) => source.keep_alive(),
+            None => return Ok(()),
+        };
+
+        match result {
+            // reconnect when the next message is asked for
+            Err(e) if is_recoverable(&e) => {
+                self.lose(e);
+                Ok(())
+            },
+            result => result,
+        }
+    }
+
+    fn interrupter(&self) -> Option<Interrupter> {
+        let shared = self.shared.clone();
+
+        Some(Interrupter::new(move || {
+            let mut state = shared.state.lock().unwrap();
+            state.cancelled = true;
+
+            if let Some(connection) = state.connection.take() {
+                connection.interrupt();
+            }
+
+            shared.wake.notify_all();
+        }))
+    }
+}
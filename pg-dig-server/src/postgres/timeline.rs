@@ -0,0 +1,118 @@
+use crate::postgres::connection::ReplicationConnection;
+use crate::postgres::common::lsn::Lsn;
+use crate::postgres::error::PgDigError;
+use std::str::FromStr;
+
+/// What IDENTIFY_SYSTEM reports about the server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemIdentification {
+    /// Unique identifier of the cluster, shared by its standbys
+    pub system_id: u64,
+    /// The server's current timeline
+    pub timeline: u32,
+    /// The server's current WAL flush position
+    pub xlogpos: u64,
+    /// The database connected to, if any
+    pub dbname: Option<String>,
+}
+
+/// Runs IDENTIFY_SYSTEM.
+///
+/// `conn` must not be streaming.
+pub fn identify_system(conn: &mut dyn ReplicationConnection) -> Result<SystemIdentification, PgDigError> {
+    let row = conn.query_row("IDENTIFY_SYSTEM", &["systemid", "timeline", "xlogpos", "dbname"])?;
+
+    Ok(SystemIdentification {
+        system_id: parse_column(&row[0], "systemid")?,
+        timeline: parse_column(&row[1], "timeline")?,
+        xlogpos: parse_lsn_column(&row[2], "xlogpos")?,
+        dbname: row[3].clone(),
+    })
+}
+
+/// One line of a timeline history file: `timeline` branched off at `switchpoint`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineHistoryEntry {
+    pub timeline: u32,
+    /// The first LSN that is not on `timeline`
+    pub switchpoint: u64,
+    pub reason: String,
+}
+
+/// The parent timelines of a timeline, oldest first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimelineHistory {
+    pub entries: Vec<TimelineHistoryEntry>,
+}
+
+impl TimelineHistory {
+    /// Parses the contents of a `.history` file.
+    ///
+    /// Each line holds a parent timeline, the LSN where it was switched away from and a free
+    /// text reason, separated by tabs. Blank lines and lines starting with '#' are ignored.
+    pub fn parse(content: &str) -> Result<TimelineHistory, PgDigError> {
+        let mut entries = Vec::new();
+
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || PgDigError::Protocol(format!("invalid timeline history line: {}", line));
+            let mut fields = line.splitn(3, char::is_whitespace);
+
+            let timeline = fields.next()
+                .and_then(|timeline| timeline.parse().ok())
+                .ok_or_else(invalid)?;
+            let switchpoint = fields.next()
+                .and_then(|lsn| Lsn::from_str(lsn).ok())
+                .ok_or_else(invalid)?;
+
+            entries.push(TimelineHistoryEntry {
+                timeline,
+                switchpoint: switchpoint.to_u64(),
+                reason: fields.next().unwrap_or("").trim().to_string(),
+            });
+        }
+
+        Ok(TimelineHistory { entries })
+    }
+
+    /// Where `timeline` ended, or None if it is not a parent in this history.
+    pub fn switchpoint(&self, timeline: u32) -> Option<u64> {
+        self.entries.iter()
+            .find(|entry| entry.timeline == timeline)
+            .map(|entry| entry.switchpoint)
+    }
+
+    /// The timeline that `lsn` was written on, given the newest timeline is `current`.
+    pub fn timeline_containing(&self, lsn: u64, current: u32) -> u32 {
+        self.entries.iter()
+            .find(|entry| lsn < entry.switchpoint)
+            .map(|entry| entry.timeline)
+            .unwrap_or(current)
+    }
+}
+
+/// Runs TIMELINE_HISTORY for `timeline`, which must be greater than 1.
+///
+/// `conn` must not be streaming.
+pub fn timeline_history(conn: &mut dyn ReplicationConnection, timeline: u32) -> Result<TimelineHistory, PgDigError> {
+    let stmt = format!("TIMELINE_HISTORY {}", timeline);
+    let row = conn.query_row(stmt.as_str(), &["content"])?;
+
+    match &row[0] {
+        Some(content) => TimelineHistory::parse(content),
+        None => Err(PgDigError::Protocol(format!("{} returned no content", stmt))),
+    }
+}
+
+pub(crate) fn parse_column<T: FromStr>(value: &Option<String>, column: &str) -> Result<T, PgDigError> {
+    value.as_deref()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| PgDigError::Protocol(format!("invalid {}: {:?}", column, value)))
+}
+
+pub(crate) fn parse_lsn_column(value: &Option<String>, column: &str) -> Result<u64, PgDigError> {
+    parse_column::<Lsn>(value, column).map(Lsn::to_u64)
+}
@@ -0,0 +1,246 @@
+use crate::postgres::common::lsn::Lsn;
+use crate::postgres::error::PgDigError;
+use crate::postgres::replication::decode_record;
+use crate::postgres::source::WalSource;
+use crate::postgres::xlog::constants::{XLOG_BLCKSZ, XLOG_PAGE_MAGIC};
+use crate::postgres::xlog::page_header::{XLogPageHeader, XLogPageHeaderFlags};
+use crate::postgres::xlog::reassembler::RecordReassembler;
+use crate::postgres::xlog_message::XLogMessage;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The name of a WAL segment file, e.g. 000000010000000000000003.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentName {
+    pub timeline: u32,
+    /// The "log" part: which 4GB of WAL the segment is in
+    pub log: u32,
+    /// The segment within that 4GB
+    pub seg: u32,
+}
+
+impl SegmentName {
+    /// Parses a segment file name. Files left by pg_receivewal with a `.partial` suffix are
+    /// accepted too.
+    pub fn parse(file_name: &str) -> Option<SegmentName> {
+        let name = file_name.strip_suffix(".partial").unwrap_or(file_name);
+
+        if name.len() != 24 || !name.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        Some(SegmentName {
+            timeline: u32::from_str_radix(&name[0..8], 16).ok()?,
+            log: u32::from_str_radix(&name[8..16], 16).ok()?,
+            seg: u32::from_str_radix(&name[16..24], 16).ok()?,
+        })
+    }
+
+    /// The LSN of the first byte of the segment.
+    pub fn start_lsn(&self, segment_size: u32) -> u64 {
+        ((self.log as u64) << 32) + self.seg as u64 * segment_size as u64
+    }
+}
+
+/// WalFileReader reads records from WAL segment files, such as a copy of pg_wal or an archive.
+///
+/// Segments are read in order and fed through the same reassembly and decoding as the
+/// replication stream, so `read_message` hands out the same messages `replication::read_message`
+/// does. Reading stops at the end of the last segment, or earlier at the first page that was
+/// never written: a zeroed page, or one left over from before the segment file was recycled.
+pub struct WalFileReader {
+    segments: Vec<(SegmentName, PathBuf)>,
+    next_segment: usize,
+    /// The segment size, once known from the configuration or the first long page header
+    segment_size: Option<u32>,
+    reassembler: RecordReassembler,
+    last_record_end: u64,
+    finished: bool,
+}
+
+impl WalFileReader {
+    /// Opens a single segment file, or every segment file in a directory.
+    ///
+    /// When a directory holds the same segment on several timelines, the newest timeline wins.
+    /// `segment_size` is checked against each segment; when None it is taken from the first one.
+    pub fn open(path: &Path, segment_size: Option<u32>) -> Result<WalFileReader, PgDigError> {
+        let wal_file_error = |e: std::io::Error| PgDigError::WalFile(format!("{}: {}", path.display(), e));
+        let mut segments = Vec::new();
+
+        let paths = match fs::metadata(path).map_err(wal_file_error)?.is_dir() {
+            true => fs::read_dir(path)
+                .map_err(wal_file_error)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(wal_file_error)?,
+            false => vec![path.to_path_buf()],
+        };
+
+        for path in paths {
+            if let Some(name) = path.file_name().and_then(|name| name.to_str()).and_then(SegmentName::parse) {
+                segments.push((name, path));
+            }
+        }
+
+        if segments.is_empty() {
+            return Err(PgDigError::WalFile(format!("no WAL segment files in {}", path.display())));
+        }
+
+        segments.sort_by_key(|(name, _)| (name.log, name.seg, name.timeline));
+        /* keep the last, i.e. newest timeline, of each segment */
+        segments.reverse();
+        segments.dedup_by_key(|(name, _)| (name.log, name.seg));
+        segments.reverse();
+
+        Ok(WalFileReader {
+            segments,
+            next_segment: 0,
+            segment_size,
+            reassembler: RecordReassembler::new(),
+            last_record_end: 0,
+            finished: false,
+        })
+    }
+
+    /// The segment size in use, once the first segment has been read.
+    pub fn segment_size(&self) -> Option<u32> {
+        self.segment_size
+    }
+
+    /// The end of the last record returned by `read_message`, including skipped ones.
+    pub fn last_record_end(&self) -> u64 {
+        self.last_record_end
+    }
+
+    /// Reads the next WAL record. Returns `EndOfStream` once there is no more WAL.
+    pub fn read_message(&mut self) -> Result<XLogMessage, PgDigError> {
+        loop {
+            if let Some(record) = self.reassembler.next_record() {
+                let record = record?;
+                self.last_record_end = record.end_lsn;
+                return decode_record(record, 0);
+            }
+
+            if self.finished || self.next_segment == self.segments.len() {
+                return Err(PgDigError::EndOfStream);
+            }
+
+            self.read_segment()?;
+        }
+    }
+
+    fn read_segment(&mut self) -> Result<(), PgDigError> {
+        let (name, path) = &self.segments[self.next_segment];
+        self.next_segment += 1;
+
+        let bytes = fs::read(path).map_err(|e| PgDigError::WalFile(format!("{}: {}", path.display(), e)))?;
+        let segment_size = match self.segment_size {
+            Some(segment_size) => segment_size,
+            None => read_segment_size(&bytes, path)?,
+        };
+        self.segment_size = Some(segment_size);
+
+        if bytes.len() > segment_size as usize || bytes.len() % XLOG_BLCKSZ != 0 {
+            return Err(PgDigError::WalFile(format!(
+                "{} is {} bytes, expected {} bytes of whole pages",
+                path.display(),
+                bytes.len(),
+                segment_size
+            )));
+        }
+
+        let start_lsn = name.start_lsn(segment_size);
+        let valid_length = validate_pages(&bytes, start_lsn, segment_size, path)?;
+
+        if valid_length < bytes.len() {
+            self.finished = true;
+        }
+
+        self.reassembler.push(start_lsn, &bytes[..valid_length]);
+        Ok(())
+    }
+}
+
+impl WalSource for WalFileReader {
+    fn next_message(&mut self) -> Result<XLogMessage, PgDigError> {
+        self.read_message()
+    }
+
+    fn last_record_end(&self) -> u64 {
+        self.last_record_end
+    }
+}
+
+/// Reads the segment size from the long page header at the start of a segment.
+fn read_segment_size(bytes: &[u8], path: &Path) -> Result<u32, PgDigError> {
+    let header = read_page_header(bytes, path)?;
+
+    match header.long_header {
+        Some(long_header) if long_header.xlp_seg_size.is_power_of_two() => Ok(long_header.xlp_seg_size),
+        Some(long_header) => Err(PgDigError::WalFile(format!(
+            "{} has an invalid segment size {}",
+            path.display(),
+            long_header.xlp_seg_size
+        ))),
+        None => Err(PgDigError::WalFile(format!("{} does not start with a long page header", path.display()))),
+    }
+}
+
+/// Checks the page headers of a segment and returns how many of its bytes hold WAL.
+fn validate_pages(bytes: &[u8], start_lsn: u64, segment_size: u32, path: &Path) -> Result<usize, PgDigError> {
+    for (index, page) in bytes.chunks(XLOG_BLCKSZ).enumerate() {
+        let page_address = start_lsn + (index * XLOG_BLCKSZ) as u64;
+        let header = read_page_header(page, path)?;
+
+        /* a page that was never written: this is the end of the WAL */
+        if header.xlp_magic == 0 && header.xlp_pageaddr == 0 {
+            return Ok(index * XLOG_BLCKSZ);
+        }
+
+        if header.xlp_magic != XLOG_PAGE_MAGIC {
+            return Err(PgDigError::WalFile(format!(
+                "{}: invalid magic number {:04X} in page at {}, expected {:04X}",
+                path.display(),
+                header.xlp_magic,
+                Lsn::from_u64(page_address),
+                XLOG_PAGE_MAGIC
+            )));
+        }
+
+        if index == 0 {
+            let long_header = header.long_header.as_ref()
+                .filter(|_| header.read_flags().contains(XLogPageHeaderFlags::XLP_LONG_HEADER))
+                .ok_or_else(|| PgDigError::WalFile(format!("{} does not start with a long page header", path.display())))?;
+
+            if long_header.xlp_seg_size != segment_size {
+                return Err(PgDigError::WalFile(format!(
+                    "{} has segment size {}, expected {}",
+                    path.display(),
+                    long_header.xlp_seg_size,
+                    segment_size
+                )));
+            }
+
+            if long_header.xlp_xlog_blcksz as usize != XLOG_BLCKSZ {
+                return Err(PgDigError::WalFile(format!(
+                    "{} has WAL block size {}, expected {}",
+                    path.display(),
+                    long_header.xlp_xlog_blcksz,
+                    XLOG_BLCKSZ
+                )));
+            }
+        }
+
+        /* left over from before the segment file was recycled: also the end of the WAL */
+        if header.xlp_pageaddr != page_address {
+            return Ok(index * XLOG_BLCKSZ);
+        }
+    }
+
+    Ok(bytes.len())
+}
+
+fn read_page_header(page: &[u8], path: &Path) -> Result<XLogPageHeader, PgDigError> {
+    XLogPageHeader::from_bytes(page)
+        .map_err(|e| PgDigError::WalFile(format!("{}: invalid page header: {}", path.display(), e)))
+}
@@ -1,9 +1,10 @@
 use crate::postgres::common::RelFileLocator;
 use crate::postgres::xlog::block_image_header::XLogRecordBlockImageHeader;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
 use bitflags::bitflags;
-use scroll::Pread;
+use std::fmt;
 use std::fmt::Formatter;
-use std::{fmt, ptr, slice};
 
 #[repr(C)]
 #[derive(Debug, PartialEq)]
@@ -29,69 +30,65 @@ impl fmt::Display for XLogRecordBlockHeader {
 }
 
 impl XLogRecordBlockHeader {
-    pub unsafe fn read_flags(&self) -> XLogRecordBlockHeaderFlags {
+    pub fn read_flags(&self) -> XLogRecordBlockHeaderFlags {
         XLogRecordBlockHeaderFlags::from_bits_retain(self.fork_flags)
     }
 
-    pub unsafe fn from_raw_ptr(ptr: *const u8) -> XLogRecordBlockHeader {
-        let mut _offset = 0;
+    pub fn from_bytes(bytes: &[u8]) -> Result<XLogRecordBlockHeader, DecodeError> {
+        Self::read(&mut ByteReader::new(bytes))
+    }
+
+    pub(crate) fn read(reader: &mut ByteReader) -> Result<XLogRecordBlockHeader, DecodeError> {
         /* block reference ID */
-        let id = *ptr;
-        _offset += size_of::<u8>();
+        let id = reader.read_u8("block_id")?;
 
         /* fork within the relation, and flags
          * The fork number fits in the lower 4 bits in the fork_flags field. The upper
          * bits are used for flags.
          */
-        let fork_flags = *ptr.add(_offset);
-        _offset += size_of::<u8>();
+        let fork_flags = reader.read_u8("fork_flags")?;
 
         /* number of payload bytes (not including page image) */
-        let data_length = u16::from_le_bytes(
-            slice::from_raw_parts(ptr.add(_offset), size_of::<u16>())
-                .try_into()
-                .expect("failed to parse data_length"),
-        );
-        _offset += size_of::<u16>();
+        let data_length = reader.read_u16("data_length")?;
 
         let flags = XLogRecordBlockHeaderFlags::from_bits_retain(fork_flags);
-        /* If BKPBLOCK_HAS_IMAGE, an XLogRecordBlockImageHeader struct follows */
 
-        let image_header: Option<XLogRecordBlockImageHeader> =
-            match flags.contains(XLogRecordBlockHeaderFlags::BKPBLOCK_HAS_IMAGE) {
-                true => {
-                    let header = XLogRecordBlockImageHeader::from_bytes(ptr.add(_offset));
-                    _offset += size_of::<XLogRecordBlockImageHeader>();
+        /* If BKPBLOCK_HAS_IMAGE, an XLogRecordBlockImageHeader struct follows */
+        let image_header = match flags.contains(XLogRecordBlockHeaderFlags::BKPBLOCK_HAS_IMAGE) {
+            true => {
+                let header = XLogRecordBlockImageHeader::read(reader)?;
 
-                    // FIXME: Hacky - look at padding
-                    _offset -= size_of::<u8>();
-                    Some(header)
+                /* If the image is compressed and has a hole, the hole length follows */
+                if header.has_compress_header() {
+                    reader.skip(size_of::<u16>(), "hole_length")?;
                 }
-                false => None,
-            };
+                Some(header)
+            }
+            false => None,
+        };
 
         /* If BKPBLOCK_SAME_REL is not set, a RelFileLocator follows */
         let rel_file_locator = match flags.contains(XLogRecordBlockHeaderFlags::BKPBLOCK_SAME_REL) {
-            false => {
-                let locator = slice::from_raw_parts(ptr.add(_offset), size_of::<RelFileLocator>())
-                    .pread_with::<RelFileLocator>(0, scroll::LE)
-                    .expect("failed to parse rel file locator");
-                //print_hex_bytes(ptr.add(_offset), size_of::<RelFileLocator>());
-                _offset += size_of::<RelFileLocator>();
-
-                Some(locator)
-            }
+            false => Some(reader.read::<RelFileLocator>("rel_file_locator")?),
             true => None,
         };
 
-        XLogRecordBlockHeader {
+        let block_number = reader.read_u32("block_number")?;
+
+        Ok(XLogRecordBlockHeader {
             id,
             fork_flags,
             data_length,
             image_header,
             rel_file_locator,
-            block_number: ptr::read(ptr.add(_offset) as *const u32),
-        }
+            block_number,
+        })
+    }
+
+    /// Number of bytes of block data (page image and payload) that follow all the headers.
+    pub fn payload_length(&self) -> usize {
+        let image_length = self.image_header.as_ref().map_or(0, |header| header.length);
+        image_length as usize + self.data_length as usize
     }
 }
 
@@ -1,6 +1,8 @@
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
 use bitflags::bitflags;
+use std::fmt;
 use std::fmt::Formatter;
-use std::{fmt, slice};
 
 /*
 typedef struct XLogRecordBlockImageHeader
@@ -25,36 +27,36 @@ pub struct XLogRecordBlockImageHeader {
 }
 
 impl XLogRecordBlockImageHeader {
-    pub unsafe fn read_flags(&self) -> XLogRecordBlockImageHeaderFlags {
+    pub fn read_flags(&self) -> XLogRecordBlockImageHeaderFlags {
         XLogRecordBlockImageHeaderFlags::from_bits_retain(self.bimg_info)
     }
 
-    pub unsafe fn from_bytes(bytes: *const u8) -> XLogRecordBlockImageHeader {
-        let mut _offset = 0;
-
-        let length = u16::from_le_bytes(
-            slice::from_raw_parts(bytes, size_of::<u16>())
-                .try_into()
-                .expect("failed to parse length"),
-        );
-        _offset += size_of::<u16>();
-
-        let hole_offset = u16::from_le_bytes(
-            slice::from_raw_parts(bytes.add(_offset), size_of::<u16>())
-                .try_into()
-                .expect("failed to parse hole_offset"),
-        );
-        _offset += size_of::<u16>();
+    pub fn from_bytes(bytes: &[u8]) -> Result<XLogRecordBlockImageHeader, DecodeError> {
+        Self::read(&mut ByteReader::new(bytes))
+    }
 
-        let bimg_info = *bytes.add(_offset);
-        _offset += size_of::<u8>();
+    pub(crate) fn read(reader: &mut ByteReader) -> Result<XLogRecordBlockImageHeader, DecodeError> {
+        let length = reader.read_u16("bimg_length")?;
+        let hole_offset = reader.read_u16("hole_offset")?;
+        let bimg_info = reader.read_u8("bimg_info")?;
 
-        XLogRecordBlockImageHeader {
+        Ok(XLogRecordBlockImageHeader {
             length,
             hole_offset,
             bimg_info,
             padding: 0,
-        }
+        })
+    }
+
+    /// Whether an XLogRecordBlockCompressHeader (the hole length) follows this header.
+    pub fn has_compress_header(&self) -> bool {
+        let flags = XLogRecordBlockImageHeaderFlags::from_bits_retain(self.bimg_info);
+        flags.contains(XLogRecordBlockImageHeaderFlags::BKPIMAGE_HAS_HOLE)
+            && flags.intersects(
+                XLogRecordBlockImageHeaderFlags::BKPIMAGE_COMPRESS_PGLZ
+                    | XLogRecordBlockImageHeaderFlags::BKPIMAGE_COMPRESS_LZ4
+                    | XLogRecordBlockImageHeaderFlags::BKPIMAGE_COMPRESS_ZSTD,
+            )
     }
 }
 
@@ -0,0 +1,106 @@
+use crate::postgres::xlog::decode_error::DecodeError;
+use scroll::ctx::TryFromCtx;
+use scroll::{Endian, Pread};
+
+/// ByteReader walks a byte slice, checking every read against the end of the slice.
+///
+/// Failed reads name the field being read, so the caller can tell which part of a record
+/// was cut off.
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    endian: Endian,
+}
+
+impl<'a> ByteReader<'a> {
+    /// Creates a reader for little-endian data, which is how WAL records are laid out.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self::with_endian(bytes, scroll::LE)
+    }
+
+    pub fn with_endian(bytes: &'a [u8], endian: Endian) -> Self {
+        ByteReader {
+            bytes,
+            offset: 0,
+            endian,
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.offset
+    }
+
+    fn check(&self, field: &'static str, needed: usize) -> Result<(), DecodeError> {
+        if self.remaining() < needed {
+            return Err(DecodeError::Truncated {
+                field,
+                offset: self.offset,
+                needed,
+                available: self.remaining(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Reads a fixed-size value, e.g. an integer or a `#[derive(Pread)]` struct.
+    pub fn read<T>(&mut self, field: &'static str) -> Result<T, DecodeError>
+    where
+        T: TryFromCtx<'a, Endian, Error = scroll::Error>,
+    {
+        self.check(field, size_of::<T>())?;
+        self.bytes
+            .gread_with::<T>(&mut self.offset, self.endian)
+            .map_err(|_| DecodeError::Truncated {
+                field,
+                offset: self.offset,
+                needed: size_of::<T>(),
+                available: self.remaining(),
+            })
+    }
+
+    pub fn read_u8(&mut self, field: &'static str) -> Result<u8, DecodeError> {
+        self.read::<u8>(field)
+    }
+
+    pub fn read_u16(&mut self, field: &'static str) -> Result<u16, DecodeError> {
+        self.read::<u16>(field)
+    }
+
+    pub fn read_u32(&mut self, field: &'static str) -> Result<u32, DecodeError> {
+        self.read::<u32>(field)
+    }
+
+    pub fn read_u64(&mut self, field: &'static str) -> Result<u64, DecodeError> {
+        self.read::<u64>(field)
+    }
+
+    pub fn read_bytes(&mut self, len: usize, field: &'static str) -> Result<&'a [u8], DecodeError> {
+        self.check(field, len)?;
+        let bytes = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    pub fn skip(&mut self, len: usize, field: &'static str) -> Result<(), DecodeError> {
+        self.read_bytes(len, field).map(|_| ())
+    }
+
+    /// Reads a NUL-terminated string, consuming the terminator.
+    pub fn read_c_string(&mut self, field: &'static str) -> Result<String, DecodeError> {
+        let len = self.bytes[self.offset..]
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or(DecodeError::Truncated {
+                field,
+                offset: self.offset,
+                needed: self.remaining() + 1,
+                available: self.remaining(),
+            })?;
+        let bytes = self.read_bytes(len + 1, field)?;
+        Ok(String::from_utf8_lossy(&bytes[..len]).into_owned())
+    }
+}
@@ -2,4 +2,23 @@ pub const XLR_MAX_BLOCK_ID: u8 = 32;
 pub const XLR_BLOCK_ID_DATA_SHORT: u8 = 255;
 pub const XLR_BLOCK_ID_DATA_LONG: u8 = 254;
 pub const XLR_BLOCK_ID_ORIGIN: u8 = 253;
-pub const XLR_BLOCK_ID_TOPLEVEL_XID: u8 = 252;
\ No newline at end of file
+pub const XLR_BLOCK_ID_TOPLEVEL_XID: u8 = 252;
+
+/* SizeOfXLogRecord: the header ends after xl_crc */
+pub const SIZE_OF_XLOG_RECORD: usize = 24;
+
+/* XLogRecordMaxSize: the largest record the server will write */
+pub const XLOG_RECORD_MAX_SIZE: usize = 1020 * 1024 * 1024;
+
+pub const XLOG_BLCKSZ: usize = 8192;
+
+/* XLOG_PAGE_MAGIC for PostgreSQL 16 */
+pub const XLOG_PAGE_MAGIC: u16 = 0xD113;
+
+pub const DEFAULT_WAL_SEGMENT_SIZE: u32 = 16 * 1024 * 1024;
+
+/* SizeOfXLogShortPHD and SizeOfXLogLongPHD, both MAXALIGNed */
+pub const SIZE_OF_XLOG_SHORT_PHD: usize = 24;
+pub const SIZE_OF_XLOG_LONG_PHD: usize = 40;
+
+pub const MAXIMUM_ALIGNOF: u64 = 8;
\ No newline at end of file
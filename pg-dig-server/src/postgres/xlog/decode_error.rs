@@ -0,0 +1,64 @@
+use crate::postgres::common::rmgr::ResourceManager;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// DecodeError describes why a WAL record could not be decoded.
+///
+/// Every read is checked against both the slice we were given and the record's own
+/// `xl_tot_len`, so a damaged record is reported here instead of reading past the buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    /// A field ran past the end of the available bytes.
+    Truncated {
+        field: &'static str,
+        offset: usize,
+        needed: usize,
+        available: usize,
+    },
+    /// `xl_tot_len` is too small to hold a record header, or larger than any record can be.
+    InvalidRecordLength { xl_tot_len: u32 },
+    /// A block id that is neither a block reference nor a known special id.
+    InvalidBlockId { block_id: u8, offset: usize },
+    /// `xl_rmid` does not name a built-in resource manager.
+    InvalidResourceManager { rmid: u8 },
+    /// The lengths announced by the block and data headers don't add up to `xl_tot_len`.
+    DataLengthMismatch { expected: usize, actual: usize },
+    /// The rmgr bits of `xl_info` are not a record type of the resource manager.
+    UnknownRecordType { rmgr: ResourceManager, info: u8 },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated {
+                field,
+                offset,
+                needed,
+                available,
+            } => write!(
+                f,
+                "truncated record: {} at offset {} needs {} bytes, {} available",
+                field, offset, needed, available
+            ),
+            DecodeError::InvalidRecordLength { xl_tot_len } => {
+                write!(f, "invalid record length: {}", xl_tot_len)
+            }
+            DecodeError::InvalidBlockId { block_id, offset } => {
+                write!(f, "invalid block id {} at offset {}", block_id, offset)
+            }
+            DecodeError::InvalidResourceManager { rmid } => {
+                write!(f, "invalid resource manager id: {}", rmid)
+            }
+            DecodeError::DataLengthMismatch { expected, actual } => write!(
+                f,
+                "record data length mismatch: headers describe {} bytes, record holds {}",
+                expected, actual
+            ),
+            DecodeError::UnknownRecordType { rmgr, info } => {
+                write!(f, "unknown {} record type 0x{:02X}", rmgr, info)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
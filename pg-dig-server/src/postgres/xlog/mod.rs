@@ -2,3 +2,7 @@ pub mod record_header;
 pub mod block_header;
 pub mod block_image_header;
 pub mod constants;
+pub mod byte_reader;
+pub mod decode_error;
+pub mod page_header;
+pub mod reassembler;
@@ -0,0 +1,112 @@
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::constants::{SIZE_OF_XLOG_LONG_PHD, SIZE_OF_XLOG_SHORT_PHD};
+use crate::postgres::xlog::decode_error::DecodeError;
+use bitflags::bitflags;
+
+/*
+typedef struct XLogPageHeaderData
+{
+    uint16		xlp_magic;		/* magic value for correctness checks */
+    uint16		xlp_info;		/* flag bits, see below */
+    TimeLineID	xlp_tli;		/* TimeLineID of first record on page */
+    XLogRecPtr	xlp_pageaddr;	/* XLOG address of this page */
+    uint32		xlp_rem_len;	/* total len of remaining data for record */
+} XLogPageHeaderData;
+
+typedef struct XLogLongPageHeaderData
+{
+    XLogPageHeaderData std;		/* standard header fields */
+    uint64		xlp_sysid;		/* system identifier from pg_control */
+    uint32		xlp_seg_size;	/* just as a cross-check */
+    uint32		xlp_xlog_blcksz;	/* just as a cross-check */
+} XLogLongPageHeaderData;
+ */
+#[derive(Debug, PartialEq)]
+pub struct XLogPageHeader {
+    pub xlp_magic: u16,
+    pub xlp_info: u16,
+    pub xlp_tli: u32,
+    pub xlp_pageaddr: u64,
+    pub xlp_rem_len: u32,
+    pub long_header: Option<XLogLongPageHeader>,
+}
+
+/// The extra fields present on the first page of every WAL segment.
+#[derive(Debug, PartialEq)]
+pub struct XLogLongPageHeader {
+    pub xlp_sysid: u64,
+    pub xlp_seg_size: u32,
+    pub xlp_xlog_blcksz: u32,
+}
+
+impl XLogPageHeader {
+    pub fn read_flags(&self) -> XLogPageHeaderFlags {
+        XLogPageHeaderFlags::from_bits_retain(self.xlp_info)
+    }
+
+    /// Size of the header on the page, including alignment padding.
+    pub fn size(&self) -> usize {
+        match self.long_header {
+            Some(_) => SIZE_OF_XLOG_LONG_PHD,
+            None => SIZE_OF_XLOG_SHORT_PHD,
+        }
+    }
+
+    /// Size of the header that starts with `bytes`, once its `xlp_info` field is available.
+    pub fn size_from_prefix(bytes: &[u8]) -> Option<usize> {
+        let xlp_info = u16::from_le_bytes(bytes.get(2..4)?.try_into().ok()?);
+
+        match XLogPageHeaderFlags::from_bits_retain(xlp_info).contains(XLogPageHeaderFlags::XLP_LONG_HEADER) {
+            true => Some(SIZE_OF_XLOG_LONG_PHD),
+            false => Some(SIZE_OF_XLOG_SHORT_PHD),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<XLogPageHeader, DecodeError> {
+        let mut reader = ByteReader::new(bytes);
+
+        let xlp_magic = reader.read_u16("xlp_magic")?;
+        let xlp_info = reader.read_u16("xlp_info")?;
+        let xlp_tli = reader.read_u32("xlp_tli")?;
+        let xlp_pageaddr = reader.read_u64("xlp_pageaddr")?;
+        let xlp_rem_len = reader.read_u32("xlp_rem_len")?;
+        /* 4 bytes of padding up to MAXALIGN */
+        reader.skip(4, "page header padding")?;
+
+        let long_header = match XLogPageHeaderFlags::from_bits_retain(xlp_info)
+            .contains(XLogPageHeaderFlags::XLP_LONG_HEADER)
+        {
+            true => Some(XLogLongPageHeader {
+                xlp_sysid: reader.read_u64("xlp_sysid")?,
+                xlp_seg_size: reader.read_u32("xlp_seg_size")?,
+                xlp_xlog_blcksz: reader.read_u32("xlp_xlog_blcksz")?,
+            }),
+            false => None,
+        };
+
+        Ok(XLogPageHeader {
+            xlp_magic,
+            xlp_info,
+            xlp_tli,
+            xlp_pageaddr,
+            xlp_rem_len,
+            long_header,
+        })
+    }
+}
+
+bitflags! {
+    /*
+        #define XLP_FIRST_IS_CONTRECORD		0x0001
+        #define XLP_LONG_HEADER				0x0002
+        #define XLP_BKP_REMOVABLE			0x0004
+        #define XLP_FIRST_IS_OVERWRITE_CONTRECORD 0x0008
+     */
+    #[derive(Debug)]
+    pub struct XLogPageHeaderFlags: u16 {
+        const XLP_FIRST_IS_CONTRECORD           = 0x0001;
+        const XLP_LONG_HEADER                   = 0x0002;
+        const XLP_BKP_REMOVABLE                 = 0x0004;
+        const XLP_FIRST_IS_OVERWRITE_CONTRECORD = 0x0008;
+    }
+}
@@ -0,0 +1,205 @@
+use crate::postgres::common::lsn::Lsn;
+use crate::postgres::xlog::constants::{
+    MAXIMUM_ALIGNOF, SIZE_OF_XLOG_RECORD, XLOG_BLCKSZ, XLOG_RECORD_MAX_SIZE,
+};
+use crate::postgres::xlog::decode_error::DecodeError;
+use crate::postgres::xlog::page_header::{XLogPageHeader, XLogPageHeaderFlags};
+use log::warn;
+use std::collections::VecDeque;
+
+/// A complete WAL record cut out of the stream, with page headers removed.
+#[derive(Debug, PartialEq)]
+pub struct ReassembledRecord {
+    /// LSN of the first byte of the record
+    pub start_lsn: u64,
+    /// LSN just past the last byte of the record
+    pub end_lsn: u64,
+    pub bytes: Vec<u8>,
+}
+
+/// RecordReassembler turns raw WAL bytes into complete records.
+///
+/// WAL arrives as a stream of 8kB pages, each starting with an XLogPageHeaderData (the long
+/// variant at the start of a segment). Records are MAXALIGNed, may span pages and may be split
+/// across any number of pushes, so partial records and page headers are carried over until
+/// the rest of their bytes arrive.
+pub struct RecordReassembler {
+    /// LSN of the next byte we expect to be pushed
+    next_lsn: Option<u64>,
+    /// Bytes of a page header that has not been fully received yet
+    page_header: Vec<u8>,
+    /// Bytes of the record being assembled
+    record: Vec<u8>,
+    record_start: u64,
+    /// Bytes to pass over before the next record: alignment padding, or the tail of a
+    /// record that began before we joined the stream
+    skip: u64,
+    last_page_header: Option<XLogPageHeader>,
+    records: VecDeque<Result<ReassembledRecord, DecodeError>>,
+}
+
+impl Default for RecordReassembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RecordReassembler {
+    pub fn new() -> Self {
+        RecordReassembler {
+            next_lsn: None,
+            page_header: Vec::new(),
+            record: Vec::new(),
+            record_start: 0,
+            skip: 0,
+            last_page_header: None,
+            records: VecDeque::new(),
+        }
+    }
+
+    /// The most recent page header seen in the stream.
+    pub fn last_page_header(&self) -> Option<&XLogPageHeader> {
+        self.last_page_header.as_ref()
+    }
+
+    /// The LSN of the next byte expected, i.e. the end of everything pushed so far.
+    pub fn next_lsn(&self) -> Option<u64> {
+        self.next_lsn
+    }
+
+    /// Returns the next complete record, or the error that stopped one from being assembled.
+    pub fn next_record(&mut self) -> Option<Result<ReassembledRecord, DecodeError>> {
+        self.records.pop_front()
+    }
+
+    /// Forgets any partial record and starts again at `lsn`, which must be the start of a page
+    /// or the end of a record, as reported for a timeline switch.
+    pub fn reset(&mut self, lsn: u64) {
+        self.next_lsn = Some(lsn);
+        self.page_header.clear();
+        self.record.clear();
+        /* the next record begins at the next MAXALIGN boundary */
+        self.skip = align(lsn) - lsn;
+    }
+
+    /// Adds WAL bytes starting at `start_lsn`.
+    pub fn push(&mut self, start_lsn: u64, mut bytes: &[u8]) {
+        if self.next_lsn != Some(start_lsn) {
+            if let Some(expected) = self.next_lsn {
+                warn!(
+                    "WAL stream jumped from {} to {}, dropping partial record",
+                    Lsn::from_u64(expected),
+                    Lsn::from_u64(start_lsn)
+                );
+            }
+            self.reset(start_lsn);
+        }
+
+        let mut lsn = start_lsn;
+
+        while !bytes.is_empty() {
+            let page_offset = (lsn % XLOG_BLCKSZ as u64) as usize;
+            let page_remaining = XLOG_BLCKSZ - page_offset;
+
+            let taken = if page_offset == 0 || !self.page_header.is_empty() {
+                self.consume_page_header(bytes)
+            } else if self.skip > 0 {
+                let taken = (self.skip as usize).min(page_remaining).min(bytes.len());
+                self.skip -= taken as u64;
+                taken
+            } else {
+                self.consume_record(bytes, lsn, page_remaining)
+            };
+
+            bytes = &bytes[taken..];
+            lsn += taken as u64;
+        }
+
+        self.next_lsn = Some(lsn);
+    }
+
+    fn consume_page_header(&mut self, bytes: &[u8]) -> usize {
+        /* read up to xlp_info first, it tells us whether this is a long header */
+        let needed = XLogPageHeader::size_from_prefix(&self.page_header).unwrap_or(4);
+        let taken = (needed - self.page_header.len()).min(bytes.len());
+        self.page_header.extend_from_slice(&bytes[..taken]);
+
+        if XLogPageHeader::size_from_prefix(&self.page_header) != Some(self.page_header.len()) {
+            return taken;
+        }
+
+        match XLogPageHeader::from_bytes(&self.page_header) {
+            Ok(page_header) => {
+                let flags = page_header.read_flags();
+                let continues = flags.contains(XLogPageHeaderFlags::XLP_FIRST_IS_CONTRECORD);
+
+                if !self.record.is_empty() && !continues {
+                    self.records.push_back(Err(DecodeError::Truncated {
+                        field: "continuation record",
+                        offset: self.record.len(),
+                        needed: self.record_length().unwrap_or(SIZE_OF_XLOG_RECORD),
+                        available: self.record.len(),
+                    }));
+                    self.record.clear();
+                } else if self.record.is_empty() && continues {
+                    /* we joined partway through a record, skip the rest of it */
+                    self.skip = align(page_header.xlp_rem_len as u64);
+                }
+
+                self.last_page_header = Some(page_header);
+            }
+            Err(e) => self.records.push_back(Err(e)),
+        }
+
+        self.page_header.clear();
+        taken
+    }
+
+    fn consume_record(&mut self, bytes: &[u8], lsn: u64, page_remaining: usize) -> usize {
+        if self.record.is_empty() {
+            self.record_start = lsn;
+        }
+
+        /* read xl_tot_len first, then the rest of the record */
+        let target = self.record_length().unwrap_or(size_of::<u32>());
+        let taken = (target - self.record.len()).min(page_remaining).min(bytes.len());
+        self.record.extend_from_slice(&bytes[..taken]);
+        let end = lsn + taken as u64;
+
+        match self.record_length() {
+            /* zeroes where a record should be: the rest of the page is unused */
+            Some(0) => {
+                self.record.clear();
+                self.skip = (page_remaining - taken) as u64;
+            }
+            /* too short for a header, or a damaged length we would buffer up to 4GB for */
+            Some(length) if !(SIZE_OF_XLOG_RECORD..=XLOG_RECORD_MAX_SIZE).contains(&length) => {
+                self.records.push_back(Err(DecodeError::InvalidRecordLength {
+                    xl_tot_len: length as u32,
+                }));
+                self.record.clear();
+                self.skip = (page_remaining - taken) as u64;
+            }
+            Some(length) if length == self.record.len() => {
+                self.records.push_back(Ok(ReassembledRecord {
+                    start_lsn: self.record_start,
+                    end_lsn: end,
+                    bytes: std::mem::take(&mut self.record),
+                }));
+                self.skip = align(end) - end;
+            }
+            _ => {}
+        }
+
+        taken
+    }
+
+    fn record_length(&self) -> Option<usize> {
+        let xl_tot_len = self.record.get(..size_of::<u32>())?;
+        Some(u32::from_le_bytes(xl_tot_len.try_into().ok()?) as usize)
+    }
+}
+
+fn align(value: u64) -> u64 {
+    (value + MAXIMUM_ALIGNOF - 1) & !(MAXIMUM_ALIGNOF - 1)
+}
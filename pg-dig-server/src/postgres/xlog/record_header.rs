@@ -1,13 +1,14 @@
 use crate::postgres::common::transaction_id::TransactionId;
 use bitflags::bitflags;
-use scroll::Pread;
+use std::fmt;
 use std::fmt::Formatter;
-use std::{fmt, slice};
 use crate::postgres::common::rmgr::RmgrId;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
 
 /// XLogRecordHeader contains information about the record contained in the message.
 #[repr(C)]
-#[derive(Debug, Pread, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct XLogRecordHeader {
     pub xl_tot_len: u32,       /* total len of entire record */
     pub xl_xid: TransactionId, /* xact id */
@@ -18,7 +19,7 @@ pub struct XLogRecordHeader {
 }
 
 impl XLogRecordHeader {
-    pub unsafe fn read_flags(&self) -> XLogRecordHeaderFlags {
+    pub fn read_flags(&self) -> XLogRecordHeaderFlags {
         XLogRecordHeaderFlags::from_bits_retain(self.xl_info)
     }
 
@@ -26,14 +27,28 @@ impl XLogRecordHeader {
         (self.xl_info & 0xF0) >> 4
     }
 
-    pub unsafe fn from_raw_ptr(bytes: *const u8) -> XLogRecordHeader {
-        let record = slice::from_raw_parts(bytes, size_of::<XLogRecordHeader>())
-            .pread_with::<XLogRecordHeader>(0, scroll::LE)
-            .expect("failed to read xlog record");
+    pub fn from_bytes(bytes: &[u8]) -> Result<XLogRecordHeader, DecodeError> {
+        Self::read(&mut ByteReader::new(bytes))
+    }
 
-        //println!("record: {:#?}", record);
+    pub(crate) fn read(reader: &mut ByteReader) -> Result<XLogRecordHeader, DecodeError> {
+        let xl_tot_len = reader.read_u32("xl_tot_len")?;
+        let xl_xid = reader.read::<TransactionId>("xl_xid")?;
+        let xl_prev = reader.read_u64("xl_prev")?;
+        let xl_info = reader.read_u8("xl_info")?;
+        let xl_rmid = reader.read::<RmgrId>("xl_rmid")?;
+        /* 2 bytes of padding before the CRC */
+        reader.skip(2, "xl_crc")?;
+        let xl_crc = reader.read_u32("xl_crc")?;
 
-        record
+        Ok(XLogRecordHeader {
+            xl_tot_len,
+            xl_xid,
+            xl_prev,
+            xl_info,
+            xl_rmid,
+            xl_crc,
+        })
     }
 }
 
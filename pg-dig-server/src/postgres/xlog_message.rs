@@ -1,20 +1,15 @@
 use crate::postgres::common::lsn::Lsn;
-use crate::postgres::common::rmgr::{get_simple_rmgr_info, ResourceManager};
+use crate::postgres::common::rmgr::ResourceManager;
+use crate::postgres::common::timestamp::{format_timestamp, TimestampTz};
+use crate::postgres::records::RmgrRecord;
 use crate::postgres::xlog::block_header::XLogRecordBlockHeader;
+use crate::postgres::xlog::byte_reader::ByteReader;
+use crate::postgres::xlog::decode_error::DecodeError;
 use crate::postgres::xlog::record_header::XLogRecordHeader;
 use crate::postgres::xlog_parser::process_wal_record;
-use scroll::{Endian, Pread};
+use scroll::Pread;
+use std::fmt;
 use std::fmt::Formatter;
-use std::{fmt, slice};
-
-fn get_endianness() -> Endian {
-    if cfg!(target_arch = "x86_64") {
-        return scroll::BE;
-    } else if cfg!(target_arch = "arm") {
-        return scroll::BE;
-    }
-    panic!("unknown arch")
-}
 
 /// XLogMessage contains the relevant parts of the replication message for monitoring.
 ///
@@ -24,6 +19,9 @@ pub struct XLogMessage {
     pub header: XLogMessageHeader,
     pub wal_header: XLogRecordHeader,
     pub wal_block_headers: Vec<XLogRecordBlockHeader>,
+    pub resource_manager: ResourceManager,
+    /// What the record does, decoded from its main data
+    pub record: RmgrRecord,
 }
 
 impl fmt::Display for XLogMessage {
@@ -38,15 +36,17 @@ message:
 wal_header:
     transaction id: {}
     resource manager: {} ({})
+    record type: {}
 block_headers:
     {}
 "#,
             Lsn::from_u64(self.header.start_lsn),
             Lsn::from_u64(self.header.end_lsn),
-            "NYI",
+            self.message_time().map_or("unknown".to_string(), format_timestamp),
             self.wal_header.xl_xid.0.to_string(),
-            get_simple_rmgr_info(self.wal_header.xl_rmid.clone(), self.wal_header.read_rmgr_info_bytes()).rmgr_name,
+            self.resource_manager,
             self.wal_header.xl_rmid.0,
+            self.record_type(),
             self.wal_block_headers.iter().map(|block_header| format!("{})", block_header)).collect::<Vec<_>>().join("\n    ")
         )
     }
@@ -60,34 +60,45 @@ impl XLogMessage {
             .collect()
     }
 
-    pub unsafe fn from_ptr(ptr: *const u8) -> Result<XLogMessage, String> {
-        let mut _offset = 0;
-
-        let message_header = XLogMessageHeader::from_raw_ptr(ptr.add(_offset));
-        _offset += size_of::<XLogMessageHeader>();
+    /// When the record happened: the transaction's own time for commits, aborts and prepares,
+    /// otherwise when the server sent it, if it came over replication.
+    pub fn message_time(&self) -> Option<TimestampTz> {
+        let xact_time = match &self.record {
+            RmgrRecord::Transaction(record) => record.xact_time(),
+            _ => None,
+        };
 
-        let wal_header = XLogRecordHeader::from_raw_ptr(ptr.add(_offset));
-        _offset += size_of::<XLogRecordHeader>();
+        xact_time.or((self.header.send_time != 0).then_some(self.header.send_time as TimestampTz))
+    }
 
-        let rmgr = ResourceManager::try_from(wal_header.xl_rmid.clone());
+    /// Names the record type, e.g. "INSERT".
+    pub fn record_type(&self) -> String {
+        self.resource_manager.get_record_type(self.wal_header.xl_info)
+    }
 
-        if rmgr.is_err() {
-            return Err(format!("failed to find ResourceManager with id: {}", wal_header.xl_rmid.0).to_string());
-        }
+    /// Decodes an XLogData message body: the message header followed by a WAL record.
+    pub fn from_bytes(bytes: &[u8]) -> Result<XLogMessage, DecodeError> {
+        let message_header = XLogMessageHeader::from_bytes(bytes)?;
+        Self::from_record(message_header, &bytes[size_of::<XLogMessageHeader>()..])
+    }
 
-        let rm = rmgr?;
-        println!("lsn: {} ({})", Lsn::from_u64(message_header.start_lsn), rm);
+    /// Decodes a WAL record that starts at the beginning of `record`.
+    pub fn from_record(header: XLogMessageHeader, record: &[u8]) -> Result<XLogMessage, DecodeError> {
+        let decoded = process_wal_record(record)?;
 
-        if !matches!(rm, ResourceManager::Heap) && !matches!(rm, ResourceManager::Heap2) {
-            return Err(format!("{} not yet handled", rm))
-        }
+        let resource_manager = ResourceManager::try_from(decoded.header.xl_rmid.clone())
+            .map_err(|_| DecodeError::InvalidResourceManager {
+                rmid: decoded.header.xl_rmid.0,
+            })?;
 
-        let wal_block_headers = process_wal_record(ptr.add(_offset));
+        let record = RmgrRecord::decode(resource_manager, &decoded)?;
 
         Ok(XLogMessage {
-            header: message_header,
-            wal_header,
-            wal_block_headers,
+            header,
+            wal_header: decoded.header,
+            wal_block_headers: decoded.blocks.into_iter().map(|block| block.header).collect(),
+            resource_manager,
+            record,
         })
     }
 }
@@ -106,9 +117,8 @@ pub struct XLogMessageHeader {
 }
 
 impl XLogMessageHeader {
-    pub unsafe fn from_raw_ptr(ptr: *const u8) -> XLogMessageHeader {
-        slice::from_raw_parts(ptr, size_of::<XLogMessageHeader>())
-            .pread_with::<XLogMessageHeader>(0, get_endianness())
-            .expect("failed to read xlog record")
+    /// Reads the header of an XLogData message, which is sent in network byte order.
+    pub fn from_bytes(bytes: &[u8]) -> Result<XLogMessageHeader, DecodeError> {
+        ByteReader::with_endian(bytes, scroll::BE).read::<XLogMessageHeader>("xlog message header")
     }
 }
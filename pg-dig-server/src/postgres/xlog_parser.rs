@@ -1,76 +1,132 @@
-use crate::postgres::common::transaction_id::{BOOTSTRAP_TRANSACTION_ID, INVALID_TRANSACTION_ID};
-use crate::postgres::common::RelFileLocator;
+use crate::postgres::common::transaction_id::TransactionId;
 use crate::postgres::xlog::block_header::XLogRecordBlockHeader;
+use crate::postgres::xlog::byte_reader::ByteReader;
 use crate::postgres::xlog::constants::{
-    XLR_BLOCK_ID_DATA_LONG, XLR_BLOCK_ID_DATA_SHORT, XLR_BLOCK_ID_ORIGIN,
+    SIZE_OF_XLOG_RECORD, XLR_BLOCK_ID_DATA_LONG, XLR_BLOCK_ID_DATA_SHORT, XLR_BLOCK_ID_ORIGIN,
     XLR_BLOCK_ID_TOPLEVEL_XID, XLR_MAX_BLOCK_ID,
 };
+use crate::postgres::xlog::decode_error::DecodeError;
 use crate::postgres::xlog::record_header::XLogRecordHeader;
-use log::info;
+use log::debug;
 
-unsafe fn parse_normal_block_header(buffer: *const u8) -> (XLogRecordBlockHeader, usize) {
-    let mut _offset = 0;
-
-    let xlog_block_header = XLogRecordBlockHeader::from_raw_ptr(buffer.add(_offset));
-
-    let header_size = match xlog_block_header.rel_file_locator {
-        Some(_) => size_of::<XLogRecordBlockHeader>(),
-        None => size_of::<XLogRecordBlockHeader>() - size_of::<RelFileLocator>(),
-    };
+/// A block reference together with the payload that belongs to it.
+#[allow(dead_code)]
+pub struct DecodedBlock<'a> {
+    pub header: XLogRecordBlockHeader,
+    pub image: &'a [u8],
+    pub data: &'a [u8],
+}
 
-    _offset += header_size;
-    (xlog_block_header, _offset)
+/// A WAL record split into its headers, block references and main data.
+///
+/// The payloads borrow from the buffer the record was decoded from.
+#[allow(dead_code)]
+pub struct DecodedRecord<'a> {
+    pub header: XLogRecordHeader,
+    pub blocks: Vec<DecodedBlock<'a>>,
+    pub main_data: &'a [u8],
+    pub origin: Option<u16>,
+    pub toplevel_xid: Option<TransactionId>,
 }
 
-pub unsafe fn process_wal_record(buffer: *const u8) -> Vec<XLogRecordBlockHeader> {
-    let mut _offset = 0;
-    let record = XLogRecordHeader::from_raw_ptr(buffer);
-    _offset += size_of::<XLogRecordHeader>();
+/// Decodes a single WAL record from the start of `buffer`.
+///
+/// All reads are bounded by `xl_tot_len`; bytes after the record are ignored.
+pub fn process_wal_record(buffer: &[u8]) -> Result<DecodedRecord<'_>, DecodeError> {
+    let header = XLogRecordHeader::from_bytes(buffer)?;
+    let total_length = header.xl_tot_len as usize;
 
-    let txid = record.xl_xid;
+    if total_length < SIZE_OF_XLOG_RECORD {
+        return Err(DecodeError::InvalidRecordLength {
+            xl_tot_len: header.xl_tot_len,
+        });
+    }
 
-    match txid {
-        INVALID_TRANSACTION_ID => {
-            info!("skipping record with invalid transaction id [0]");
-            return Vec::new();
-        }
-        BOOTSTRAP_TRANSACTION_ID => {
-            info!("skipping record with bootstrap transaction id [1]");
-            return Vec::new();
-        }
-        _ => {}
+    if buffer.len() < total_length {
+        return Err(DecodeError::Truncated {
+            field: "record",
+            offset: 0,
+            needed: total_length,
+            available: buffer.len(),
+        });
     }
 
+    let mut reader = ByteReader::new(&buffer[..total_length]);
+    reader.skip(SIZE_OF_XLOG_RECORD, "record header")?;
+
     let mut block_headers = Vec::new();
+    let mut main_data_length = 0;
+    let mut origin = None;
+    let mut toplevel_xid = None;
+    let mut data_total = 0;
+
     /* peek at the block header id */
-    loop {
-        let block_id = *buffer.add(_offset);
-        println!("block index: {} at offset {}", block_id, _offset);
+    while reader.remaining() > data_total {
+        let offset = reader.offset();
+        let block_id = buffer[offset];
+
         match block_id {
-            0..XLR_MAX_BLOCK_ID => {
-                let (block_header, block_header_size) = parse_normal_block_header(buffer);
-                println!("{:#?}", block_header);
+            0..=XLR_MAX_BLOCK_ID => {
+                let block_header = XLogRecordBlockHeader::read(&mut reader)?;
+                debug!("block header: {:?}", block_header);
+                data_total += block_header.payload_length();
                 block_headers.push(block_header);
-                _offset += block_header_size;
             }
-            XLR_BLOCK_ID_DATA_SHORT | XLR_BLOCK_ID_DATA_LONG => {
-                println!("finished reading block headers");
+            XLR_BLOCK_ID_DATA_SHORT => {
+                reader.skip(1, "block_id")?;
+                main_data_length = reader.read_u8("main_data_length")? as usize;
+                data_total += main_data_length;
+                /* by convention, the main data fragment is always last */
                 break;
             }
-            XLR_BLOCK_ID_ORIGIN => {
-                println!("skipping origin block header");
+            XLR_BLOCK_ID_DATA_LONG => {
+                reader.skip(1, "block_id")?;
+                main_data_length = reader.read_u32("main_data_length")? as usize;
+                data_total += main_data_length;
                 break;
             }
-            XLR_BLOCK_ID_TOPLEVEL_XID => println!("skipping toplevelxid block header"),
-            _ => {
-                println!(
-                    "found invalid block id: {}. skipping rest of message.",
-                    block_id
-                );
-                break;
+            XLR_BLOCK_ID_ORIGIN => {
+                reader.skip(1, "block_id")?;
+                origin = Some(reader.read_u16("origin")?);
+            }
+            XLR_BLOCK_ID_TOPLEVEL_XID => {
+                reader.skip(1, "block_id")?;
+                toplevel_xid = Some(reader.read::<TransactionId>("toplevel_xid")?);
             }
+            _ => return Err(DecodeError::InvalidBlockId { block_id, offset }),
         }
     }
 
-    block_headers
+    if reader.remaining() != data_total {
+        return Err(DecodeError::DataLengthMismatch {
+            expected: data_total,
+            actual: reader.remaining(),
+        });
+    }
+
+    /* block images and data follow the headers in block order, then the main data */
+    let mut blocks = Vec::with_capacity(block_headers.len());
+    for header in block_headers {
+        let image = match &header.image_header {
+            Some(image_header) => reader.read_bytes(image_header.length as usize, "block image")?,
+            None => &[],
+        };
+        let data = reader.read_bytes(header.data_length as usize, "block data")?;
+
+        blocks.push(DecodedBlock {
+            header,
+            image,
+            data,
+        });
+    }
+
+    let main_data = reader.read_bytes(main_data_length, "main data")?;
+
+    Ok(DecodedRecord {
+        header,
+        blocks,
+        main_data,
+        origin,
+        toplevel_xid,
+    })
 }
@@ -0,0 +1,107 @@
+use pg_dig_server::config::{Config, StartPosition, DEFAULT_SLOT_NAME};
+use pg_dig_server::postgres::error::PgDigError;
+use std::time::Duration;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn config_defaults() {
+    let config = Config::from_sources(args(&[]), |_| None).unwrap();
+
+    assert_eq!(config, Config::default());
+    assert_eq!(config.slot_name, DEFAULT_SLOT_NAME);
+    assert_eq!(config.start_position, StartPosition::Lsn(0x100_0000));
+}
+
+#[test]
+fn config_flags_override_environment() {
+    let env = |name: &str| match name {
+        "PGDIG_SLOT" => Some("from_env".to_string()),
+        "PGDIG_TIMELINE" => Some("2".to_string()),
+        _ => None,
+    };
+
+    let config = Config::from_sources(args(&["--slot", "from_flag", "--start-lsn=0/1552C80"]), env).unwrap();
+
+    assert_eq!(config.slot_name, "from_flag");
+    assert_eq!(config.timeline, Some(2));
+    assert_eq!(config.start_position, StartPosition::Lsn(0x1552C80));
+}
+
+#[test]
+fn config_from_toml() {
+    let mut config = Config::default();
+    config.apply_toml(r#"
+        connection_string = "host=replica replication=true"
+        slot_name = "dig"
+        start_lsn = "slot"
+        status_interval_secs = 30
+    "#).unwrap();
+
+    assert_eq!(config.connection_string, "host=replica replication=true");
+    assert_eq!(config.slot_name, "dig");
+    assert_eq!(config.start_position, StartPosition::SlotRestartLsn);
+    assert_eq!(config.status_interval, Duration::from_secs(30));
+    assert_eq!(config.timeline, None);
+}
+
+#[test]
+fn config_rejects_a_zero_status_interval() {
+    assert!(matches!(Config::from_sources(args(&["--status-interval", "0"]), |_| None), Err(PgDigError::Config(_))));
+
+    let env = |name: &str| (name == "PGDIG_STATUS_INTERVAL").then(|| "0".to_string());
+    assert!(matches!(Config::from_sources(args(&[]), env), Err(PgDigError::Config(_))));
+
+    assert!(matches!(Config::default().apply_toml("status_interval_secs = 0"), Err(PgDigError::Config(_))));
+}
+
+#[test]
+fn config_start_positions() {
+    assert_eq!("current".parse(), Ok(StartPosition::CurrentInsert));
+    assert_eq!("restart_lsn".parse(), Ok(StartPosition::SlotRestartLsn));
+    assert!("not an lsn".parse::<StartPosition>().is_err());
+}
+
+#[test]
+fn config_rejects_unknown_options() {
+    assert!(matches!(Config::from_sources(args(&["--bogus", "1"]), |_| None), Err(PgDigError::Config(_))));
+    assert!(matches!(Config::from_sources(args(&["--timeline"]), |_| None), Err(PgDigError::Config(_))));
+    assert!(matches!(Config::from_sources(args(&["--timeline", "two"]), |_| None), Err(PgDigError::Config(_))));
+}
+
+#[test]
+fn config_slot_switches() {
+    let config = Config::from_sources(args(&["--temporary-slot", "--no-create-slot", "--slot", "scratch"]), |_| None).unwrap();
+
+    assert!(config.temporary_slot);
+    assert!(!config.create_slot);
+    assert_eq!(config.slot_name, "scratch");
+
+    let env = |name: &str| (name == "PGDIG_CREATE_SLOT").then(|| "false".to_string());
+    assert!(!Config::from_sources(args(&[]), env).unwrap().create_slot);
+    assert!(Config::from_sources(args(&["--create-slot", "--slot", "scratch"]), env).unwrap().create_slot);
+}
+
+#[test]
+fn config_wal_files() {
+    let config = Config::from_sources(args(&["--wal-path", "/var/lib/postgresql/wal_archive", "--segment-size-mb", "64"]), |_| None).unwrap();
+
+    assert_eq!(config.wal_path, Some("/var/lib/postgresql/wal_archive".into()));
+    assert_eq!(config.segment_size, Some(64 * 1024 * 1024));
+    assert!(matches!(Config::from_sources(args(&["--segment-size-mb", "48"]), |_| None), Err(PgDigError::Config(_))));
+}
+
+#[test]
+fn config_reconnect() {
+    let config = Config::from_sources(args(&["--no-reconnect", "--max-reconnect-delay", "5"]), |_| None).unwrap();
+
+    assert!(!config.reconnect);
+    assert_eq!(config.max_reconnect_delay, Duration::from_secs(5));
+    assert!(Config::default().reconnect);
+
+    let env = |name: &str| (name == "PGDIG_RECONNECT").then(|| "false".to_string());
+    assert!(!Config::from_sources(args(&[]), env).unwrap().reconnect);
+    assert!(Config::from_sources(args(&["--reconnect", "--max-reconnect-delay", "5"]), env).unwrap().reconnect);
+}
@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 
+use pg_dig_server::config::Config;
 use pg_dig_server::postgres::replication::{start_replication};
-use pg_dig_server::postgres::bindings::{PQfinish};
 use pg_dig_server::postgres::connection::connect;
 
 const LOCAL_CONNECTION_STRING: &str = "host=localhost user=postgres dbname=postgres password=postgres replication=database";
@@ -9,15 +9,11 @@ const TEST_BUFFER: [u8; 1024] = [0x77, 0x0, 0x0, 0x0, 0x0, 0x01, 0x55, 0x2c, 0x8
 
 #[test]
 fn test_replication() {
-    unsafe {
-        let conn = connect(LOCAL_CONNECTION_STRING);
-        let result = start_replication(conn);
+    let mut conn = connect(LOCAL_CONNECTION_STRING).unwrap();
+    let result = start_replication(conn.as_mut(), &Config::default());
 
-        assert!(
-            result.is_ok(),
-            "Expected Ok(()), got Err({:?})",
-            result.err());
-
-        PQfinish(conn);
-    }
+    assert!(
+        result.is_ok(),
+        "Expected Ok(_), got Err({:?})",
+        result.err());
 }
\ No newline at end of file
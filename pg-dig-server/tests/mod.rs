@@ -1,2 +1,3 @@
 mod postgres;
-mod integration;
\ No newline at end of file
+mod integration;
+mod config;
\ No newline at end of file
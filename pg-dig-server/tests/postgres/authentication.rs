@@ -0,0 +1,57 @@
+use crate::postgres::fake_walsender::{Auth, FakeWalSender, Script};
+use pg_dig_server::postgres::connection::connect;
+use pg_dig_server::postgres::error::PgDigError;
+use pg_dig_server::postgres::timeline::identify_system;
+
+fn server_with(auth: Auth) -> FakeWalSender {
+    FakeWalSender::start(Script {
+        auth,
+        ..Script::default()
+    })
+}
+
+fn connection_string(server: &FakeWalSender, password: &str) -> String {
+    format!("{} password={}", server.connection_string(), password)
+}
+
+#[test]
+fn authenticates_with_md5() {
+    let server = server_with(Auth::Md5 { password: "secret".to_string() });
+
+    let mut conn = connect(&connection_string(&server, "secret")).unwrap();
+    assert_eq!(identify_system(conn.as_mut()).unwrap().timeline, 1);
+    drop(conn);
+
+    assert!(server.finish().authenticated);
+}
+
+#[test]
+fn authenticates_with_scram_sha_256() {
+    let server = server_with(Auth::Scram { password: "correct horse".to_string() });
+
+    let mut conn = connect(&connection_string(&server, "'correct horse'")).unwrap();
+    assert_eq!(identify_system(conn.as_mut()).unwrap().system_id, Script::default().system_id);
+    drop(conn);
+
+    assert!(server.finish().authenticated);
+}
+
+// whether libpq catches this depends on its version
+#[cfg(feature = "native-client")]
+#[test]
+fn rejects_scram_without_the_server_signature() {
+    let server = server_with(Auth::ScramWithoutFinal);
+
+    let result = connect(&connection_string(&server, "secret"));
+    assert!(matches!(result, Err(PgDigError::Connection(_))));
+    server.finish();
+}
+
+#[test]
+fn rejects_a_wrong_password() {
+    let server = server_with(Auth::Scram { password: "secret".to_string() });
+
+    let result = connect(&connection_string(&server, "guess"));
+    assert!(matches!(result, Err(PgDigError::Connection(_))));
+    assert!(!server.finish().authenticated);
+}
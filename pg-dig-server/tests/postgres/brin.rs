@@ -0,0 +1,66 @@
+use crate::postgres::record_builder::{decode, le_bytes, rmgr_record, wal_record};
+use pg_dig_server::postgres::common::rmgr::{get_simple_rmgr_info, ResourceManager, RmgrId};
+use pg_dig_server::postgres::records::brin::{BrinInsert, BrinRecord};
+
+#[test]
+fn brin_simple_rmgr_info() {
+    let info = get_simple_rmgr_info(RmgrId(ResourceManager::BRIN as u8), 0x90).unwrap();
+
+    assert_eq!(info.rmgr_name, "BRIN");
+    assert_eq!(info.record_type, "INSERT+INIT");
+    assert_eq!(get_simple_rmgr_info(RmgrId(ResourceManager::BRIN as u8), 0x30).unwrap().record_type, "SAMEPAGE_UPDATE");
+}
+
+#[test]
+fn brin_create_index_and_insert() {
+    let mut main_data = le_bytes(&[128]);
+    main_data.extend_from_slice(&[0x01, 0x00]);
+    let create = decode(&wal_record(ResourceManager::BRIN, 0x00, &[&[]], &main_data));
+    assert_eq!(create.record_type(), "CREATE_INDEX");
+    assert_eq!(rmgr_record!(create, BRIN), &BrinRecord::CreateIndex { pages_per_range: 128, version: 1 });
+
+    // rmgr: BRIN desc: INSERT+INIT heapBlk 256 pagesPerRange 128 offnum 1
+    let mut main_data = le_bytes(&[256, 128]);
+    main_data.extend_from_slice(&[0x01, 0x00]);
+    let insert = decode(&wal_record(ResourceManager::BRIN, 0x90, &[b"tuple", b"revmap"], &main_data));
+    assert_eq!(insert.record_type(), "INSERT+INIT");
+    assert_eq!(rmgr_record!(insert, BRIN), &BrinRecord::Insert(BrinInsert {
+        heap_block: 256,
+        pages_per_range: 128,
+        offnum: 1,
+        init_page: true,
+    }));
+    assert_eq!(insert.get_block_numbers(), vec![0, 1]);
+}
+
+#[test]
+fn brin_updates() {
+    // rmgr: BRIN desc: UPDATE heapBlk 0 pagesPerRange 128 old offnum 2, new offnum 5
+    let mut main_data = vec![0x02, 0x00, 0x00, 0x00];
+    main_data.extend(le_bytes(&[0, 128]));
+    main_data.extend_from_slice(&[0x05, 0x00]);
+    let update = decode(&wal_record(ResourceManager::BRIN, 0x20, &[b"tuple", b"revmap", &[]], &main_data));
+    assert_eq!(update.record_type(), "UPDATE");
+    assert_eq!(rmgr_record!(update, BRIN), &BrinRecord::Update {
+        old_offnum: 2,
+        insert: BrinInsert { heap_block: 0, pages_per_range: 128, offnum: 5, init_page: false },
+    });
+    assert_eq!(update.get_block_numbers(), vec![0, 1, 2]);
+
+    let same_page = decode(&wal_record(ResourceManager::BRIN, 0x30, &[b"tuple"], &[0x03, 0x00]));
+    assert_eq!(same_page.record_type(), "SAMEPAGE_UPDATE");
+    assert_eq!(rmgr_record!(same_page, BRIN), &BrinRecord::SamePageUpdate { offnum: 3 });
+}
+
+#[test]
+fn brin_revmap_extend_and_desummarize() {
+    let extend = decode(&wal_record(ResourceManager::BRIN, 0x40, &[&[], &[]], &le_bytes(&[1])));
+    assert_eq!(extend.record_type(), "REVMAP_EXTEND");
+    assert_eq!(rmgr_record!(extend, BRIN), &BrinRecord::RevmapExtend { target_block: 1 });
+
+    let mut main_data = le_bytes(&[128, 384]);
+    main_data.extend_from_slice(&[0x04, 0x00]);
+    let desummarize = decode(&wal_record(ResourceManager::BRIN, 0x50, &[&[], &[]], &main_data));
+    assert_eq!(desummarize.record_type(), "DESUMMARIZE");
+    assert_eq!(rmgr_record!(desummarize, BRIN), &BrinRecord::Desummarize { pages_per_range: 128, heap_block: 384, offnum: 4 });
+}
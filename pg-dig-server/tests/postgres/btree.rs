@@ -0,0 +1,117 @@
+use crate::postgres::record_builder::{decode, le_bytes, rmgr_record, wal_record};
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::common::transaction_id::{FullTransactionId, TransactionId};
+use pg_dig_server::postgres::records::btree::{BtreeDelete, BtreeMarkPageHalfdead, BtreeRecord, BtreeSplit, BtreeUnlinkPage};
+
+#[test]
+fn btree_inserts() {
+    let leaf = decode(&wal_record(ResourceManager::Btree, 0x00, &[b"itup"], &[0x02, 0x00]));
+    assert_eq!(leaf.record_type(), "INSERT_LEAF");
+    assert_eq!(rmgr_record!(leaf, Btree), &BtreeRecord::InsertLeaf { offnum: 2 });
+
+    // the downlink goes to block 0, block 1 is the child whose split it finishes
+    let upper = decode(&wal_record(ResourceManager::Btree, 0x10, &[b"itup", &[]], &[0x05, 0x00]));
+    assert_eq!(upper.record_type(), "INSERT_UPPER");
+    assert_eq!(rmgr_record!(upper, Btree), &BtreeRecord::InsertUpper { offnum: 5 });
+    assert_eq!(upper.get_block_numbers(), vec![0, 1]);
+
+    let post = decode(&wal_record(ResourceManager::Btree, 0x50, &[b"itup"], &[0x03, 0x00]));
+    assert_eq!(post.record_type(), "INSERT_POST");
+    assert_eq!(rmgr_record!(post, Btree), &BtreeRecord::InsertPost { offnum: 3 });
+}
+
+#[test]
+fn btree_split_keeps_block_references() {
+    // rmgr: Btree desc: SPLIT_R level: 0, firstrightoff: 184, newitemoff: 200, postingoff: 0
+    let main_data = [0x00, 0x00, 0x00, 0x00, 0xB8, 0x00, 0xC8, 0x00, 0x00, 0x00];
+    let message = decode(&wal_record(ResourceManager::Btree, 0x40, &[&[], b"right", &[]], &main_data));
+
+    assert_eq!(message.record_type(), "SPLIT_R");
+    assert_eq!(rmgr_record!(message, Btree), &BtreeRecord::SplitRight(BtreeSplit {
+        level: 0,
+        first_right_offnum: 184,
+        new_item_offnum: 200,
+        posting_offset: 0,
+    }));
+    assert_eq!(message.get_block_numbers(), vec![0, 1, 2]);
+
+    let left = decode(&wal_record(ResourceManager::Btree, 0x30, &[&[], b"right"], &main_data));
+    assert_eq!(left.record_type(), "SPLIT_L");
+    assert!(matches!(rmgr_record!(left, Btree), BtreeRecord::SplitLeft(split) if split.first_right_offnum == 184));
+}
+
+#[test]
+fn btree_delete_vacuum_and_dedup() {
+    // snapshotConflictHorizon: 745, ndeleted: 3, nupdated: 1, isCatalogRel: F
+    let delete = decode(&wal_record(ResourceManager::Btree, 0x70, &[b"offsets"], &[0xE9, 0x02, 0x00, 0x00, 0x03, 0x00, 0x01, 0x00, 0x00]));
+    assert_eq!(delete.record_type(), "DELETE");
+    assert_eq!(rmgr_record!(delete, Btree), &BtreeRecord::Delete(BtreeDelete {
+        snapshot_conflict_horizon: TransactionId(745),
+        ndeleted: 3,
+        nupdated: 1,
+        is_catalog_rel: false,
+    }));
+
+    let vacuum = decode(&wal_record(ResourceManager::Btree, 0xC0, &[b"offsets"], &[0x0A, 0x00, 0x00, 0x00]));
+    assert_eq!(vacuum.record_type(), "VACUUM");
+    assert_eq!(rmgr_record!(vacuum, Btree), &BtreeRecord::Vacuum { ndeleted: 10, nupdated: 0 });
+
+    let dedup = decode(&wal_record(ResourceManager::Btree, 0x60, &[b"intervals"], &[0x04, 0x00]));
+    assert_eq!(dedup.record_type(), "DEDUP");
+    assert_eq!(rmgr_record!(dedup, Btree), &BtreeRecord::Dedup { nintervals: 4 });
+}
+
+#[test]
+fn btree_page_deletion() {
+    // poffset: 4, leafblk: 7, leftblk: 6, rightblk: 8, topparent: 0
+    let mut main_data = vec![0x04, 0x00, 0x00, 0x00];
+    main_data.extend(le_bytes(&[7, 6, 8, 0]));
+    let halfdead = decode(&wal_record(ResourceManager::Btree, 0xB0, &[&[], &[]], &main_data));
+
+    assert_eq!(halfdead.record_type(), "MARK_PAGE_HALFDEAD");
+    assert_eq!(rmgr_record!(halfdead, Btree), &BtreeRecord::MarkPageHalfdead(BtreeMarkPageHalfdead {
+        parent_offnum: 4,
+        leaf_block: 7,
+        left_block: 6,
+        right_block: 8,
+        top_parent: 0,
+    }));
+
+    // leftsib: 6, rightsib: 8, level: 0, safexid: 0:750, leafleftsib: 6, leafrightsib: 8, leaftopparent: 0
+    let mut main_data = le_bytes(&[6, 8, 0, 0]);
+    main_data.extend_from_slice(&750u64.to_le_bytes());
+    main_data.extend(le_bytes(&[6, 8, 0, 0]));
+    let unlink = decode(&wal_record(ResourceManager::Btree, 0x80, &[&[], &[], &[]], &main_data));
+
+    assert_eq!(unlink.record_type(), "UNLINK_PAGE");
+    assert_eq!(rmgr_record!(unlink, Btree), &BtreeRecord::UnlinkPage(BtreeUnlinkPage {
+        left_sibling: 6,
+        right_sibling: 8,
+        level: 0,
+        safe_xid: FullTransactionId(750),
+        leaf_left_sibling: 6,
+        leaf_right_sibling: 8,
+        leaf_top_parent: 0,
+    }));
+    assert_eq!(unlink.get_block_numbers(), vec![0, 1, 2]);
+}
+
+#[test]
+fn btree_newroot_reuse_and_meta_cleanup() {
+    let newroot = decode(&wal_record(ResourceManager::Btree, 0xA0, &[&[], &[]], &le_bytes(&[3, 1])));
+    assert_eq!(newroot.record_type(), "NEWROOT");
+    assert_eq!(rmgr_record!(newroot, Btree), &BtreeRecord::NewRoot { root_block: 3, level: 1 });
+
+    let mut main_data = le_bytes(&[1663, 5, 16390, 12]);
+    main_data.extend_from_slice(&746u64.to_le_bytes());
+    main_data.extend_from_slice(&[0x01, 0, 0, 0, 0, 0, 0, 0]);
+    let reuse = decode(&wal_record(ResourceManager::Btree, 0xD0, &[], &main_data));
+    assert_eq!(reuse.record_type(), "REUSE_PAGE");
+    assert!(matches!(rmgr_record!(reuse, Btree), BtreeRecord::ReusePage(reuse)
+        if reuse.locator.rel_number == 16390 && reuse.block == 12 && reuse.snapshot_conflict_horizon == FullTransactionId(746)
+            && reuse.is_catalog_rel));
+
+    let cleanup = decode(&wal_record(ResourceManager::Btree, 0xE0, &[b"metadata"], &[]));
+    assert_eq!(cleanup.record_type(), "META_CLEANUP");
+    assert_eq!(rmgr_record!(cleanup, Btree), &BtreeRecord::MetaCleanup);
+}
@@ -0,0 +1,100 @@
+use crate::postgres::test_data::TEST_BUFFER;
+use pg_dig_server::postgres::capture::{CaptureReader, CaptureWriter, CapturedMessage, ReplaySource, ReplaySpeed};
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::error::PgDigError;
+use pg_dig_server::postgres::source::WalSource;
+use pg_dig_server::postgres::timeline::SystemIdentification;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+fn system() -> SystemIdentification {
+    SystemIdentification {
+        system_id: 7_412_345_678_901_234_567,
+        timeline: 1,
+        xlogpos: 0x1552F00,
+        dbname: Some("postgres".to_string()),
+    }
+}
+
+fn capture(messages: &[(i64, &[u8])]) -> Vec<u8> {
+    let mut writer = CaptureWriter::new(Vec::new(), &system()).unwrap();
+    for (received_at, payload) in messages {
+        writer.write_message(*received_at, payload).unwrap();
+    }
+    writer.into_inner()
+}
+
+#[test]
+fn capture_round_trip() {
+    let keepalive = [b'k', 0, 0, 0, 0, 1, 0x55, 0x2F, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let bytes = capture(&[(1_000, &TEST_BUFFER), (2_000, &keepalive)]);
+
+    let mut reader = CaptureReader::new(Cursor::new(bytes)).unwrap();
+
+    assert_eq!(reader.system(), &system());
+    assert_eq!(reader.next_message().unwrap(), Some(CapturedMessage { received_at: 1_000, payload: TEST_BUFFER.to_vec() }));
+    assert_eq!(reader.next_message().unwrap(), Some(CapturedMessage { received_at: 2_000, payload: keepalive.to_vec() }));
+    assert_eq!(reader.next_message().unwrap(), None);
+}
+
+#[test]
+fn capture_ends_at_partial_message() {
+    let mut bytes = capture(&[(1_000, &TEST_BUFFER)]);
+    bytes.truncate(bytes.len() - 10);
+
+    let mut reader = CaptureReader::new(Cursor::new(bytes)).unwrap();
+
+    assert_eq!(reader.next_message().unwrap(), None);
+}
+
+#[test]
+fn capture_rejects_other_files() {
+    assert!(matches!(CaptureReader::new(Cursor::new(b"PGDMP\0\0\0\0\0".to_vec())), Err(PgDigError::Capture(_))));
+}
+
+#[test]
+fn replay_decodes_captured_records() {
+    let bytes = capture(&[(1_000, &TEST_BUFFER)]);
+    let mut source = ReplaySource::new(CaptureReader::new(Cursor::new(bytes)).unwrap(), ReplaySpeed::Unthrottled);
+
+    let messages: Vec<_> = source.messages().map(|message| message.unwrap()).collect();
+
+    let resource_managers: Vec<_> = messages.iter().map(|message| message.resource_manager).collect();
+    assert_eq!(resource_managers, vec![
+        ResourceManager::Standby,
+        ResourceManager::XLOG,
+        ResourceManager::Storage,
+        ResourceManager::Heap2,
+        ResourceManager::Heap2,
+    ]);
+    assert_eq!(messages[0].header.start_lsn, 0x1552C80);
+}
+
+#[test]
+fn replay_passes_over_records_streamed_again() {
+    // a reconnect streams from the start of the page again
+    let bytes = capture(&[(1_000, &TEST_BUFFER), (2_000, &TEST_BUFFER)]);
+    let mut source = ReplaySource::new(CaptureReader::new(Cursor::new(bytes)).unwrap(), ReplaySpeed::Unthrottled);
+
+    let messages: Vec<_> = source.messages().map(|message| message.unwrap()).collect();
+    assert_eq!(messages.len(), 5);
+}
+
+#[test]
+fn replay_keeps_scaled_timing() {
+    let keepalive = [b'k'; 18];
+    let bytes = capture(&[(0, &keepalive), (400_000, &keepalive), (400_001, &TEST_BUFFER)]);
+    let mut source = ReplaySource::new(CaptureReader::new(Cursor::new(bytes)).unwrap(), ReplaySpeed::Scaled(10.0));
+
+    let started = Instant::now();
+    source.next_message().ok();
+
+    assert!(started.elapsed() >= Duration::from_millis(40));
+}
+
+#[test]
+fn replay_speed_from_str() {
+    assert_eq!("max".parse(), Ok(ReplaySpeed::Unthrottled));
+    assert_eq!("2.5".parse(), Ok(ReplaySpeed::Scaled(2.5)));
+    assert!("0".parse::<ReplaySpeed>().is_err());
+}
@@ -0,0 +1,35 @@
+use crate::postgres::record_builder::{decode, le_bytes, wal_record};
+use pg_dig_server::postgres::common::rmgr::{get_simple_rmgr_info, ResourceManager, RmgrId};
+use pg_dig_server::postgres::common::transaction_id::TransactionId;
+use pg_dig_server::postgres::records::clog::ClogRecord;
+use pg_dig_server::postgres::records::commit_ts::CommitTsRecord;
+use pg_dig_server::postgres::records::RmgrRecord;
+
+#[test]
+fn clog_zero_page_and_truncate() {
+    // rmgr: CLOG desc: ZEROPAGE page 1
+    let zero = decode(&wal_record(ResourceManager::CLOG, 0x00, &[], &le_bytes(&[1])));
+    assert_eq!(zero.record_type(), "ZEROPAGE");
+    assert_eq!(zero.record, RmgrRecord::CLOG(ClogRecord::ZeroPage { page: 1 }));
+
+    // rmgr: CLOG desc: TRUNCATE page 0; oldestXact 722
+    let truncate = decode(&wal_record(ResourceManager::CLOG, 0x10, &[], &le_bytes(&[0, 722, 5])));
+    assert_eq!(truncate.record_type(), "TRUNCATE");
+    assert_eq!(truncate.record, RmgrRecord::CLOG(ClogRecord::Truncate {
+        page: 0,
+        oldest_xact: TransactionId(722),
+        oldest_xact_db: 5,
+    }));
+}
+
+#[test]
+fn commit_ts_zero_page_and_truncate() {
+    let zero = decode(&wal_record(ResourceManager::CommitTs, 0x00, &[], &le_bytes(&[2])));
+    assert_eq!(zero.record_type(), "ZEROPAGE");
+    assert_eq!(zero.record, RmgrRecord::CommitTs(CommitTsRecord::ZeroPage { page: 2 }));
+
+    let truncate = decode(&wal_record(ResourceManager::CommitTs, 0x10, &[], &le_bytes(&[1, 722])));
+    assert_eq!(truncate.record_type(), "TRUNCATE");
+    assert_eq!(truncate.record, RmgrRecord::CommitTs(CommitTsRecord::Truncate { page: 1, oldest_xid: TransactionId(722) }));
+    assert_eq!(get_simple_rmgr_info(RmgrId(ResourceManager::CommitTs as u8), 0x10).unwrap().rmgr_name, "CommitTs");
+}
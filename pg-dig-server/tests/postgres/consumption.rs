@@ -0,0 +1,64 @@
+use crate::postgres::multixact::create_id;
+use crate::postgres::record_builder::{decode, le_bytes, wal_record, wal_record_for};
+use crate::postgres::sequence::seq_log;
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::common::transaction_id::TransactionId;
+use pg_dig_server::postgres::consumption::{ConsumptionTracker, SequenceActivity};
+
+/* 2025-10-11 09:14:56.123456 UTC */
+const XACT_TIME: i64 = 813_489_296_123_456;
+
+fn commit(xid: u32, time: i64) -> Vec<u8> {
+    wal_record_for(xid, ResourceManager::Transaction, 0x00, &[], &time.to_le_bytes())
+}
+
+#[test]
+fn xid_age_and_rate() {
+    let mut tracker = ConsumptionTracker::new();
+    assert_eq!(tracker.xid_age(), None);
+
+    tracker.observe(&decode(&commit(1000, XACT_TIME)));
+    tracker.observe(&decode(&wal_record(ResourceManager::CLOG, 0x10, &[], &le_bytes(&[0, 722, 5]))));
+    assert_eq!(tracker.xid_age(), Some(278));
+    assert_eq!(tracker.xids_per_second(), None);
+
+    // 500 xids over two seconds; an older xid finishing late does not move the newest back
+    tracker.observe(&decode(&commit(1500, XACT_TIME + 2_000_000)));
+    tracker.observe(&decode(&commit(1200, XACT_TIME + 3_000_000)));
+
+    assert_eq!(tracker.newest_xid(), Some(TransactionId(1500)));
+    assert_eq!(tracker.xid_age(), Some(778));
+    assert_eq!(tracker.xids_per_second(), Some(250.0));
+}
+
+#[test]
+fn multixact_age() {
+    let mut tracker = ConsumptionTracker::new();
+
+    tracker.observe(&decode(&wal_record(ResourceManager::MultiXact, 0x20, &[], &create_id(4100, &[(746, 0), (747, 1)]))));
+    tracker.observe(&decode(&wal_record(ResourceManager::MultiXact, 0x20, &[], &create_id(4099, &[(748, 0)]))));
+    assert_eq!(tracker.multixact_age(), None);
+
+    tracker.observe(&decode(&wal_record(ResourceManager::MultiXact, 0x30, &[], &le_bytes(&[5, 1, 4000, 1, 9000]))));
+    assert_eq!(tracker.multixact_age(), Some(100));
+    assert_eq!(tracker.multixact_members(), 3);
+}
+
+#[test]
+fn busiest_sequences() {
+    let mut tracker = ConsumptionTracker::new();
+
+    for last_value in [33, 65, 97] {
+        tracker.observe(&decode(&wal_record(ResourceManager::Sequence, 0x00, &[&[]], &seq_log(last_value, 32))));
+    }
+    let mut other = seq_log(1, 0);
+    other[8..12].copy_from_slice(&16400u32.to_le_bytes());
+    tracker.observe(&decode(&wal_record(ResourceManager::Sequence, 0x00, &[&[]], &other)));
+
+    let busiest = tracker.busiest_sequences();
+    let summary: Vec<_> = busiest.iter().map(|(locator, activity)| (locator.rel_number, *activity)).collect();
+    assert_eq!(summary, vec![
+        (16390, &SequenceActivity { records: 3, last_value: 97 }),
+        (16400, &SequenceActivity { records: 1, last_value: 1 }),
+    ]);
+}
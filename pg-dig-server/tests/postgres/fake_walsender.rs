@@ -0,0 +1,500 @@
+//! An in-process stand-in for a walsender, good enough for libpq to connect, run the
+//! replication commands we use and stream scripted WAL. It works for the native client too.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use pbkdf2::pbkdf2_hmac;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const SSL_REQUEST_CODE: i32 = 80_877_103;
+const GSSENC_REQUEST_CODE: i32 = 80_877_104;
+const PROTOCOL_VERSION_3: i32 = 196_608;
+
+const SCRAM_SALT: &[u8] = b"fake walsender salt";
+const SCRAM_ITERATIONS: u32 = 4096;
+const MD5_SALT: [u8; 4] = [0x01, 0x02, 0x03, 0x04];
+
+const TEXT_OID: i32 = 25;
+const INT4_OID: i32 = 23;
+const INT8_OID: i32 = 20;
+
+/// Something the server does while a START_REPLICATION stream is open.
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// Send an XLogData message holding `bytes`, which start at `start_lsn`
+    XLogData { start_lsn: u64, bytes: Vec<u8> },
+    /// Send a primary keepalive
+    Keepalive { wal_end: u64, reply_requested: bool },
+    /// Wait for the client to send a standby status update
+    ExpectStatusUpdate,
+    /// End the stream because the timeline ended; the next one starts at `start_lsn`
+    SwitchTimeline { timeline: u32, start_lsn: u64 },
+    /// Drop the connection, as if the server had crashed
+    Disconnect,
+    /// Send a CopyData message with `body` as it is, whether or not it makes sense
+    CopyData { body: Vec<u8> },
+}
+
+/// How the server asks the client to authenticate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Auth {
+    Trust,
+    Md5 { password: String },
+    Scram { password: String },
+    /// SCRAM, but AuthenticationOk follows SASLContinue without the proof being checked or
+    /// the server's signature being sent
+    #[cfg_attr(not(feature = "native-client"), allow(dead_code))]
+    ScramWithoutFinal,
+}
+
+/// A status update received from the client.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatusUpdate {
+    pub write_lsn: u64,
+    pub flush_lsn: u64,
+    pub reply_requested: bool,
+}
+
+/// What the fake server pretends to be, and what it streams.
+#[derive(Debug, Clone)]
+pub struct Script {
+    pub auth: Auth,
+    pub system_id: u64,
+    pub timeline: u32,
+    pub xlogpos: u64,
+    /// The content of the history file for `timeline`
+    pub timeline_history: String,
+    /// Slots that already exist, with their restart_lsn
+    pub slots: Vec<(String, u64)>,
+    /// The steps for each START_REPLICATION, in order. A stream whose steps don't end in a
+    /// timeline switch is ended as if the server were shutting down.
+    pub streams: Vec<Vec<Step>>,
+    /// How many connections to accept, one after the other
+    pub connections: usize,
+}
+
+impl Default for Script {
+    fn default() -> Self {
+        Script {
+            auth: Auth::Trust,
+            system_id: 7_412_345_678_901_234_567,
+            timeline: 1,
+            xlogpos: 0x1560000,
+            timeline_history: String::new(),
+            slots: vec![("physical".to_string(), 0x1552C80)],
+            streams: Vec::new(),
+            connections: 1,
+        }
+    }
+}
+
+/// What the server saw the client do.
+#[derive(Debug, Default)]
+pub struct Observed {
+    /// Whether the client got through authentication
+    pub authenticated: bool,
+    pub queries: Vec<String>,
+    pub status_updates: Vec<StatusUpdate>,
+}
+
+pub struct FakeWalSender {
+    port: u16,
+    shared: Arc<Shared>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// What the sessions share with the test, which can wait for them to get somewhere.
+#[derive(Default)]
+struct Shared {
+    observed: Mutex<Observed>,
+    changed: Condvar,
+}
+
+impl FakeWalSender {
+    /// Starts a server that accepts `script.connections` connections in turn and follows
+    /// `script` across them.
+    pub fn start(mut script: Script) -> FakeWalSender {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let shared = Arc::new(Shared::default());
+
+        let session_shared = shared.clone();
+        let handle = thread::spawn(move || {
+            for _ in 0..script.connections {
+                let (stream, _) = listener.accept().unwrap();
+                let mut session = Session { stream, script, shared: session_shared.clone() };
+                /* the client hanging up is how most sessions end */
+                let _ = session.run();
+                script = session.script;
+            }
+        });
+
+        FakeWalSender { port, shared, handle: Some(handle) }
+    }
+
+    pub fn connection_string(&self) -> String {
+        format!(
+            "host=127.0.0.1 port={} user=postgres dbname=postgres replication=database sslmode=disable gssencmode=disable",
+            self.port
+        )
+    }
+
+    /// Waits until the client has done what `done` looks for, failing the test if that takes
+    /// more than a few seconds.
+    pub fn wait_for(&self, done: impl Fn(&Observed) -> bool) {
+        let observed = self.shared.observed.lock().unwrap();
+        let (_observed, wait) = self.shared.changed
+            .wait_timeout_while(observed, Duration::from_secs(10), |observed| !done(observed))
+            .unwrap();
+        assert!(!wait.timed_out(), "timed out waiting for the client");
+    }
+
+    /// Waits for the session to end and returns what the client did.
+    pub fn finish(mut self) -> Observed {
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap();
+        }
+        std::mem::take(&mut *self.shared.observed.lock().unwrap())
+    }
+}
+
+struct Session {
+    stream: TcpStream,
+    script: Script,
+    shared: Arc<Shared>,
+}
+
+type IoResult<T> = std::io::Result<T>;
+
+impl Session {
+    fn run(&mut self) -> IoResult<()> {
+        self.startup()?;
+
+        loop {
+            let (tag, body) = self.read_message()?;
+
+            match tag {
+                b'Q' => {
+                    let query = cstr(&body);
+                    self.observe(|observed| observed.queries.push(query.clone()));
+                    self.command(&query)?;
+                },
+                b'X' => return Ok(()),
+                _ => {},
+            }
+        }
+    }
+
+    fn startup(&mut self) -> IoResult<()> {
+        let parameters = loop {
+            let length = self.read_i32()?;
+            let mut body = vec![0; length as usize - 4];
+            self.stream.read_exact(&mut body)?;
+
+            match i32::from_be_bytes(body[0..4].try_into().unwrap()) {
+                SSL_REQUEST_CODE | GSSENC_REQUEST_CODE => self.stream.write_all(b"N")?,
+                PROTOCOL_VERSION_3 => break body[4..].to_vec(),
+                other => panic!("unexpected startup code {}", other),
+            }
+        };
+
+        let fields: Vec<String> = parameters.split(|byte| *byte == 0).map(|field| String::from_utf8_lossy(field).into_owned()).collect();
+        let user = fields.chunks(2).find(|pair| pair[0] == "user").map(|pair| pair[1].clone()).unwrap_or_default();
+        let authenticated = match self.script.auth.clone() {
+            Auth::Trust => true,
+            Auth::Md5 { password } => self.md5_auth(&user, &password)?,
+            Auth::Scram { password } => self.scram_auth(Some(&password))?,
+            Auth::ScramWithoutFinal => self.scram_auth(None)?,
+        };
+
+        if !authenticated {
+            self.error(&format!("password authentication failed for user \"{}\"", user))?;
+            return Err(std::io::Error::other("authentication failed"));
+        }
+
+        self.observe(|observed| observed.authenticated = true);
+        self.send(b'R', &0i32.to_be_bytes())?;
+
+        for (name, value) in [
+            ("server_version", "16.4"),
+            ("server_encoding", "UTF8"),
+            ("client_encoding", "UTF8"),
+            ("DateStyle", "ISO, MDY"),
+            ("integer_datetimes", "on"),
+            ("standard_conforming_strings", "on"),
+        ] {
+            self.send(b'S', &[name.as_bytes(), b"\0", value.as_bytes(), b"\0"].concat())?;
+        }
+
+        self.send(b'K', &[1i32.to_be_bytes(), 2i32.to_be_bytes()].concat())?;
+        self.ready()
+    }
+
+    fn md5_auth(&mut self, user: &str, password: &str) -> IoResult<bool> {
+        self.send(b'R', &[&5i32.to_be_bytes()[..], &MD5_SALT].concat())?;
+        let (_, body) = self.read_message()?;
+
+        let inner = hex(&Md5::digest(format!("{}{}", password, user)));
+        let expected = format!("md5{}", hex(&Md5::digest([inner.as_bytes(), &MD5_SALT].concat())));
+        Ok(cstr(&body) == expected)
+    }
+
+    /// The server side of SCRAM-SHA-256, as in RFC 5802. Without a password the exchange stops
+    /// after SASLContinue and the client is let in regardless.
+    fn scram_auth(&mut self, password: Option<&str>) -> IoResult<bool> {
+        self.send(b'R', &[&10i32.to_be_bytes()[..], b"SCRAM-SHA-256\0\0"].concat())?;
+
+        /* SASLInitialResponse: mechanism, then the client-first-message */
+        let (_, body) = self.read_message()?;
+        let mechanism = cstr(&body);
+        let client_first = String::from_utf8(body[mechanism.len() + 5..].to_vec()).unwrap();
+        let client_first_bare = client_first.splitn(3, ',').nth(2).unwrap().to_string();
+        let client_nonce = client_first_bare.split(',').find_map(|attribute| attribute.strip_prefix("r=")).unwrap();
+
+        let server_first = format!("r={}fakeservernonce,s={},i={}", client_nonce, STANDARD.encode(SCRAM_SALT), SCRAM_ITERATIONS);
+        self.send(b'R', &[&11i32.to_be_bytes()[..], server_first.as_bytes()].concat())?;
+
+        let (_, body) = self.read_message()?;
+        let Some(password) = password else {
+            return Ok(true);
+        };
+        let client_final = String::from_utf8(body).unwrap();
+        let (without_proof, proof) = client_final.rsplit_once(",p=").unwrap();
+
+        let mut salted_password = [0u8; 32];
+        pbkdf2_hmac::<Sha256>(password.as_bytes(), SCRAM_SALT, SCRAM_ITERATIONS, &mut salted_password);
+        let client_key = hmac(&salted_password, b"Client Key");
+        let auth_message = format!("{},{},{}", client_first_bare, server_first, without_proof);
+        let client_signature = hmac(&Sha256::digest(client_key), auth_message.as_bytes());
+        let expected: Vec<u8> = client_key.iter().zip(client_signature).map(|(key, signature)| key ^ signature).collect();
+
+        if STANDARD.decode(proof).unwrap() != expected {
+            return Ok(false);
+        }
+
+        let server_signature = hmac(&hmac(&salted_password, b"Server Key"), auth_message.as_bytes());
+        let server_final = format!("v={}", STANDARD.encode(server_signature));
+        self.send(b'R', &[&12i32.to_be_bytes()[..], server_final.as_bytes()].concat())?;
+        Ok(true)
+    }
+
+    fn command(&mut self, query: &str) -> IoResult<()> {
+        let words: Vec<&str> = query.split_whitespace().collect();
+
+        match words.as_slice() {
+            ["IDENTIFY_SYSTEM"] => {
+                let row = [
+                    Some(self.script.system_id.to_string()),
+                    Some(self.script.timeline.to_string()),
+                    Some(lsn(self.script.xlogpos)),
+                    Some("postgres".to_string()),
+                ];
+                self.send_row(&[("systemid", TEXT_OID), ("timeline", INT4_OID), ("xlogpos", TEXT_OID), ("dbname", TEXT_OID)], &row)?;
+                self.complete("IDENTIFY_SYSTEM")
+            },
+            ["TIMELINE_HISTORY", timeline] => {
+                let row = [
+                    Some(format!("{:08X}.history", timeline.parse::<u32>().unwrap())),
+                    Some(self.script.timeline_history.clone()),
+                ];
+                self.send_row(&[("filename", TEXT_OID), ("content", TEXT_OID)], &row)?;
+                self.complete("TIMELINE_HISTORY")
+            },
+            ["READ_REPLICATION_SLOT", name] => {
+                let slot = self.script.slots.iter().find(|(slot, _)| slot == name);
+                let row = match slot {
+                    Some((_, restart_lsn)) => [Some("physical".to_string()), Some(lsn(*restart_lsn)), Some("1".to_string())],
+                    None => [None, None, None],
+                };
+                self.send_row(&[("slot_type", TEXT_OID), ("restart_lsn", TEXT_OID), ("restart_tli", INT8_OID)], &row)?;
+                self.complete("READ_REPLICATION_SLOT")
+            },
+            ["CREATE_REPLICATION_SLOT", name, ..] => {
+                self.script.slots.push((name.to_string(), self.script.xlogpos));
+                let row = [Some(name.to_string()), Some(lsn(self.script.xlogpos)), None, None];
+                self.send_row(
+                    &[("slot_name", TEXT_OID), ("consistent_point", TEXT_OID), ("snapshot_name", TEXT_OID), ("output_plugin", TEXT_OID)],
+                    &row,
+                )?;
+                self.complete("CREATE_REPLICATION_SLOT")
+            },
+            ["START_REPLICATION", ..] if !self.script.streams.is_empty() => {
+                let steps = self.script.streams.remove(0);
+                self.stream_wal(steps)
+            },
+            _ => {
+                self.error(&format!("fake walsender does not support: {}", query))?;
+                self.ready()
+            },
+        }
+    }
+
+    fn stream_wal(&mut self, steps: Vec<Step>) -> IoResult<()> {
+        /* CopyBothResponse: text format, no columns */
+        self.send(b'W', &[0, 0, 0])?;
+        let mut switch = None;
+
+        for step in steps {
+            match step {
+                Step::XLogData { start_lsn, bytes } => {
+                    let mut payload = vec![b'w'];
+                    payload.extend_from_slice(&start_lsn.to_be_bytes());
+                    payload.extend_from_slice(&(start_lsn + bytes.len() as u64).to_be_bytes());
+                    payload.extend_from_slice(&0i64.to_be_bytes());
+                    payload.extend_from_slice(&bytes);
+                    self.send(b'd', &payload)?;
+                },
+                Step::Keepalive { wal_end, reply_requested } => {
+                    let mut payload = vec![b'k'];
+                    payload.extend_from_slice(&wal_end.to_be_bytes());
+                    payload.extend_from_slice(&0i64.to_be_bytes());
+                    payload.push(reply_requested as u8);
+                    self.send(b'd', &payload)?;
+                },
+                Step::ExpectStatusUpdate => {
+                    let before = self.shared.observed.lock().unwrap().status_updates.len();
+                    while self.shared.observed.lock().unwrap().status_updates.len() == before {
+                        self.read_copy_message()?;
+                    }
+                },
+                Step::SwitchTimeline { timeline, start_lsn } => switch = Some((timeline, start_lsn)),
+                Step::Disconnect => {
+                    self.stream.shutdown(Shutdown::Both)?;
+                    return Err(std::io::Error::other("disconnected"));
+                },
+                Step::CopyData { body } => self.send(b'd', &body)?,
+            }
+        }
+
+        /* end our half of the COPY and wait for the client to end theirs */
+        self.send(b'c', &[])?;
+        while !self.read_copy_message()? {}
+
+        if let Some((timeline, start_lsn)) = switch {
+            self.send_row(&[("next_tli", INT8_OID), ("next_tli_startpos", TEXT_OID)], &[Some(timeline.to_string()), Some(lsn(start_lsn))])?;
+            self.send(b'C', b"SELECT\0")?;
+        }
+
+        self.complete("START_STREAMING")
+    }
+
+    /// Reads one message sent during COPY, recording status updates. Returns true at CopyDone.
+    fn read_copy_message(&mut self) -> IoResult<bool> {
+        let (tag, body) = self.read_message()?;
+
+        match tag {
+            b'd' if body.first() == Some(&b'r') => {
+                let update = StatusUpdate {
+                    write_lsn: u64::from_be_bytes(body[1..9].try_into().unwrap()),
+                    flush_lsn: u64::from_be_bytes(body[9..17].try_into().unwrap()),
+                    reply_requested: body[33] != 0,
+                };
+                self.observe(|observed| observed.status_updates.push(update));
+                Ok(false)
+            },
+            b'c' => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    fn send_row(&mut self, columns: &[(&str, i32)], values: &[Option<String>]) -> IoResult<()> {
+        let mut description = (columns.len() as i16).to_be_bytes().to_vec();
+        for (name, type_oid) in columns {
+            description.extend_from_slice(name.as_bytes());
+            description.push(0);
+            description.extend_from_slice(&0i32.to_be_bytes());
+            description.extend_from_slice(&0i16.to_be_bytes());
+            description.extend_from_slice(&type_oid.to_be_bytes());
+            description.extend_from_slice(&(-1i16).to_be_bytes());
+            description.extend_from_slice(&(-1i32).to_be_bytes());
+            description.extend_from_slice(&0i16.to_be_bytes());
+        }
+        self.send(b'T', &description)?;
+
+        let mut row = (values.len() as i16).to_be_bytes().to_vec();
+        for value in values {
+            match value {
+                Some(value) => {
+                    row.extend_from_slice(&(value.len() as i32).to_be_bytes());
+                    row.extend_from_slice(value.as_bytes());
+                },
+                None => row.extend_from_slice(&(-1i32).to_be_bytes()),
+            }
+        }
+        self.send(b'D', &row)
+    }
+
+    fn complete(&mut self, tag: &str) -> IoResult<()> {
+        self.send(b'C', &[tag.as_bytes(), b"\0"].concat())?;
+        self.ready()
+    }
+
+    fn error(&mut self, message: &str) -> IoResult<()> {
+        let mut body = Vec::new();
+        for (field, value) in [(b'S', "ERROR"), (b'V', "ERROR"), (b'C', "XX000"), (b'M', message)] {
+            body.push(field);
+            body.extend_from_slice(value.as_bytes());
+            body.push(0);
+        }
+        body.push(0);
+        self.send(b'E', &body)
+    }
+
+    fn ready(&mut self) -> IoResult<()> {
+        self.send(b'Z', b"I")
+    }
+
+    /// Records something the client did, and wakes a test waiting for it.
+    fn observe(&self, record: impl FnOnce(&mut Observed)) {
+        record(&mut self.shared.observed.lock().unwrap());
+        self.shared.changed.notify_all();
+    }
+
+    fn send(&mut self, tag: u8, body: &[u8]) -> IoResult<()> {
+        let mut message = vec![tag];
+        message.extend_from_slice(&(body.len() as i32 + 4).to_be_bytes());
+        message.extend_from_slice(body);
+        self.stream.write_all(&message)
+    }
+
+    fn read_message(&mut self) -> IoResult<(u8, Vec<u8>)> {
+        let mut tag = [0u8; 1];
+        self.stream.read_exact(&mut tag)?;
+        let length = self.read_i32()?;
+        let mut body = vec![0; length as usize - 4];
+        self.stream.read_exact(&mut body)?;
+        Ok((tag[0], body))
+    }
+
+    fn read_i32(&mut self) -> IoResult<i32> {
+        let mut bytes = [0u8; 4];
+        self.stream.read_exact(&mut bytes)?;
+        Ok(i32::from_be_bytes(bytes))
+    }
+}
+
+fn cstr(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|byte| *byte == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn lsn(lsn: u64) -> String {
+    format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFF_FFFF)
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
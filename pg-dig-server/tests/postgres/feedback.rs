@@ -0,0 +1,43 @@
+use pg_dig_server::postgres::feedback::{FeedbackTracker, PrimaryKeepalive};
+use std::time::{Duration, Instant};
+
+#[test]
+fn keepalive_from_bytes() {
+    let mut bytes = 0x1552C80u64.to_be_bytes().to_vec();
+    bytes.extend_from_slice(&789_000_000i64.to_be_bytes());
+    bytes.push(1);
+
+    let keepalive = PrimaryKeepalive::from_bytes(&bytes).unwrap();
+
+    assert_eq!(keepalive.wal_end, 0x1552C80);
+    assert_eq!(keepalive.send_time, 789_000_000);
+    assert!(keepalive.reply_requested);
+}
+
+#[test]
+fn status_update_reports_confirmed_positions() {
+    let mut feedback = FeedbackTracker::new(Duration::from_secs(10));
+    feedback.received(0x2000);
+    feedback.confirm(0x1800);
+
+    let bytes = feedback.status_update(Instant::now()).to_bytes();
+
+    assert_eq!(bytes.len(), 34);
+    assert_eq!(bytes[0], b'r');
+    assert_eq!(u64::from_be_bytes(bytes[1..9].try_into().unwrap()), 0x2000, "write");
+    assert_eq!(u64::from_be_bytes(bytes[9..17].try_into().unwrap()), 0x1800, "flush");
+    assert_eq!(u64::from_be_bytes(bytes[17..25].try_into().unwrap()), 0x1800, "apply");
+    assert_eq!(bytes[33], 0, "reply requested");
+}
+
+#[test]
+fn status_update_is_due_after_interval() {
+    let mut feedback = FeedbackTracker::new(Duration::from_secs(10));
+    let start = Instant::now();
+
+    assert!(feedback.is_due(start), "the first update is due straight away");
+    feedback.status_update(start);
+
+    assert!(!feedback.is_due(start + Duration::from_secs(5)));
+    assert!(feedback.is_due(start + Duration::from_secs(10)));
+}
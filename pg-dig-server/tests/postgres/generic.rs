@@ -0,0 +1,42 @@
+use crate::postgres::record_builder::{decode, decode_failure, rmgr_record, wal_record};
+use pg_dig_server::postgres::common::rmgr::{get_simple_rmgr_info, ResourceManager, RmgrId};
+use pg_dig_server::postgres::records::generic::{GenericFragment, GenericPageDelta, GenericRecord};
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
+
+fn fragment(offset: u16, data: &[u8]) -> Vec<u8> {
+    let mut bytes = offset.to_le_bytes().to_vec();
+    bytes.extend((data.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+#[test]
+fn generic_page_deltas() {
+    // a bloom insert: the new tuple and the page header's pd_lower on block 0, the metapage on block 1
+    let mut tuple_page = fragment(12, &[0x40, 0x00]);
+    tuple_page.extend(fragment(8000, &[0xAB; 24]));
+    let meta_page = fragment(24, &[0x01, 0x00, 0x00, 0x00]);
+    let message = decode(&wal_record(ResourceManager::Generic, 0x00, &[&tuple_page, &meta_page], &[]));
+
+    assert_eq!(message.record_type(), "Generic");
+    let GenericRecord::Changes(deltas) = rmgr_record!(message, Generic);
+    assert_eq!(deltas, &vec![
+        GenericPageDelta {
+            block: 0,
+            full_image: false,
+            fragments: vec![GenericFragment { offset: 12, length: 2 }, GenericFragment { offset: 8000, length: 24 }],
+        },
+        GenericPageDelta { block: 1, full_image: false, fragments: vec![GenericFragment { offset: 24, length: 4 }] },
+    ]);
+    assert_eq!(deltas[0].bytes_changed(), 26);
+    assert_eq!(get_simple_rmgr_info(RmgrId(ResourceManager::Generic as u8), 0x00).unwrap().rmgr_name, "Generic");
+}
+
+#[test]
+fn generic_fragment_past_block_data() {
+    let mut block_data = fragment(100, &[0x01; 8]);
+    block_data.truncate(10);
+    let error = decode_failure(&wal_record(ResourceManager::Generic, 0x00, &[&block_data], &[]));
+
+    assert!(matches!(error, DecodeError::Truncated { .. }));
+}
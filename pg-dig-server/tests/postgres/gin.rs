@@ -0,0 +1,107 @@
+use crate::postgres::record_builder::{decode, le_bytes, rmgr_record, wal_record};
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::common::transaction_id::TransactionId;
+use pg_dig_server::postgres::common::RelFileLocator;
+use pg_dig_server::postgres::records::gin::{GinInsertFlags, GinMetaPage, GinRecord, GinSplit, GinUpdateMeta};
+
+/// GinMetaPageData with a pending list from block 2 to block 4.
+fn meta_page() -> (Vec<u8>, GinMetaPage) {
+    let mut bytes = le_bytes(&[2, 4, 1024, 3]);
+    bytes.extend_from_slice(&57i64.to_le_bytes());
+    bytes.extend(le_bytes(&[40, 20, 15, 0]));
+    bytes.extend_from_slice(&900i64.to_le_bytes());
+    bytes.extend(le_bytes(&[2, 0]));
+
+    (bytes, GinMetaPage {
+        head: 2,
+        tail: 4,
+        tail_free_size: 1024,
+        n_pending_pages: 3,
+        n_pending_heap_tuples: 57,
+        n_total_pages: 40,
+        n_entry_pages: 20,
+        n_data_pages: 15,
+        n_entries: 900,
+        gin_version: 2,
+    })
+}
+
+#[test]
+fn gin_pending_list_insert() {
+    // rmgr: Gin desc: UPDATE_META_PAGE ntuples: 3
+    let (metadata_bytes, metadata) = meta_page();
+    let mut main_data = le_bytes(&[1663, 5, 16384, 0]);
+    main_data.extend(metadata_bytes);
+    main_data.extend(le_bytes(&[0xFFFFFFFF, 0xFFFFFFFF, 3, 0]));
+    let message = decode(&wal_record(ResourceManager::Gin, 0x80, &[&[], b"tuples"], &main_data));
+
+    assert_eq!(message.record_type(), "UPDATE_META_PAGE");
+    assert_eq!(rmgr_record!(message, Gin), &GinRecord::UpdateMetaPage(GinUpdateMeta {
+        locator: RelFileLocator { spc_oid: 1663, db_oid: 5, rel_number: 16384 },
+        metadata,
+        prev_tail: 0xFFFFFFFF,
+        new_right_link: 0xFFFFFFFF,
+        ntuples: 3,
+    }));
+    assert_eq!(message.get_block_numbers(), vec![0, 1]);
+
+    let list_page = decode(&wal_record(ResourceManager::Gin, 0xA0, &[b"tuples"], &le_bytes(&[0xFFFFFFFF, 12])));
+    assert_eq!(list_page.record_type(), "INSERT_LISTPAGE");
+    assert_eq!(rmgr_record!(list_page, Gin), &GinRecord::InsertListPage { right_link: 0xFFFFFFFF, ntuples: 12 });
+}
+
+#[test]
+fn gin_pending_list_cleanup() {
+    // rmgr: Gin desc: DELETE_LISTPAGE ndeleted: 2
+    let (mut main_data, metadata) = meta_page();
+    main_data.extend(le_bytes(&[2]));
+    let message = decode(&wal_record(ResourceManager::Gin, 0xB0, &[&[], &[], &[]], &main_data));
+
+    assert_eq!(message.record_type(), "DELETE_LISTPAGE");
+    assert_eq!(rmgr_record!(message, Gin), &GinRecord::DeleteListPages { metadata, ndeleted: 2 });
+}
+
+#[test]
+fn gin_inserts_and_splits() {
+    let leaf = decode(&wal_record(ResourceManager::Gin, 0x20, &[b"item"], &[0x03, 0x00]));
+    assert_eq!(leaf.record_type(), "INSERT");
+    assert_eq!(rmgr_record!(leaf, Gin), &GinRecord::Insert {
+        flags: GinInsertFlags::GIN_INSERT_ISDATA | GinInsertFlags::GIN_INSERT_ISLEAF,
+        children: None,
+    });
+
+    // the child block numbers are BlockIdData, high half first
+    let upper = decode(&wal_record(ResourceManager::Gin, 0x20, &[b"item", &[]], &[0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x09, 0x00]));
+    assert_eq!(rmgr_record!(upper, Gin), &GinRecord::Insert { flags: GinInsertFlags::empty(), children: Some((0x10002, 9)) });
+
+    // rmgr: Gin desc: SPLIT isrootsplit: T
+    let mut main_data = le_bytes(&[1663, 5, 16384, 0xFFFFFFFF, 0, 0]);
+    main_data.extend_from_slice(&[0x06, 0x00]);
+    let split = decode(&wal_record(ResourceManager::Gin, 0x30, &[&[], &[], &[]], &main_data));
+    assert_eq!(split.record_type(), "SPLIT");
+    assert_eq!(rmgr_record!(split, Gin), &GinRecord::Split(GinSplit {
+        locator: RelFileLocator { spc_oid: 1663, db_oid: 5, rel_number: 16384 },
+        right_link: 0xFFFFFFFF,
+        left_child: 0,
+        right_child: 0,
+        flags: GinInsertFlags::GIN_INSERT_ISLEAF | GinInsertFlags::GIN_SPLIT_ROOT,
+    }));
+    assert_eq!(split.get_block_numbers(), vec![0, 1, 2]);
+}
+
+#[test]
+fn gin_vacuum_and_delete_page() {
+    let vacuum = decode(&wal_record(ResourceManager::Gin, 0x90, &[b"segments"], &[]));
+    assert_eq!(vacuum.record_type(), "VACUUM_DATA_LEAF_PAGE");
+    assert_eq!(rmgr_record!(vacuum, Gin), &GinRecord::VacuumDataLeafPage);
+
+    let mut main_data = vec![0x04, 0x00, 0x00, 0x00];
+    main_data.extend(le_bytes(&[7, 750]));
+    let delete = decode(&wal_record(ResourceManager::Gin, 0x70, &[&[], &[], &[]], &main_data));
+    assert_eq!(delete.record_type(), "DELETE_PAGE");
+    assert_eq!(rmgr_record!(delete, Gin), &GinRecord::DeletePage {
+        parent_offnum: 4,
+        right_link: 7,
+        delete_xid: TransactionId(750),
+    });
+}
@@ -0,0 +1,65 @@
+use crate::postgres::record_builder::{decode, decode_failure, rmgr_record, wal_record};
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::common::transaction_id::{FullTransactionId, TransactionId};
+use pg_dig_server::postgres::records::gist::{GistDelete, GistPageSplit, GistRecord, GistSplitPage};
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
+
+#[test]
+fn gist_page_split() {
+    // rmgr: Gist desc: PAGE_SPLIT page_split: splits to 2 pages
+    let mut main_data = 0xFFFFFFFFu32.to_le_bytes().to_vec();
+    main_data.extend_from_slice(&[0; 4]);
+    main_data.extend_from_slice(&0x1552C80u64.to_le_bytes());
+    main_data.extend_from_slice(&[0x01, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00]);
+    let left = [&90i32.to_le_bytes()[..], b"tuples"].concat();
+    let right = [&75i32.to_le_bytes()[..], b"tuples"].concat();
+    let message = decode(&wal_record(ResourceManager::Gist, 0x30, &[&[], &left, &right], &main_data));
+
+    assert_eq!(message.record_type(), "PAGE_SPLIT");
+    assert_eq!(rmgr_record!(message, Gist), &GistRecord::PageSplit(GistPageSplit {
+        orig_right_link: 0xFFFFFFFF,
+        orig_nsn: 0x1552C80,
+        orig_leaf: true,
+        mark_follow_right: true,
+        pages: vec![GistSplitPage { block: 1, ntuples: 90 }, GistSplitPage { block: 2, ntuples: 75 }],
+    }));
+}
+
+#[test]
+fn gist_page_split_without_page_data() {
+    let mut main_data = vec![0; 16];
+    main_data.extend_from_slice(&[0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    let record = wal_record(ResourceManager::Gist, 0x30, &[&[], &[]], &main_data);
+
+    assert!(matches!(
+        decode_failure(&record),
+        DecodeError::Truncated { field: "gistxlogPage.num", .. }
+    ));
+}
+
+#[test]
+fn gist_updates_and_deletes() {
+    let update = decode(&wal_record(ResourceManager::Gist, 0x00, &[b"tuples"], &[0x01, 0x00, 0x02, 0x00]));
+    assert_eq!(update.record_type(), "PAGE_UPDATE");
+    assert_eq!(rmgr_record!(update, Gist), &GistRecord::PageUpdate { ndeleted: 1, ninserted: 2 });
+
+    let mut main_data = 745u32.to_le_bytes().to_vec();
+    main_data.extend_from_slice(&[0x03, 0x00, 0x00, 0x00]);
+    let delete = decode(&wal_record(ResourceManager::Gist, 0x10, &[b"offsets"], &main_data));
+    assert_eq!(delete.record_type(), "DELETE");
+    assert_eq!(rmgr_record!(delete, Gist), &GistRecord::Delete(GistDelete {
+        snapshot_conflict_horizon: TransactionId(745),
+        ndeleted: 3,
+        is_catalog_rel: false,
+    }));
+
+    let mut main_data = 750u64.to_le_bytes().to_vec();
+    main_data.extend_from_slice(&[0x05, 0x00]);
+    let page_delete = decode(&wal_record(ResourceManager::Gist, 0x60, &[&[], &[]], &main_data));
+    assert_eq!(page_delete.record_type(), "PAGE_DELETE");
+    assert_eq!(rmgr_record!(page_delete, Gist), &GistRecord::PageDelete {
+        delete_xid: FullTransactionId(750),
+        downlink_offnum: 5,
+    });
+    assert_eq!(page_delete.get_block_numbers(), vec![0, 1]);
+}
@@ -0,0 +1,83 @@
+use crate::postgres::record_builder::{decode, rmgr_record, wal_record};
+use pg_dig_server::postgres::common::rmgr::{get_simple_rmgr_info, ResourceManager, RmgrId};
+use pg_dig_server::postgres::common::transaction_id::TransactionId;
+use pg_dig_server::postgres::records::hash::{
+    HashInitMetaPage, HashRecord, HashSplitAllocatePage, HashSplitMetaFlags, HashSqueezePage, HashVacuumOnePage,
+};
+
+#[test]
+fn hash_simple_rmgr_info() {
+    let info = get_simple_rmgr_info(RmgrId(ResourceManager::Hash as u8), 0xC0).unwrap();
+
+    assert_eq!(info.rmgr_name, "Hash");
+    assert_eq!(info.record_type, "VACUUM_ONE_PAGE");
+    assert_eq!(get_simple_rmgr_info(RmgrId(ResourceManager::Hash as u8), 0xD0).unwrap().record_type, "UNKNOWN");
+}
+
+#[test]
+fn hash_init_meta_page_and_insert() {
+    // rmgr: Hash desc: INIT_META_PAGE num_tuples: 1000, fillfactor: 75
+    let mut main_data = 1000f64.to_le_bytes().to_vec();
+    main_data.extend_from_slice(&450u32.to_le_bytes());
+    main_data.extend_from_slice(&[0x4B, 0x00, 0x00, 0x00]);
+    let meta = decode(&wal_record(ResourceManager::Hash, 0x00, &[&[]], &main_data));
+    assert_eq!(meta.record_type(), "INIT_META_PAGE");
+    assert_eq!(rmgr_record!(meta, Hash), &HashRecord::InitMetaPage(HashInitMetaPage {
+        num_tuples: 1000.0,
+        proc_id: 450,
+        fill_factor: 75,
+    }));
+
+    // the tuple goes to the bucket page in block 0, the metapage is block 1
+    let insert = decode(&wal_record(ResourceManager::Hash, 0x20, &[b"itup", &[]], &[0x07, 0x00]));
+    assert_eq!(insert.record_type(), "INSERT");
+    assert_eq!(rmgr_record!(insert, Hash), &HashRecord::Insert { offnum: 7 });
+    assert_eq!(insert.get_block_numbers(), vec![0, 1]);
+}
+
+#[test]
+fn hash_overflow_and_split() {
+    let overflow = decode(&wal_record(ResourceManager::Hash, 0x30, &[b"bitmap", &[], &[], &[], &[]], &[0x00, 0x10, 0x01, 0x00]));
+    assert_eq!(overflow.record_type(), "ADD_OVFL_PAGE");
+    assert_eq!(rmgr_record!(overflow, Hash), &HashRecord::AddOverflowPage { bitmap_size: 4096, bitmap_page_found: true });
+    assert_eq!(overflow.get_block_numbers(), vec![0, 1, 2, 3, 4]);
+
+    // rmgr: Hash desc: SPLIT_ALLOCATE_PAGE new_bucket: 5, meta_page_masks_updated: T, issplitpoint_changed: F
+    let mut main_data = 5u32.to_le_bytes().to_vec();
+    main_data.extend_from_slice(&[0x22, 0x00, 0x42, 0x00, 0x01, 0x00, 0x00, 0x00]);
+    let split = decode(&wal_record(ResourceManager::Hash, 0x40, &[&[], &[], b"masks"], &main_data));
+    assert_eq!(split.record_type(), "SPLIT_ALLOCATE_PAGE");
+    assert_eq!(rmgr_record!(split, Hash), &HashRecord::SplitAllocatePage(HashSplitAllocatePage {
+        new_bucket: 5,
+        old_bucket_flag: 0x22,
+        new_bucket_flag: 0x42,
+        flags: HashSplitMetaFlags::XLH_SPLIT_META_UPDATE_MASKS,
+    }));
+}
+
+#[test]
+fn hash_squeeze_and_vacuum() {
+    // rmgr: Hash desc: SQUEEZE_PAGE prevblkno 4, nextblkno 4294967295, ntups 3, is_primary T
+    let mut main_data = [4u32, 0xFFFFFFFF].iter().flat_map(|block| block.to_le_bytes()).collect::<Vec<_>>();
+    main_data.extend_from_slice(&[0x03, 0x00, 0x01, 0x00]);
+    let squeeze = decode(&wal_record(ResourceManager::Hash, 0x80, &[&[], b"tuples", &[], &[], &[], &[], &[]], &main_data));
+    assert_eq!(squeeze.record_type(), "SQUEEZE_PAGE");
+    assert_eq!(rmgr_record!(squeeze, Hash), &HashRecord::SqueezePage(HashSqueezePage {
+        prev_block: 4,
+        next_block: 0xFFFFFFFF,
+        ntuples: 3,
+        is_primary_bucket_same_write: true,
+        is_prev_bucket_same_write: false,
+    }));
+    assert_eq!(squeeze.get_block_numbers().len(), 7);
+
+    let mut main_data = 745u32.to_le_bytes().to_vec();
+    main_data.extend_from_slice(&[0x02, 0x00, 0x00, 0x00]);
+    let vacuum = decode(&wal_record(ResourceManager::Hash, 0xC0, &[b"offsets", &[]], &main_data));
+    assert_eq!(vacuum.record_type(), "VACUUM_ONE_PAGE");
+    assert_eq!(rmgr_record!(vacuum, Hash), &HashRecord::VacuumOnePage(HashVacuumOnePage {
+        snapshot_conflict_horizon: TransactionId(745),
+        ndeleted: 2,
+        is_catalog_rel: false,
+    }));
+}
@@ -0,0 +1,111 @@
+use crate::postgres::record_builder::{decode, decode_failure, rmgr_record, wal_record};
+use pg_dig_server::postgres::common::rmgr::{get_simple_rmgr_info, ResourceManager, RmgrId};
+use pg_dig_server::postgres::common::transaction_id::TransactionId;
+use pg_dig_server::postgres::records::heap::{
+    HeapDelete, HeapDeleteFlags, HeapInsert, HeapInsertFlags, HeapRecord, HeapTruncate, HeapTruncateFlags, HeapUpdate,
+    HeapUpdateFlags, InfoBits,
+};
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
+
+#[test]
+fn heap_insert() {
+    // rmgr: Heap desc: INSERT+INIT off: 1, flags: 0x08
+    let message = decode(&wal_record(ResourceManager::Heap, 0x80, &[b"tuple"], &[0x01, 0x00, 0x08]));
+
+    assert_eq!(message.record_type(), "INSERT+INIT");
+    assert_eq!(rmgr_record!(message, Heap), &HeapRecord::Insert(HeapInsert {
+        offnum: 1,
+        flags: HeapInsertFlags::XLH_INSERT_CONTAINS_NEW_TUPLE,
+        init_page: true,
+    }));
+    assert_eq!(message.get_block_numbers(), vec![0]);
+}
+
+#[test]
+fn heap_delete() {
+    // rmgr: Heap desc: DELETE xmax: 747, off: 3, infobits: [KEYS_UPDATED], flags: 0x01
+    let main_data = [0xEB, 0x02, 0x00, 0x00, 0x03, 0x00, 0x10, 0x01];
+    let message = decode(&wal_record(ResourceManager::Heap, 0x10, &[&[]], &main_data));
+
+    assert_eq!(message.record_type(), "DELETE");
+    assert_eq!(rmgr_record!(message, Heap), &HeapRecord::Delete(HeapDelete {
+        xmax: TransactionId(747),
+        offnum: 3,
+        infobits_set: InfoBits::XLHL_KEYS_UPDATED,
+        flags: HeapDeleteFlags::XLH_DELETE_ALL_VISIBLE_CLEARED,
+    }));
+}
+
+#[test]
+fn heap_updates() {
+    // old_xmax: 747, old_off: 2, old_infobits: [], flags: 0x10, new_xmax: 0, new_off: 9
+    let main_data = [0xEB, 0x02, 0x00, 0x00, 0x02, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x09, 0x00];
+    let expected = HeapUpdate {
+        old_xmax: TransactionId(747),
+        old_offnum: 2,
+        old_infobits_set: InfoBits::empty(),
+        flags: HeapUpdateFlags::XLH_UPDATE_CONTAINS_NEW_TUPLE,
+        new_xmax: TransactionId(0),
+        new_offnum: 9,
+        init_page: false,
+    };
+
+    let update = decode(&wal_record(ResourceManager::Heap, 0x20, &[b"new", b"old"], &main_data));
+    assert_eq!(update.record_type(), "UPDATE");
+    assert_eq!(rmgr_record!(update, Heap), &HeapRecord::Update(expected.clone()));
+    assert_eq!(update.get_block_numbers(), vec![0, 1]);
+
+    let hot_update = decode(&wal_record(ResourceManager::Heap, 0x40, &[b"new"], &main_data));
+    assert_eq!(hot_update.record_type(), "HOT_UPDATE");
+    assert_eq!(rmgr_record!(hot_update, Heap), &HeapRecord::HotUpdate(expected));
+}
+
+#[test]
+fn heap_truncate() {
+    // dbId: 5, nrelids: 2, flags: CASCADE, relids: 16384 16390
+    let main_data = [
+        0x05, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x40, 0x00, 0x00, 0x06, 0x40, 0x00, 0x00,
+    ];
+    let message = decode(&wal_record(ResourceManager::Heap, 0x30, &[], &main_data));
+
+    assert_eq!(message.record_type(), "TRUNCATE");
+    assert_eq!(rmgr_record!(message, Heap), &HeapRecord::Truncate(HeapTruncate {
+        db_id: 5,
+        flags: HeapTruncateFlags::XLH_TRUNCATE_CASCADE,
+        relids: vec![16384, 16390],
+    }));
+}
+
+#[test]
+fn heap_confirm_lock_and_inplace() {
+    let confirm = decode(&wal_record(ResourceManager::Heap, 0x50, &[&[]], &[0x04, 0x00]));
+    assert_eq!(rmgr_record!(confirm, Heap), &HeapRecord::Confirm { offnum: 4 });
+
+    let lock = decode(&wal_record(ResourceManager::Heap, 0x60, &[&[]], &[0xEB, 0x02, 0x00, 0x00, 0x05, 0x00, 0x06, 0x00]));
+    assert_eq!(lock.record_type(), "LOCK");
+    assert!(matches!(rmgr_record!(lock, Heap), HeapRecord::Lock(lock)
+        if lock.offnum == 5 && lock.infobits_set == InfoBits::XLHL_XMAX_LOCK_ONLY | InfoBits::XLHL_XMAX_EXCL_LOCK));
+
+    let inplace = decode(&wal_record(ResourceManager::Heap, 0x70, &[b"tuple"], &[0x07, 0x00]));
+    assert_eq!(inplace.record_type(), "INPLACE");
+    assert_eq!(rmgr_record!(inplace, Heap), &HeapRecord::Inplace { offnum: 7 });
+}
+
+#[test]
+fn heap_record_type_ignores_flag_bits() {
+    // XLR_SPECIAL_REL_UPDATE and XLR_CHECK_CONSISTENCY live in the low bits
+    let info = get_simple_rmgr_info(RmgrId(ResourceManager::Heap as u8), 0x43).unwrap();
+
+    assert_eq!(info.record_type, "HOT_UPDATE");
+}
+
+#[test]
+fn heap_truncated_main_data() {
+    let record = wal_record(ResourceManager::Heap, 0x10, &[&[]], &[0xEB, 0x02, 0x00, 0x00, 0x03, 0x00]);
+
+    assert!(matches!(
+        decode_failure(&record),
+        DecodeError::Truncated { field: "xl_heap_delete.infobits_set", .. }
+    ));
+}
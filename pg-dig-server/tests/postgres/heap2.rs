@@ -0,0 +1,139 @@
+use crate::postgres::record_builder::{decode, decode_failure, rmgr_record, test_buffer_record, wal_record};
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::common::transaction_id::TransactionId;
+use pg_dig_server::postgres::common::RelFileLocator;
+use pg_dig_server::postgres::records::heap::HeapInsertFlags;
+use pg_dig_server::postgres::records::heap2::{
+    Heap2Record, HeapFreezePage, HeapMultiInsert, HeapNewCid, HeapPrune, HeapVisible, VisibilityMapFlags,
+};
+use pg_dig_server::postgres::records::ItemPointer;
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
+
+const INVALID_COMMAND_ID: u32 = 0xFFFFFFFF;
+
+#[test]
+fn heap2_new_cid() {
+    // rmgr: Heap2 desc: NEW_CID rel: 1663/5/1259, tid: 0/3, cmin: 4294967295, cmax: 0, combo: 4294967295
+    let first = test_buffer_record(0x1552D00);
+    assert_eq!(first.record_type(), "NEW_CID");
+    assert_eq!(rmgr_record!(first, Heap2), &Heap2Record::NewCid(HeapNewCid {
+        top_xid: TransactionId(746),
+        cmin: INVALID_COMMAND_ID,
+        cmax: 0,
+        combocid: INVALID_COMMAND_ID,
+        target_locator: RelFileLocator { spc_oid: 1663, db_oid: 5, rel_number: 1259 },
+        target_tid: ItemPointer { block: 0, offnum: 3 },
+    }));
+
+    let second = test_buffer_record(0x1552D40);
+    assert!(matches!(rmgr_record!(second, Heap2), Heap2Record::NewCid(new_cid)
+        if new_cid.cmin == 0 && new_cid.cmax == INVALID_COMMAND_ID && new_cid.target_tid.offnum == 6));
+}
+
+#[test]
+fn heap2_new_cid_block_number() {
+    // tid: 65539/2, the block number split over bi_hi and bi_lo
+    let mut main_data = vec![0xEA, 0x02, 0x00, 0x00];
+    main_data.extend_from_slice(&[0; 12]);
+    [1663u32, 5, 1259].iter().for_each(|oid| main_data.extend_from_slice(&oid.to_le_bytes()));
+    main_data.extend_from_slice(&[0x01, 0x00, 0x03, 0x00, 0x02, 0x00]);
+    let message = decode(&wal_record(ResourceManager::Heap2, 0x70, &[], &main_data));
+
+    assert!(matches!(rmgr_record!(message, Heap2), Heap2Record::NewCid(new_cid)
+        if new_cid.target_tid == ItemPointer { block: 0x10003, offnum: 2 }));
+}
+
+#[test]
+fn heap2_prune() {
+    // snapshotConflictHorizon: 740, nredirected: 1, ndead: 2, isCatalogRel: F
+    // block 0: redirect 3->5, dead 4 7, unused 8 9 10
+    let main_data = [0xE4, 0x02, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00];
+    let offsets: Vec<u8> = [3u16, 5, 4, 7, 8, 9, 10].iter().flat_map(|offnum| offnum.to_le_bytes()).collect();
+    let message = decode(&wal_record(ResourceManager::Heap2, 0x10, &[&offsets], &main_data));
+
+    assert_eq!(message.record_type(), "PRUNE");
+    assert_eq!(rmgr_record!(message, Heap2), &Heap2Record::Prune(HeapPrune {
+        snapshot_conflict_horizon: TransactionId(740),
+        nredirected: 1,
+        ndead: 2,
+        nunused: 3,
+        is_catalog_rel: false,
+    }));
+}
+
+#[test]
+fn heap2_prune_short_block_data() {
+    // two redirects need four offsets, the block holds three
+    let main_data = [0xE4, 0x02, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00];
+    let record = wal_record(ResourceManager::Heap2, 0x10, &[&[0x03, 0x00, 0x05, 0x00, 0x04, 0x00]], &main_data);
+
+    assert!(matches!(
+        decode_failure(&record),
+        DecodeError::Truncated { field: "xl_heap_prune redirected and dead offsets", .. }
+    ));
+}
+
+#[test]
+fn heap2_vacuum_freeze_and_visible() {
+    let vacuum = decode(&wal_record(ResourceManager::Heap2, 0x20, &[&[0x02, 0x00, 0x03, 0x00]], &[0x02, 0x00]));
+    assert_eq!(vacuum.record_type(), "VACUUM");
+    assert_eq!(rmgr_record!(vacuum, Heap2), &Heap2Record::Vacuum { nunused: 2 });
+
+    // snapshotConflictHorizon: 0, nplans: 1, isCatalogRel: T
+    let freeze = decode(&wal_record(ResourceManager::Heap2, 0x30, &[b"plans"], &[0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01]));
+    assert_eq!(freeze.record_type(), "FREEZE_PAGE");
+    assert_eq!(rmgr_record!(freeze, Heap2), &Heap2Record::FreezePage(HeapFreezePage {
+        snapshot_conflict_horizon: TransactionId(0),
+        nplans: 1,
+        is_catalog_rel: true,
+    }));
+
+    // the visibility map page is block 0, the heap page block 1
+    let visible = decode(&wal_record(ResourceManager::Heap2, 0x40, &[&[], &[]], &[0xEA, 0x02, 0x00, 0x00, 0x03]));
+    assert_eq!(visible.record_type(), "VISIBLE");
+    assert_eq!(rmgr_record!(visible, Heap2), &Heap2Record::Visible(HeapVisible {
+        snapshot_conflict_horizon: TransactionId(746),
+        flags: VisibilityMapFlags::VISIBILITYMAP_ALL_VISIBLE | VisibilityMapFlags::VISIBILITYMAP_ALL_FROZEN,
+    }));
+    assert_eq!(visible.get_block_numbers(), vec![0, 1]);
+}
+
+#[test]
+fn heap2_multi_insert() {
+    // flags: 0x08, ntuples: 3, offsets: 4 5 6
+    let main_data = [0x08, 0x00, 0x03, 0x00, 0x04, 0x00, 0x05, 0x00, 0x06, 0x00];
+    let message = decode(&wal_record(ResourceManager::Heap2, 0x50, &[b"tuples"], &main_data));
+
+    assert_eq!(message.record_type(), "MULTI_INSERT");
+    assert_eq!(rmgr_record!(message, Heap2), &Heap2Record::MultiInsert(HeapMultiInsert {
+        flags: HeapInsertFlags::XLH_INSERT_CONTAINS_NEW_TUPLE,
+        ntuples: 3,
+        offsets: vec![4, 5, 6],
+        init_page: false,
+    }));
+
+    // an initialized page leaves out the offsets
+    let init = decode(&wal_record(ResourceManager::Heap2, 0xD0, &[b"tuples"], &main_data[..4]));
+    assert_eq!(init.record_type(), "MULTI_INSERT+INIT");
+    assert!(matches!(rmgr_record!(init, Heap2), Heap2Record::MultiInsert(multi_insert)
+        if multi_insert.ntuples == 3 && multi_insert.offsets.is_empty() && multi_insert.init_page));
+}
+
+#[test]
+fn heap2_lock_updated_and_rewrite() {
+    let lock = decode(&wal_record(ResourceManager::Heap2, 0x60, &[&[]], &[0xEB, 0x02, 0x00, 0x00, 0x02, 0x00, 0x02, 0x01]));
+    assert_eq!(lock.record_type(), "LOCK_UPDATED");
+    assert!(matches!(rmgr_record!(lock, Heap2), Heap2Record::LockUpdated(lock) if lock.offnum == 2));
+
+    // mapped_xid: 746, db: 5, rel: 16384, offset: 64, num_mappings: 2, start_lsn: 0/1552C80
+    let mut main_data = Vec::new();
+    [746u32, 5, 16384, 0].iter().for_each(|field| main_data.extend_from_slice(&field.to_le_bytes()));
+    main_data.extend_from_slice(&64i64.to_le_bytes());
+    main_data.extend_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    main_data.extend_from_slice(&0x1552C80u64.to_le_bytes());
+    let rewrite = decode(&wal_record(ResourceManager::Heap2, 0x00, &[], &main_data));
+
+    assert_eq!(rewrite.record_type(), "REWRITE");
+    assert!(matches!(rmgr_record!(rewrite, Heap2), Heap2Record::Rewrite(mapping)
+        if mapping.mapped_rel == 16384 && mapping.offset == 64 && mapping.num_mappings == 2 && mapping.start_lsn == 0x1552C80));
+}
@@ -0,0 +1,121 @@
+use crate::postgres::record_builder::{decode, decode_failure, le_bytes, test_buffer_record, wal_record};
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::common::RelFileLocator;
+use pg_dig_server::postgres::lifecycle::LifecycleEvent;
+use pg_dig_server::postgres::records::dbase::DbaseRecord;
+use pg_dig_server::postgres::records::storage::{SmgrTruncateFlags, StorageRecord, FSM_FORKNUM, MAIN_FORKNUM};
+use pg_dig_server::postgres::records::tablespace::TablespaceRecord;
+use pg_dig_server::postgres::records::RmgrRecord;
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
+use pg_dig_server::postgres::xlog_message::XLogMessage;
+
+const LOCATOR: RelFileLocator = RelFileLocator { spc_oid: 1663, db_oid: 5, rel_number: 16384 };
+
+fn events(message: &XLogMessage) -> Vec<String> {
+    LifecycleEvent::from_message(message).iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn storage_create() {
+    // rmgr: Storage desc: CREATE base/5/32768
+    let message = test_buffer_record(0x1552CD0);
+    let locator = RelFileLocator { spc_oid: 1663, db_oid: 5, rel_number: 32768 };
+
+    assert_eq!(message.record_type(), "CREATE");
+    assert_eq!(message.record, RmgrRecord::Storage(StorageRecord::Create { locator, fork: MAIN_FORKNUM }));
+    assert_eq!(LifecycleEvent::from_message(&message), vec![LifecycleEvent::RelationCreated { locator, fork: MAIN_FORKNUM }]);
+    assert_eq!(events(&message), vec!["rel 32768 created in db 5"]);
+    assert_eq!(LifecycleEvent::from_message(&message)[0].resets_relation(), Some(&locator));
+}
+
+#[test]
+fn storage_create_other_fork() {
+    let message = decode(&wal_record(ResourceManager::Storage, 0x10, &[], &le_bytes(&[1663, 5, 16384, FSM_FORKNUM as u32])));
+
+    assert_eq!(events(&message), vec!["rel 16384 fsm fork created in db 5"]);
+    assert_eq!(LifecycleEvent::from_message(&message)[0].resets_relation(), None);
+}
+
+#[test]
+fn storage_truncate() {
+    // rmgr: Storage desc: TRUNCATE base/5/16384 to 0 blocks flags 7
+    let message = decode(&wal_record(ResourceManager::Storage, 0x20, &[], &le_bytes(&[0, 1663, 5, 16384, 7])));
+
+    assert_eq!(message.record_type(), "TRUNCATE");
+    assert_eq!(message.record, RmgrRecord::Storage(StorageRecord::Truncate {
+        block: 0,
+        locator: LOCATOR,
+        flags: SmgrTruncateFlags::all(),
+    }));
+    assert_eq!(events(&message), vec!["rel 16384 in db 5 truncated to block 0"]);
+    assert_eq!(LifecycleEvent::from_message(&message)[0].resets_relation(), Some(&LOCATOR));
+}
+
+#[test]
+fn storage_truncate_cut_short() {
+    let record = wal_record(ResourceManager::Storage, 0x20, &[], &le_bytes(&[0, 1663, 5]));
+
+    assert!(matches!(decode_failure(&record), DecodeError::Truncated { .. }));
+}
+
+#[test]
+fn database_create_and_drop() {
+    // rmgr: Database desc: CREATE_FILE_COPY copy dir 1663/1 to 1663/16390
+    let file_copy = decode(&wal_record(ResourceManager::Database, 0x00, &[], &le_bytes(&[16390, 1663, 1, 1663])));
+    // rmgr: Database desc: CREATE_WAL_LOG create dir 1663/16391
+    let wal_log = decode(&wal_record(ResourceManager::Database, 0x10, &[], &le_bytes(&[16391, 1663])));
+    // rmgr: Database desc: DROP dir 1663/16390 16400/16390
+    let drop = decode(&wal_record(ResourceManager::Database, 0x20, &[], &le_bytes(&[16390, 2, 1663, 16400])));
+
+    assert_eq!(file_copy.record_type(), "CREATE_FILE_COPY");
+    assert_eq!(file_copy.record, RmgrRecord::Database(DbaseRecord::CreateFileCopy {
+        db_id: 16390,
+        tablespace_id: 1663,
+        src_db_id: 1,
+        src_tablespace_id: 1663,
+    }));
+    assert_eq!(events(&file_copy), vec!["db 16390 created in tablespace 1663"]);
+
+    assert_eq!(wal_log.record_type(), "CREATE_WAL_LOG");
+    assert_eq!(wal_log.record, RmgrRecord::Database(DbaseRecord::CreateWalLog { db_id: 16391, tablespace_id: 1663 }));
+    assert_eq!(events(&wal_log), vec!["db 16391 created in tablespace 1663"]);
+
+    assert_eq!(drop.record_type(), "DROP");
+    assert_eq!(drop.record, RmgrRecord::Database(DbaseRecord::Drop { db_id: 16390, tablespace_ids: vec![1663, 16400] }));
+    assert_eq!(events(&drop), vec!["db 16390 dropped"]);
+    assert_eq!(LifecycleEvent::from_message(&drop)[0].resets_relation(), None);
+}
+
+#[test]
+fn tablespace_create_and_drop() {
+    // rmgr: Tablespace desc: CREATE 16400 "/mnt/fast"
+    let mut main_data = le_bytes(&[16400]);
+    main_data.extend_from_slice(b"/mnt/fast\0");
+    let create = decode(&wal_record(ResourceManager::Tablespace, 0x00, &[], &main_data));
+    let drop = decode(&wal_record(ResourceManager::Tablespace, 0x10, &[], &le_bytes(&[16400])));
+
+    assert_eq!(create.record_type(), "CREATE");
+    assert_eq!(create.record, RmgrRecord::Tablespace(TablespaceRecord::Create { ts_id: 16400, path: "/mnt/fast".to_string() }));
+    assert_eq!(events(&create), vec!["tablespace 16400 created at /mnt/fast"]);
+
+    assert_eq!(drop.record_type(), "DROP");
+    assert_eq!(drop.record, RmgrRecord::Tablespace(TablespaceRecord::Drop { ts_id: 16400 }));
+    assert_eq!(events(&drop), vec!["tablespace 16400 dropped"]);
+}
+
+#[test]
+fn commit_drops_relations() {
+    // rmgr: Transaction desc: COMMIT 2025-10-11 09:14:56.123456 UTC; rels: 1663/5/16384 1663/5/16387
+    let mut main_data = 813_489_296_123_456i64.to_le_bytes().to_vec();
+    main_data.extend(le_bytes(&[0x04, 2, 1663, 5, 16384, 1663, 5, 16387]));
+    let message = decode(&wal_record(ResourceManager::Transaction, 0x80, &[], &main_data));
+
+    assert_eq!(events(&message), vec!["rel 16384 dropped from db 5", "rel 16387 dropped from db 5"]);
+    assert_eq!(LifecycleEvent::from_message(&message)[0].resets_relation(), Some(&LOCATOR));
+}
+
+#[test]
+fn other_records_have_no_events() {
+    assert!(LifecycleEvent::from_message(&test_buffer_record(0x1552C80)).is_empty());
+    assert!(LifecycleEvent::from_message(&test_buffer_record(0x1552D00)).is_empty());
+}
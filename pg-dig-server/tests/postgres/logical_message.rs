@@ -0,0 +1,52 @@
+use crate::postgres::record_builder::{decode, decode_failure, rmgr_record, wal_record};
+use pg_dig_server::postgres::common::rmgr::{get_simple_rmgr_info, ResourceManager, RmgrId};
+use pg_dig_server::postgres::records::logical_message::{LogicalMessage, LogicalMessageRecord};
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
+
+fn message_data(transactional: bool, prefix: &str, payload: &[u8]) -> Vec<u8> {
+    let mut main_data = 5u32.to_le_bytes().to_vec();
+    main_data.extend_from_slice(&[transactional as u8, 0x00, 0x00, 0x00]);
+    main_data.extend((prefix.len() as u64 + 1).to_le_bytes());
+    main_data.extend((payload.len() as u64).to_le_bytes());
+    main_data.extend_from_slice(prefix.as_bytes());
+    main_data.push(0);
+    main_data.extend_from_slice(payload);
+    main_data
+}
+
+#[test]
+fn logical_message_marker() {
+    // SELECT pg_logical_emit_message(false, 'deploy', 'v2.3.1 started');
+    let message = decode(&wal_record(ResourceManager::LogicalMessage, 0x00, &[], &message_data(false, "deploy", b"v2.3.1 started")));
+
+    assert_eq!(message.record_type(), "MESSAGE");
+    let LogicalMessageRecord::Message(marker) = rmgr_record!(message, LogicalMessage);
+    assert_eq!(marker, &LogicalMessage {
+        db_id: 5,
+        transactional: false,
+        prefix: "deploy".to_string(),
+        payload: b"v2.3.1 started".to_vec(),
+    });
+    assert_eq!(marker.to_string(), "deploy: v2.3.1 started");
+    assert_eq!(get_simple_rmgr_info(RmgrId(ResourceManager::LogicalMessage as u8), 0x00).unwrap().rmgr_name, "LogicalMessage");
+}
+
+#[test]
+fn logical_message_transactional_binary_payload() {
+    let message = decode(&wal_record(ResourceManager::LogicalMessage, 0x00, &[], &message_data(true, "job", &[0xFF, b'x'])));
+    let LogicalMessageRecord::Message(marker) = rmgr_record!(message, LogicalMessage);
+
+    assert!(marker.transactional);
+    assert_eq!(marker.payload.len(), 2);
+    assert_eq!(marker.payload_text(), "\u{FFFD}x");
+    assert_eq!(marker.to_string(), "job: \u{FFFD}x (transactional)");
+}
+
+#[test]
+fn logical_message_payload_shorter_than_size() {
+    let mut main_data = message_data(false, "deploy", b"done");
+    main_data.truncate(main_data.len() - 1);
+    let error = decode_failure(&wal_record(ResourceManager::LogicalMessage, 0x00, &[], &main_data));
+
+    assert!(matches!(error, DecodeError::Truncated { .. }));
+}
@@ -0,0 +1,222 @@
+use crate::postgres::fake_walsender::{FakeWalSender, Script, Step};
+use crate::postgres::test_data::TEST_BUFFER;
+use pg_dig_server::config::{Config, StartPosition};
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::error::PgDigError;
+use pg_dig_server::postgres::message_stream::MessageStream;
+use pg_dig_server::postgres::replication::LiveSource;
+use pg_dig_server::postgres::source::WalSource;
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
+use pg_dig_server::postgres::xlog_message::{XLogMessage, XLogMessageHeader};
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// A source that plays back a fixed list of results, and shares what it was asked to do.
+struct FakeSource {
+    results: VecDeque<(u64, Result<XLogMessage, PgDigError>)>,
+    last_record_end: u64,
+    log: Arc<SharedLog>,
+}
+
+#[derive(Default)]
+struct SourceLog {
+    reads: usize,
+    keep_alives: usize,
+    confirmed: Vec<u64>,
+}
+
+/// The log of a FakeSource, which the test can wait on while the reader thread catches up.
+#[derive(Default)]
+struct SharedLog {
+    log: Mutex<SourceLog>,
+    changed: Condvar,
+}
+
+impl SharedLog {
+    fn record(&self, call: impl FnOnce(&mut SourceLog)) {
+        call(&mut self.log.lock().unwrap());
+        self.changed.notify_all();
+    }
+
+    /// Waits until the reader thread has done what `done` looks for, failing the test if that
+    /// takes more than a few seconds.
+    fn wait_for(&self, done: impl Fn(&SourceLog) -> bool) -> MutexGuard<'_, SourceLog> {
+        let log = self.log.lock().unwrap();
+        let (log, wait) = self.changed.wait_timeout_while(log, Duration::from_secs(10), |log| !done(log)).unwrap();
+        assert!(!wait.timed_out(), "timed out waiting for the reader thread");
+        log
+    }
+}
+
+impl FakeSource {
+    fn spawn(results: Vec<(u64, Result<XLogMessage, PgDigError>)>, capacity: usize) -> (MessageStream, Arc<SharedLog>) {
+        let log = Arc::new(SharedLog::default());
+        let source = FakeSource {
+            results: results.into(),
+            last_record_end: 0,
+            log: log.clone(),
+        };
+
+        let stream = MessageStream::spawn(move || Ok(Box::new(source) as Box<dyn WalSource>), capacity);
+        (stream, log)
+    }
+}
+
+impl WalSource for FakeSource {
+    fn next_message(&mut self) -> Result<XLogMessage, PgDigError> {
+        self.log.record(|log| log.reads += 1);
+        let (end_lsn, result) = self.results.pop_front().unwrap_or((0, Err(PgDigError::EndOfStream)));
+        self.last_record_end = end_lsn;
+        result
+    }
+
+    fn confirm(&mut self, lsn: u64) {
+        self.log.record(|log| log.confirmed.push(lsn));
+    }
+
+    fn last_record_end(&self) -> u64 {
+        self.last_record_end
+    }
+
+    fn keep_alive(&mut self) -> Result<(), PgDigError> {
+        self.log.record(|log| log.keep_alives += 1);
+        Ok(())
+    }
+}
+
+fn message(end_lsn: u64) -> Result<XLogMessage, PgDigError> {
+    let mut message = XLogMessage::from_bytes(&TEST_BUFFER[1..]).unwrap();
+    message.header.end_lsn = end_lsn;
+    Ok(message)
+}
+
+/// A record that could not be decoded, which reading skips over.
+fn malformed() -> PgDigError {
+    PgDigError::MalformedRecord(DecodeError::UnknownRecordType { rmgr: ResourceManager::Btree, info: 0xF0 })
+}
+
+#[test]
+fn hands_out_messages_and_skips_errors() {
+    let (stream, _) = FakeSource::spawn(vec![
+        (0x10, message(0x10)),
+        (0x20, Err(malformed())),
+        (0x30, message(0x30)),
+    ], 4);
+
+    let end_lsns: Vec<u64> = stream.map(|message| message.unwrap().header.end_lsn).collect();
+    assert_eq!(end_lsns, vec![0x10, 0x30]);
+}
+
+#[test]
+fn confirms_a_message_once_the_next_is_taken() {
+    let (stream, log) = FakeSource::spawn(vec![
+        (0x10, message(0x10)),
+        (0x20, Err(malformed())),
+        (0x30, message(0x30)),
+        (0x40, message(0x40)),
+    ], 1);
+
+    // the reader has queued 0x30 and waits for room, with nothing handled yet
+    assert_eq!(stream.recv().unwrap().unwrap().header.end_lsn, 0x10);
+    assert!(log.wait_for(|log| log.reads == 3).confirmed.is_empty());
+
+    // the skipped record is confirmed along with the message before it
+    assert_eq!(stream.recv().unwrap().unwrap().header.end_lsn, 0x30);
+    assert_eq!(log.wait_for(|log| !log.confirmed.is_empty()).confirmed, vec![0x20]);
+}
+
+#[test]
+fn stops_reading_while_the_consumer_is_behind() {
+    let results = (1..=10).map(|i| (i * 0x10, message(i * 0x10))).collect();
+    let (stream, log) = FakeSource::spawn(results, 2);
+
+    // a full queue is waited out with keepalives rather than more reads
+    assert_eq!(log.wait_for(|log| log.keep_alives > 0).reads, 2);
+
+    stream.recv().unwrap().unwrap();
+    let keep_alives = log.wait_for(|log| log.reads == 3).keep_alives;
+    assert_eq!(log.wait_for(|log| log.keep_alives > keep_alives).reads, 3);
+    assert_eq!(stream.count(), 9);
+}
+
+#[test]
+fn ends_with_the_first_error() {
+    let (stream, _) = FakeSource::spawn(vec![
+        (0x10, message(0x10)),
+        (0x20, Err(PgDigError::Protocol("bad message".to_string()))),
+        (0x30, message(0x30)),
+    ], 4);
+
+    let results: Vec<_> = stream.collect();
+    assert_eq!(results.len(), 2);
+    assert!(matches!(results[1], Err(PgDigError::Protocol(_))));
+}
+
+#[test]
+fn hands_out_an_error_opening_the_source() {
+    let stream = MessageStream::spawn(|| Err(PgDigError::Config("no source".to_string())), 4);
+
+    assert!(matches!(stream.recv(), Some(Err(PgDigError::Config(_)))));
+    assert!(stream.recv().is_none());
+    assert!(stream.is_finished());
+}
+
+fn live_stream(server: &FakeWalSender, status_interval: Duration) -> MessageStream {
+    let config = Config {
+        connection_string: server.connection_string(),
+        start_position: StartPosition::Lsn(0x1552C80),
+        status_interval,
+        ..Config::default()
+    };
+
+    MessageStream::spawn(move || Ok(Box::new(LiveSource::connect(&config)?) as Box<dyn WalSource>), 4)
+}
+
+#[test]
+fn streams_from_a_live_server() {
+    let records = TEST_BUFFER[1 + size_of::<XLogMessageHeader>()..][..0x100].to_vec();
+    let server = FakeWalSender::start(Script {
+        streams: vec![vec![Step::XLogData { start_lsn: 0x1552C80, bytes: records }]],
+        ..Script::default()
+    });
+
+    let stream = live_stream(&server, Duration::from_secs(10));
+    let start_lsns: Vec<u64> = stream.map(|message| message.unwrap().header.start_lsn).collect();
+    server.finish();
+
+    assert_eq!(start_lsns, vec![0x1552C80, 0x1552CB0, 0x1552CD0, 0x1552D00, 0x1552D40]);
+}
+
+#[test]
+fn reports_progress_while_the_server_is_idle() {
+    let server = FakeWalSender::start(Script {
+        streams: vec![vec![Step::ExpectStatusUpdate, Step::ExpectStatusUpdate, Step::ExpectStatusUpdate]],
+        ..Script::default()
+    });
+
+    let stream = live_stream(&server, Duration::from_millis(100));
+    assert!(stream.recv().is_none());
+    let observed = server.finish();
+
+    assert!(observed.status_updates.len() >= 3);
+}
+
+#[test]
+fn cancel_wakes_a_waiting_source() {
+    let server = FakeWalSender::start(Script {
+        streams: vec![vec![Step::ExpectStatusUpdate, Step::ExpectStatusUpdate]],
+        ..Script::default()
+    });
+
+    let stream = live_stream(&server, Duration::from_secs(60));
+    server.wait_for(|observed| observed.queries.iter().any(|query| query.starts_with("START_REPLICATION")));
+
+    let started = Instant::now();
+    stream.cancel();
+    assert!(stream.recv().is_none());
+    drop(stream);
+    server.finish();
+
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
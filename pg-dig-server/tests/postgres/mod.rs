@@ -1,2 +1,36 @@
 mod test_data;
 mod xlog;
+mod rmgr;
+mod reassembler;
+mod feedback;
+mod timeline;
+mod wal_file;
+mod source;
+mod capture;
+mod fake_walsender;
+mod replication;
+mod authentication;
+mod native_client;
+
+mod message_stream;
+mod supervisor;
+mod record_builder;
+mod heap;
+mod heap2;
+mod btree;
+mod xlog_rmgr;
+mod xact;
+mod standby;
+mod lifecycle;
+mod gin;
+mod gist;
+mod spgist;
+mod hash;
+mod brin;
+mod sequence;
+mod multixact;
+mod clog;
+mod consumption;
+mod relmap;
+mod generic;
+mod logical_message;
\ No newline at end of file
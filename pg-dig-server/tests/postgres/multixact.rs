@@ -0,0 +1,51 @@
+use crate::postgres::record_builder::{decode, le_bytes, rmgr_record, wal_record};
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::common::transaction_id::TransactionId;
+use pg_dig_server::postgres::records::multixact::{
+    MultiXactCreate, MultiXactMember, MultiXactRecord, MultiXactStatus, MultiXactTruncate,
+};
+
+/// The main data of a CREATE_ID record for multixact `mid`, with `members` as (xid, status) pairs.
+pub fn create_id(mid: u32, members: &[(u32, u32)]) -> Vec<u8> {
+    let mut main_data = le_bytes(&[mid, mid * 2, members.len() as u32]);
+    members.iter().for_each(|(xid, status)| main_data.extend(le_bytes(&[*xid, *status])));
+    main_data
+}
+
+#[test]
+fn multixact_create() {
+    // rmgr: MultiXact desc: CREATE_ID 12 offset 24 nmembers 2: 746 (keysh) 747 (nokeyupd)
+    let message = decode(&wal_record(ResourceManager::MultiXact, 0x20, &[], &create_id(12, &[(746, 0), (747, 4)])));
+
+    assert_eq!(message.record_type(), "CREATE_ID");
+    assert_eq!(rmgr_record!(message, MultiXact), &MultiXactRecord::Create(MultiXactCreate {
+        mid: 12,
+        offset: 24,
+        members: vec![
+            MultiXactMember { xid: TransactionId(746), status: MultiXactStatus::ForKeyShare },
+            MultiXactMember { xid: TransactionId(747), status: MultiXactStatus::NoKeyUpdate },
+        ],
+    }));
+}
+
+#[test]
+fn multixact_zero_pages_and_truncate() {
+    let offsets = decode(&wal_record(ResourceManager::MultiXact, 0x00, &[], &le_bytes(&[3])));
+    assert_eq!(offsets.record_type(), "ZERO_OFF_PAGE");
+    assert_eq!(rmgr_record!(offsets, MultiXact), &MultiXactRecord::ZeroOffsetPage { page: 3 });
+
+    let members = decode(&wal_record(ResourceManager::MultiXact, 0x10, &[], &le_bytes(&[7])));
+    assert_eq!(members.record_type(), "ZERO_MEM_PAGE");
+    assert_eq!(rmgr_record!(members, MultiXact), &MultiXactRecord::ZeroMemberPage { page: 7 });
+
+    // rmgr: MultiXact desc: TRUNCATE_ID offsets [1, 4000), members [1, 9000)
+    let truncate = decode(&wal_record(ResourceManager::MultiXact, 0x30, &[], &le_bytes(&[5, 1, 4000, 1, 9000])));
+    assert_eq!(truncate.record_type(), "TRUNCATE_ID");
+    assert_eq!(rmgr_record!(truncate, MultiXact), &MultiXactRecord::Truncate(MultiXactTruncate {
+        oldest_multi_db: 5,
+        start_trunc_off: 1,
+        end_trunc_off: 4000,
+        start_trunc_memb: 1,
+        end_trunc_memb: 9000,
+    }));
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "native-client")]
+
+use pg_dig_server::postgres::error::PgDigError;
+use pg_dig_server::postgres::native::auth::{md5_password, ScramSha256};
+use pg_dig_server::postgres::native::conn_string::ConnectionParams;
+
+#[test]
+fn parses_a_connection_string() {
+    let params = ConnectionParams::parse(
+        "host=db.internal port=5433 user=replicator password='it\\'s secret' dbname=postgres replication=true sslmode=disable",
+    ).unwrap();
+
+    assert_eq!(params.host, "db.internal");
+    assert_eq!(params.port, 5433);
+    assert_eq!(params.user, "replicator");
+    assert_eq!(params.password.as_deref(), Some("it's secret"));
+    assert_eq!(params.dbname.as_deref(), Some("postgres"));
+    assert_eq!(params.replication.as_deref(), Some("true"));
+}
+
+#[test]
+fn refuses_what_the_native_client_cannot_do() {
+    for conn_string in [
+        "host=localhost sslmode=require",
+        "host=localhost bogus=1",
+        "postgres://localhost/postgres",
+        "host=localhost password='unterminated",
+    ] {
+        assert!(
+            matches!(ConnectionParams::parse(conn_string), Err(PgDigError::Connection(_))),
+            "{} should be refused",
+            conn_string
+        );
+    }
+}
+
+#[test]
+fn hashes_md5_passwords() {
+    assert_eq!(md5_password("postgres", "secret", &[1, 2, 3, 4]), "md5bb41a296aab6baccb36ff243a562abff");
+}
+
+/// The SCRAM-SHA-256 example exchange from RFC 7677, section 3.
+#[test]
+fn follows_the_rfc_7677_exchange() {
+    let mut scram = ScramSha256::with_nonce("user", "pencil", "rOprNGfwEbeRWgbNEkqO");
+    assert_eq!(scram.client_first(), "n,,n=user,r=rOprNGfwEbeRWgbNEkqO");
+
+    let client_final = scram.client_final(
+        "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096",
+    ).unwrap();
+    assert_eq!(
+        client_final,
+        "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ="
+    );
+
+    assert!(scram.verify_server_final("v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=").is_ok());
+    assert!(scram.verify_server_final("v=AAAATRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=").is_err());
+}
+
+#[test]
+fn rejects_a_server_nonce_that_does_not_extend_ours() {
+    let mut scram = ScramSha256::with_nonce("", "pencil", "clientnonce");
+    assert!(scram.client_final("r=othernonce,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096").is_err());
+}
@@ -0,0 +1,145 @@
+use crate::postgres::test_data::TEST_BUFFER;
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
+use pg_dig_server::postgres::xlog::reassembler::{ReassembledRecord, RecordReassembler};
+use pg_dig_server::postgres::xlog_message::XLogMessageHeader;
+
+const MESSAGE_START_LSN: u64 = 0x1552C80;
+
+fn wal_bytes() -> &'static [u8] {
+    &TEST_BUFFER[1 + size_of::<XLogMessageHeader>()..]
+}
+
+pub fn page_header(info: u16, page_address: u64, rem_len: u32) -> Vec<u8> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&0xD113u16.to_le_bytes());
+    header.extend_from_slice(&info.to_le_bytes());
+    header.extend_from_slice(&1u32.to_le_bytes());
+    header.extend_from_slice(&page_address.to_le_bytes());
+    header.extend_from_slice(&rem_len.to_le_bytes());
+    header.extend_from_slice(&[0; 4]);
+
+    if info & 0x0002 != 0 {
+        header.extend_from_slice(&7_000_000_000u64.to_le_bytes());
+        header.extend_from_slice(&(16 * 1024 * 1024u32).to_le_bytes());
+        header.extend_from_slice(&8192u32.to_le_bytes());
+    }
+    header
+}
+
+/// A record of `length` bytes: xl_tot_len followed by a recognisable fill byte.
+fn record(length: u32, fill: u8) -> Vec<u8> {
+    let mut record = length.to_le_bytes().to_vec();
+    record.resize(length as usize, fill);
+    record
+}
+
+fn collect(reassembler: &mut RecordReassembler) -> Vec<ReassembledRecord> {
+    let mut records = Vec::new();
+    while let Some(record) = reassembler.next_record() {
+        records.push(record.unwrap());
+    }
+    records
+}
+
+#[test]
+fn reassembles_many_records_from_one_message() {
+    let mut reassembler = RecordReassembler::new();
+    reassembler.push(MESSAGE_START_LSN, wal_bytes());
+
+    let records = collect(&mut reassembler);
+
+    assert!(records.len() > 2, "expected several records, got {}", records.len());
+    assert_eq!((records[0].start_lsn, records[0].end_lsn), (0x1552C80, 0x1552CAA));
+    assert_eq!((records[1].start_lsn, records[1].end_lsn), (0x1552CB0, 0x1552CCE));
+    assert_eq!(records[1].bytes.len(), 30);
+}
+
+#[test]
+fn reassembles_records_split_across_messages() {
+    let mut whole = RecordReassembler::new();
+    whole.push(MESSAGE_START_LSN, wal_bytes());
+    let expected = collect(&mut whole);
+
+    let mut split = RecordReassembler::new();
+    let (first, second) = wal_bytes().split_at(10);
+    split.push(MESSAGE_START_LSN, first);
+    assert!(split.next_record().is_none(), "no record is complete after 10 bytes");
+    split.push(MESSAGE_START_LSN + first.len() as u64, second);
+
+    assert_eq!(collect(&mut split), expected);
+}
+
+#[test]
+fn reassembles_record_spanning_a_page_boundary() {
+    let first_record = record(40, 0xAA);
+    let mut bytes = first_record[..16].to_vec();
+    bytes.extend(page_header(0x0001, 0x2000, 24));
+    bytes.extend_from_slice(&first_record[16..]);
+    bytes.extend(record(24, 0xBB));
+
+    let mut reassembler = RecordReassembler::new();
+    reassembler.push(0x1FF0, &bytes);
+    let records = collect(&mut reassembler);
+
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].bytes, first_record);
+    assert_eq!((records[0].start_lsn, records[0].end_lsn), (0x1FF0, 0x2030));
+    assert_eq!(records[1].start_lsn, 0x2030);
+}
+
+#[test]
+fn skips_continuation_after_long_page_header() {
+    let mut bytes = page_header(0x0003, 0x1000000, 10);
+    bytes.extend_from_slice(&[0xCC; 16]);
+    bytes.extend(record(24, 0xDD));
+
+    let mut reassembler = RecordReassembler::new();
+    for (index, chunk) in bytes.chunks(7).enumerate() {
+        reassembler.push(0x1000000 + (index * 7) as u64, chunk);
+    }
+    let records = collect(&mut reassembler);
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].start_lsn, 0x1000000 + 40 + 16);
+    assert_eq!(records[0].bytes, record(24, 0xDD));
+
+    let page_header = reassembler.last_page_header().unwrap();
+    assert_eq!(page_header.long_header.as_ref().unwrap().xlp_seg_size, 16 * 1024 * 1024);
+}
+
+
+#[test]
+fn restarts_after_end_of_record() {
+    let mut bytes = vec![0; 2];
+    bytes.extend(record(24, 0xEE));
+
+    let mut reassembler = RecordReassembler::new();
+    reassembler.reset(0x3000156);
+    reassembler.push(0x3000156, &bytes);
+    let records = collect(&mut reassembler);
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].start_lsn, 0x3000158);
+    assert_eq!(records[0].bytes, record(24, 0xEE));
+}
+
+#[test]
+fn rejects_oversized_record_and_resumes_on_next_page() {
+    /* a damaged xl_tot_len of 2GB, then garbage to the end of the page */
+    let mut bytes = 0x8000_0000u32.to_le_bytes().to_vec();
+    bytes.resize(32, 0xFF);
+    bytes.extend(page_header(0x0000, 0x6000, 0));
+    bytes.extend(record(24, 0xAB));
+
+    let mut reassembler = RecordReassembler::new();
+    reassembler.push(0x5FE0, &bytes);
+
+    assert_eq!(
+        reassembler.next_record(),
+        Some(Err(DecodeError::InvalidRecordLength { xl_tot_len: 0x8000_0000 }))
+    );
+    let records = collect(&mut reassembler);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].start_lsn, 0x6018);
+    assert_eq!(records[0].bytes, record(24, 0xAB));
+}
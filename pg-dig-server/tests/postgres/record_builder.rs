@@ -0,0 +1,102 @@
+use crate::postgres::test_data::TEST_BUFFER;
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
+use pg_dig_server::postgres::xlog_message::{XLogMessage, XLogMessageHeader};
+
+/// The transaction id on built records.
+pub const XID: u32 = 746;
+
+/// The relation every block reference in a built record points at: 1663/5/16384.
+pub const REL_FILE_LOCATOR: [u32; 3] = [1663, 5, 16384];
+
+/// Builds a WAL record for resource manager `rmgr` with `xl_info` set to `info`.
+///
+/// `blocks` holds the data of each block reference, for block ids 0, 1, ... of the same relation,
+/// each referencing the block number equal to its id. `main_data` goes last.
+pub fn wal_record(rmgr: ResourceManager, info: u8, blocks: &[&[u8]], main_data: &[u8]) -> Vec<u8> {
+    let mut headers = Vec::new();
+    let mut payload = Vec::new();
+
+    for (id, data) in blocks.iter().enumerate() {
+        headers.push(id as u8);
+        /* BKPBLOCK_HAS_DATA, main fork; BKPBLOCK_SAME_REL after the first */
+        headers.push(if id == 0 { 0x20 } else { 0xA0 });
+        headers.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        if id == 0 {
+            REL_FILE_LOCATOR.iter().for_each(|oid| headers.extend_from_slice(&oid.to_le_bytes()));
+        }
+        headers.extend_from_slice(&(id as u32).to_le_bytes());
+        payload.extend_from_slice(data);
+    }
+
+    match main_data.len() {
+        0 => {},
+        length if length < 256 => headers.extend_from_slice(&[255, length as u8]),
+        length => {
+            headers.push(254);
+            headers.extend_from_slice(&(length as u32).to_le_bytes());
+        },
+    }
+    payload.extend_from_slice(main_data);
+
+    let total_length = 24 + headers.len() + payload.len();
+    let mut record = Vec::with_capacity(total_length);
+    record.extend_from_slice(&(total_length as u32).to_le_bytes());
+    record.extend_from_slice(&XID.to_le_bytes());
+    record.extend_from_slice(&0u64.to_le_bytes());
+    record.extend_from_slice(&[info, rmgr as u8, 0, 0]);
+    record.extend_from_slice(&0u32.to_le_bytes());
+    record.extend_from_slice(&headers);
+    record.extend_from_slice(&payload);
+    record
+}
+
+/// Builds a record as `wal_record` does, logged by transaction `xid` instead of XID.
+pub fn wal_record_for(xid: u32, rmgr: ResourceManager, info: u8, blocks: &[&[u8]], main_data: &[u8]) -> Vec<u8> {
+    let mut record = wal_record(rmgr, info, blocks, main_data);
+    record[4..8].copy_from_slice(&xid.to_le_bytes());
+    record
+}
+
+/// Decodes a record built by `wal_record`.
+pub fn decode(record: &[u8]) -> XLogMessage {
+    let header = XLogMessageHeader { start_lsn: 0x1552C80, end_lsn: 0x1552C80 + record.len() as u64, send_time: 0 };
+    XLogMessage::from_record(header, record).unwrap()
+}
+
+/// Decodes a record built by `wal_record` that is expected not to decode.
+pub fn decode_failure(record: &[u8]) -> DecodeError {
+    let header = XLogMessageHeader { start_lsn: 0, end_lsn: 0, send_time: 0 };
+    XLogMessage::from_record(header, record).err().unwrap()
+}
+
+/// The error from decoding a record of resource manager `rmgr` with `xl_info` set to `info` and
+/// nothing else in it.
+pub fn decode_error(rmgr: ResourceManager, info: u8) -> DecodeError {
+    decode_failure(&wal_record(rmgr, info, &[], &[]))
+}
+
+/// The `$rmgr` record a decoded message holds, e.g. `rmgr_record!(message, Btree)` for its
+/// BtreeRecord. Fails the test if the message holds any other kind of record.
+macro_rules! rmgr_record {
+    ($message:expr, $rmgr:ident) => {
+        match &$message.record {
+            pg_dig_server::postgres::records::RmgrRecord::$rmgr(record) => record,
+            other => panic!(concat!("expected a ", stringify!($rmgr), " record, got {:?}"), other),
+        }
+    };
+}
+pub(crate) use rmgr_record;
+
+/// Concatenates `fields` as little-endian u32s, the way most record structs lay them out.
+pub fn le_bytes(fields: &[u32]) -> Vec<u8> {
+    fields.iter().flat_map(|field| field.to_le_bytes()).collect()
+}
+
+/// Decodes the TEST_BUFFER record at `lsn`.
+pub fn test_buffer_record(lsn: u64) -> XLogMessage {
+    let records = &TEST_BUFFER[1 + size_of::<XLogMessageHeader>()..];
+    let header = XLogMessageHeader { start_lsn: lsn, end_lsn: 0, send_time: 0 };
+
+    XLogMessage::from_record(header, &records[(lsn - 0x1552C80) as usize..]).unwrap()
+}
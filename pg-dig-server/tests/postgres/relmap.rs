@@ -0,0 +1,53 @@
+use crate::postgres::record_builder::{decode, le_bytes, wal_record};
+use pg_dig_server::postgres::common::rmgr::{get_simple_rmgr_info, ResourceManager, RmgrId};
+use pg_dig_server::postgres::records::relmap::{RelMapRecord, RelMapping};
+use pg_dig_server::postgres::records::replorigin::ReplicationOriginRecord;
+use pg_dig_server::postgres::records::RmgrRecord;
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
+
+#[test]
+fn relmap_update() {
+    // rmgr: RelMap desc: UPDATE database 5 tablespace 1663 size 524
+    let mut main_data = le_bytes(&[5, 1663, 524]);
+    main_data.extend(le_bytes(&[0x592717, 2, 1259, 16390, 1249, 16393]));
+    let update = decode(&wal_record(ResourceManager::RelMap, 0x00, &[], &main_data));
+
+    assert_eq!(update.record_type(), "UPDATE");
+    assert_eq!(update.record, RmgrRecord::RelMap(RelMapRecord::Update {
+        db_id: 5,
+        ts_id: 1663,
+        mappings: vec![
+            RelMapping { oid: 1259, file_number: 16390 },
+            RelMapping { oid: 1249, file_number: 16393 },
+        ],
+    }));
+    assert_eq!(get_simple_rmgr_info(RmgrId(ResourceManager::RelMap as u8), 0x00).unwrap().rmgr_name, "RelMap");
+}
+
+#[test]
+fn relmap_update_missing_mappings() {
+    let mut main_data = le_bytes(&[0, 1664, 524]);
+    main_data.extend(le_bytes(&[0x592717, 3, 1262, 1262]));
+
+    let error = RelMapRecord::decode(0x00, &main_data).err().unwrap();
+    assert!(matches!(error, DecodeError::Truncated { .. }));
+}
+
+#[test]
+fn replication_origin_set_and_drop() {
+    // rmgr: ReplicationOrigin desc: SET 0/1A2B3C4D; node 1; force: 0
+    let mut main_data = 0x1A2B3C4Du64.to_le_bytes().to_vec();
+    main_data.extend_from_slice(&[0x01, 0x00, 0x00]);
+    let set = decode(&wal_record(ResourceManager::ReplicationOrigin, 0x00, &[], &main_data));
+    assert_eq!(set.record_type(), "SET");
+    assert_eq!(set.record, RmgrRecord::ReplicationOrigin(ReplicationOriginRecord::Set {
+        remote_lsn: 0x1A2B3C4D,
+        node_id: 1,
+        force: false,
+    }));
+
+    // rmgr: ReplicationOrigin desc: DROP; node 1
+    let drop = decode(&wal_record(ResourceManager::ReplicationOrigin, 0x10, &[], &[0x01, 0x00]));
+    assert_eq!(drop.record_type(), "DROP");
+    assert_eq!(drop.record, RmgrRecord::ReplicationOrigin(ReplicationOriginRecord::Drop { node_id: 1 }));
+}
@@ -0,0 +1,150 @@
+use crate::postgres::fake_walsender::{FakeWalSender, Script, StatusUpdate, Step};
+use crate::postgres::test_data::TEST_BUFFER;
+use pg_dig_server::config::{Config, StartPosition};
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::error::PgDigError;
+use pg_dig_server::postgres::replication::LiveSource;
+use pg_dig_server::postgres::source::WalSource;
+use pg_dig_server::postgres::xlog_message::XLogMessageHeader;
+
+const WAL_START: u64 = 0x1552C80;
+
+/// The five complete records at the start of TEST_BUFFER, ending at 0x1552D7C.
+fn complete_records() -> Vec<u8> {
+    TEST_BUFFER[1 + size_of::<XLogMessageHeader>()..][..0x100].to_vec()
+}
+
+fn config(server: &FakeWalSender, start_position: StartPosition) -> Config {
+    Config {
+        connection_string: server.connection_string(),
+        start_position,
+        ..Config::default()
+    }
+}
+
+fn read_all(source: &mut LiveSource) -> Vec<(u64, ResourceManager)> {
+    source.messages()
+        .map(|message| message.unwrap())
+        .map(|message| (message.header.start_lsn, message.resource_manager))
+        .collect()
+}
+
+#[test]
+fn streams_records_and_reports_progress() {
+    let server = FakeWalSender::start(Script {
+        streams: vec![vec![
+            Step::ExpectStatusUpdate,
+            Step::XLogData { start_lsn: WAL_START, bytes: complete_records() },
+            Step::Keepalive { wal_end: 0x1552D80, reply_requested: true },
+            Step::ExpectStatusUpdate,
+        ]],
+        ..Script::default()
+    });
+
+    let mut source = LiveSource::connect(&config(&server, StartPosition::SlotRestartLsn)).unwrap();
+    let records = read_all(&mut source);
+    drop(source);
+    let observed = server.finish();
+
+    assert_eq!(records, vec![
+        (0x1552C80, ResourceManager::Standby),
+        (0x1552CB0, ResourceManager::XLOG),
+        (0x1552CD0, ResourceManager::Storage),
+        (0x1552D00, ResourceManager::Heap2),
+        (0x1552D40, ResourceManager::Heap2),
+    ]);
+    assert!(observed.queries.contains(&"IDENTIFY_SYSTEM".to_string()));
+    assert!(observed.queries.contains(&"START_REPLICATION SLOT physical PHYSICAL 0/1552000 TIMELINE 1".to_string()));
+    assert_eq!(observed.status_updates[0], StatusUpdate { write_lsn: 0, flush_lsn: 0, reply_requested: false });
+    assert_eq!(observed.status_updates[1].write_lsn, 0x1552D80);
+}
+
+#[test]
+fn skips_records_before_the_start_lsn() {
+    // the page is streamed from its start, two records before the one asked for
+    let server = FakeWalSender::start(Script {
+        streams: vec![vec![Step::XLogData { start_lsn: WAL_START, bytes: complete_records() }]],
+        ..Script::default()
+    });
+
+    let mut source = LiveSource::connect(&config(&server, StartPosition::Lsn(0x1552D00))).unwrap();
+    let records = read_all(&mut source);
+    drop(source);
+    let observed = server.finish();
+
+    assert_eq!(records, vec![(0x1552D00, ResourceManager::Heap2), (0x1552D40, ResourceManager::Heap2)]);
+    assert!(observed.queries.contains(&"START_REPLICATION SLOT physical PHYSICAL 0/1552000 TIMELINE 1".to_string()));
+}
+
+#[test]
+fn creates_a_missing_slot() {
+    let server = FakeWalSender::start(Script {
+        slots: Vec::new(),
+        streams: vec![vec![]],
+        ..Script::default()
+    });
+
+    let mut source = LiveSource::connect(&config(&server, StartPosition::SlotRestartLsn)).unwrap();
+    assert!(matches!(source.next_message(), Err(PgDigError::EndOfStream)));
+    drop(source);
+    let observed = server.finish();
+
+    assert!(observed.queries.contains(&"CREATE_REPLICATION_SLOT physical PHYSICAL RESERVE_WAL".to_string()));
+    assert!(observed.queries.contains(&"START_REPLICATION SLOT physical PHYSICAL 0/1560000 TIMELINE 1".to_string()));
+}
+
+#[test]
+fn follows_a_timeline_switch() {
+    let server = FakeWalSender::start(Script {
+        timeline: 2,
+        timeline_history: "1\t0/1552D80\tno recovery target specified\n".to_string(),
+        streams: vec![
+            vec![
+                Step::XLogData { start_lsn: WAL_START, bytes: complete_records() },
+                Step::SwitchTimeline { timeline: 2, start_lsn: 0x1552D80 },
+            ],
+            vec![Step::XLogData { start_lsn: 0x1552D80, bytes: complete_records() }],
+        ],
+        ..Script::default()
+    });
+
+    let mut source = LiveSource::connect(&config(&server, StartPosition::Lsn(WAL_START))).unwrap();
+    let records = read_all(&mut source);
+    assert_eq!(source.state().timeline(), 2);
+    drop(source);
+    let observed = server.finish();
+
+    assert_eq!(records.len(), 10);
+    assert_eq!(records[5], (0x1552D80, ResourceManager::Standby));
+    assert!(observed.queries.contains(&"TIMELINE_HISTORY 2".to_string()));
+    assert!(observed.queries.contains(&"START_REPLICATION SLOT physical PHYSICAL 0/1552000 TIMELINE 1".to_string()));
+    assert!(observed.queries.contains(&"START_REPLICATION SLOT physical PHYSICAL 0/1552D80 TIMELINE 2".to_string()));
+}
+
+#[test]
+fn rejects_a_start_lsn_past_the_server() {
+    let server = FakeWalSender::start(Script::default());
+
+    let result = LiveSource::connect(&config(&server, StartPosition::Lsn(0x2000000)));
+    assert!(matches!(result, Err(PgDigError::Config(_))));
+    server.finish();
+}
+
+#[test]
+fn rejects_an_empty_copy_data_message() {
+    let server = FakeWalSender::start(Script {
+        streams: vec![vec![Step::CopyData { body: Vec::new() }]],
+        ..Script::default()
+    });
+
+    let mut source = LiveSource::connect(&config(&server, StartPosition::SlotRestartLsn)).unwrap();
+    let result = source.next_message();
+    drop(source);
+    server.finish();
+
+    /* libpq drops empty CopyData messages itself, so only the native client hands one over */
+    match cfg!(feature = "native-client") {
+        true => assert!(matches!(result, Err(PgDigError::Protocol(message)) if message == "empty CopyData message")),
+        false => assert!(matches!(result, Err(PgDigError::EndOfStream))),
+    }
+}
@@ -0,0 +1,58 @@
+use crate::postgres::record_builder::decode_error;
+use pg_dig_server::postgres::common::rmgr::{get_simple_rmgr_info, ResourceManager, RmgrId};
+use pg_dig_server::postgres::error::PgDigError;
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
+
+#[test]
+fn simple_rmgr_info_for_heap() {
+    let info = get_simple_rmgr_info(RmgrId(10), 0).unwrap();
+
+    assert_eq!(info.rmgr_name, "Heap");
+    assert_eq!(info.record_type, "INSERT");
+}
+
+#[test]
+fn simple_rmgr_info_rejects_unknown_rmgr() {
+    let result = get_simple_rmgr_info(RmgrId(42), 0);
+
+    match result {
+        Err(PgDigError::MalformedRecord(DecodeError::InvalidResourceManager { rmid })) => {
+            assert_eq!(rmid, 42)
+        }
+        _ => panic!("expected an invalid resource manager error"),
+    }
+}
+
+#[test]
+fn unknown_record_types() {
+    // an info value each decoder has no record type for; Heap and Heap2 use every value
+    let unknown = [
+        (ResourceManager::XLOG, 0xC0),
+        (ResourceManager::Transaction, 0x70),
+        (ResourceManager::Storage, 0x00),
+        (ResourceManager::CLOG, 0x20),
+        (ResourceManager::Database, 0x30),
+        (ResourceManager::Tablespace, 0x20),
+        (ResourceManager::MultiXact, 0x40),
+        (ResourceManager::RelMap, 0x10),
+        (ResourceManager::Standby, 0x30),
+        (ResourceManager::Btree, 0xF0),
+        (ResourceManager::Hash, 0xD0),
+        (ResourceManager::Gin, 0x40),
+        (ResourceManager::Gist, 0x40),
+        (ResourceManager::Sequence, 0x10),
+        (ResourceManager::SPGist, 0x00),
+        (ResourceManager::BRIN, 0x60),
+        (ResourceManager::CommitTs, 0x20),
+        (ResourceManager::ReplicationOrigin, 0x20),
+        (ResourceManager::Generic, 0x10),
+        (ResourceManager::LogicalMessage, 0x10),
+    ];
+
+    for (rmgr, info) in unknown {
+        let error = decode_error(rmgr, info);
+
+        assert_eq!(error, DecodeError::UnknownRecordType { rmgr, info });
+        assert_eq!(error.to_string(), format!("unknown {} record type 0x{:02X}", rmgr, info));
+    }
+}
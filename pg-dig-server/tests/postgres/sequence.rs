@@ -0,0 +1,40 @@
+use crate::postgres::record_builder::{decode, decode_failure, wal_record};
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::common::RelFileLocator;
+use pg_dig_server::postgres::records::sequence::SequenceRecord;
+use pg_dig_server::postgres::records::RmgrRecord;
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
+
+/// The main data of a SEQ_LOG record for sequence 1663/5/16390.
+pub fn seq_log(last_value: i64, log_cnt: i64) -> Vec<u8> {
+    let mut main_data: Vec<u8> = [1663u32, 5, 16390].iter().flat_map(|oid| oid.to_le_bytes()).collect();
+    let mut tuple_header = [0u8; 24];
+    tuple_header[22] = 24;
+    main_data.extend_from_slice(&tuple_header);
+    main_data.extend_from_slice(&last_value.to_le_bytes());
+    main_data.extend_from_slice(&log_cnt.to_le_bytes());
+    main_data.push(0x01);
+    main_data
+}
+
+#[test]
+fn sequence_log() {
+    // rmgr: Sequence desc: LOG rel 1663/5/16390
+    let message = decode(&wal_record(ResourceManager::Sequence, 0x00, &[&[]], &seq_log(1033, 32)));
+
+    assert_eq!(message.record_type(), "LOG");
+    assert_eq!(message.record, RmgrRecord::Sequence(SequenceRecord::Log {
+        locator: RelFileLocator { spc_oid: 1663, db_oid: 5, rel_number: 16390 },
+        last_value: 1033,
+        log_cnt: 32,
+        is_called: true,
+    }));
+    assert_eq!(message.get_block_numbers(), vec![0]);
+}
+
+#[test]
+fn sequence_log_without_tuple() {
+    let record = wal_record(ResourceManager::Sequence, 0x00, &[&[]], &seq_log(1033, 32)[..30]);
+
+    assert!(matches!(decode_failure(&record), DecodeError::Truncated { .. }));
+}
@@ -0,0 +1,78 @@
+use crate::postgres::test_data::TEST_BUFFER;
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::error::PgDigError;
+use pg_dig_server::postgres::source::WalSource;
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
+use pg_dig_server::postgres::xlog_message::XLogMessage;
+use std::collections::VecDeque;
+
+/// A source that plays back a fixed list of results.
+struct FakeSource {
+    results: VecDeque<(u64, Result<XLogMessage, PgDigError>)>,
+    last_record_end: u64,
+    confirmed: Vec<u64>,
+}
+
+impl FakeSource {
+    fn new(results: Vec<(u64, Result<XLogMessage, PgDigError>)>) -> Self {
+        FakeSource {
+            results: results.into(),
+            last_record_end: 0,
+            confirmed: Vec::new(),
+        }
+    }
+}
+
+impl WalSource for FakeSource {
+    fn next_message(&mut self) -> Result<XLogMessage, PgDigError> {
+        let (end_lsn, result) = self.results.pop_front().unwrap_or((0, Err(PgDigError::EndOfStream)));
+        self.last_record_end = end_lsn;
+        result
+    }
+
+    fn confirm(&mut self, lsn: u64) {
+        self.confirmed.push(lsn);
+    }
+
+    fn last_record_end(&self) -> u64 {
+        self.last_record_end
+    }
+}
+
+fn message(end_lsn: u64) -> Result<XLogMessage, PgDigError> {
+    let mut message = XLogMessage::from_bytes(&TEST_BUFFER[1..]).unwrap();
+    message.header.end_lsn = end_lsn;
+    Ok(message)
+}
+
+/// A record that could not be decoded, which reading skips over.
+fn malformed() -> PgDigError {
+    PgDigError::MalformedRecord(DecodeError::UnknownRecordType { rmgr: ResourceManager::Btree, info: 0xF0 })
+}
+
+#[test]
+fn messages_skip_and_confirm() {
+    let mut source = FakeSource::new(vec![
+        (0x10, message(0x10)),
+        (0x20, Err(malformed())),
+        (0x30, message(0x30)),
+    ]);
+
+    let end_lsns: Vec<u64> = source.messages()
+        .map(|message| message.unwrap().header.end_lsn)
+        .collect();
+
+    assert_eq!(end_lsns, vec![0x10, 0x30]);
+    assert_eq!(source.confirmed, vec![0x10, 0x20, 0x30]);
+}
+
+#[test]
+fn messages_stop_at_fatal_error() {
+    let mut source = FakeSource::new(vec![
+        (0x10, Err(PgDigError::Connection("server closed the connection".to_string()))),
+        (0x20, message(0x20)),
+    ]);
+    let mut messages = source.messages();
+
+    assert!(matches!(messages.next(), Some(Err(PgDigError::Connection(_)))));
+}
@@ -0,0 +1,90 @@
+use crate::postgres::record_builder::{decode, rmgr_record, wal_record};
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::common::transaction_id::TransactionId;
+use pg_dig_server::postgres::records::spgist::{SpgAddLeaf, SpgMoveLeafs, SpgPickSplit, SpgistRecord};
+
+/* spgxlogState for a transaction that is not building the index */
+const STATE: [u8; 8] = [0xEA, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+
+#[test]
+fn spgist_add_leaf() {
+    // rmgr: SPGist desc: ADD_LEAF off 3, headoff 1; parent off 2, node 0
+    let main_data = [0x00, 0x00, 0x03, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00];
+    let message = decode(&wal_record(ResourceManager::SPGist, 0x10, &[b"leaf", &[]], &main_data));
+
+    assert_eq!(message.record_type(), "ADD_LEAF");
+    assert_eq!(rmgr_record!(message, SPGist), &SpgistRecord::AddLeaf(SpgAddLeaf {
+        new_page: false,
+        stores_nulls: false,
+        offnum_leaf: 3,
+        offnum_head_leaf: 1,
+        offnum_parent: 2,
+        node_i: 0,
+    }));
+    assert_eq!(message.get_block_numbers(), vec![0, 1]);
+}
+
+#[test]
+fn spgist_move_leafs() {
+    // rmgr: SPGist desc: MOVE_LEAFS 4 leafs from page 0 to page 1, parent off 2, node 1
+    let mut main_data = vec![0x04, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00];
+    main_data.extend_from_slice(&STATE);
+    let message = decode(&wal_record(ResourceManager::SPGist, 0x20, &[&[], b"leafs", &[]], &main_data));
+
+    assert_eq!(message.record_type(), "MOVE_LEAFS");
+    assert_eq!(rmgr_record!(message, SPGist), &SpgistRecord::MoveLeafs(SpgMoveLeafs {
+        nmoves: 4,
+        new_page: true,
+        replace_dead: false,
+        stores_nulls: false,
+        offnum_parent: 2,
+        node_i: 1,
+    }));
+}
+
+#[test]
+fn spgist_pick_split() {
+    // rmgr: SPGist desc: PICKSPLIT ndelete 10; ninsert 11; inner off 1; parent off 2, node 3
+    let mut main_data = vec![
+        0x00, 0x00, 0x0A, 0x00, 0x0B, 0x00, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x03, 0x00, 0x00,
+        0x00,
+    ];
+    main_data.extend_from_slice(&STATE);
+    let message = decode(&wal_record(ResourceManager::SPGist, 0x50, &[&[], b"leafs", b"inner", &[]], &main_data));
+
+    assert_eq!(message.record_type(), "PICKSPLIT");
+    assert_eq!(rmgr_record!(message, SPGist), &SpgistRecord::PickSplit(SpgPickSplit {
+        is_root_split: false,
+        ndelete: 10,
+        ninsert: 11,
+        init_src: false,
+        init_dest: true,
+        offnum_inner: 1,
+        init_inner: false,
+        stores_nulls: false,
+        inner_is_parent: false,
+        offnum_parent: 2,
+        node_i: 3,
+    }));
+    assert_eq!(message.get_block_numbers(), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn spgist_vacuum() {
+    let mut main_data = vec![0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x00];
+    main_data.extend_from_slice(&STATE);
+    let leaf = decode(&wal_record(ResourceManager::SPGist, 0x60, &[b"offsets"], &main_data));
+    assert_eq!(leaf.record_type(), "VACUUM_LEAF");
+    assert_eq!(rmgr_record!(leaf, SPGist), &SpgistRecord::VacuumLeaf { ndead: 2, nplaceholder: 1, nmove: 0, nchain: 3 });
+
+    let mut main_data = vec![0x02, 0x00, 0x05, 0x00];
+    main_data.extend_from_slice(&748u32.to_le_bytes());
+    main_data.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+    let redirect = decode(&wal_record(ResourceManager::SPGist, 0x80, &[b"offsets"], &main_data));
+    assert_eq!(redirect.record_type(), "VACUUM_REDIRECT");
+    assert_eq!(rmgr_record!(redirect, SPGist), &SpgistRecord::VacuumRedirect {
+        nto_placeholder: 2,
+        first_placeholder: 5,
+        snapshot_conflict_horizon: TransactionId(748),
+    });
+}
@@ -0,0 +1,119 @@
+use crate::postgres::record_builder::{decode, le_bytes, rmgr_record, test_buffer_record, wal_record, wal_record_for};
+use pg_dig_server::postgres::activity::ActivityTracker;
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::common::transaction_id::TransactionId;
+use pg_dig_server::postgres::records::standby::{RunningXacts, StandbyLock, StandbyRecord};
+
+/// A RUNNING_XACTS record listing `xids`, with no subtransactions.
+fn running_xacts(xids: &[u32], next_xid: u32) -> Vec<u8> {
+    let mut main_data = le_bytes(&[xids.len() as u32, 0, 0, next_xid, xids.first().copied().unwrap_or(next_xid), next_xid - 1]);
+    main_data.extend(le_bytes(xids));
+    wal_record_for(0, ResourceManager::Standby, 0x10, &[], &main_data)
+}
+
+fn heap_insert(xid: u32) -> Vec<u8> {
+    wal_record_for(xid, ResourceManager::Heap, 0x00, &[b"tuple"], &[0x01, 0x00, 0x08])
+}
+
+fn commit(xid: u32) -> Vec<u8> {
+    wal_record_for(xid, ResourceManager::Transaction, 0x00, &[], &813_489_296_123_456i64.to_le_bytes())
+}
+
+fn in_progress(tracker: &ActivityTracker) -> Vec<u32> {
+    tracker.in_progress().iter().map(|(xid, _)| xid.0).collect()
+}
+
+#[test]
+fn standby_lock() {
+    // rmgr: Standby desc: LOCK xid 746 db 5 rel 24577
+    let message = test_buffer_record(0x1552C80);
+
+    assert_eq!(message.record_type(), "LOCK");
+    assert_eq!(rmgr_record!(message, Standby), &StandbyRecord::Lock(vec![StandbyLock {
+        xid: TransactionId(746),
+        db_id: 5,
+        rel_id: 0x6001,
+    }]));
+}
+
+#[test]
+fn standby_running_xacts() {
+    // rmgr: Standby desc: RUNNING_XACTS nextXid 750 latestCompletedXid 748 oldestRunningXid 746; 2 xacts: 746 749;
+    // 1 subxacts: 747
+    let mut main_data = le_bytes(&[2, 1, 0, 750, 746, 748]);
+    main_data.extend(le_bytes(&[746, 749, 747]));
+    let message = decode(&wal_record(ResourceManager::Standby, 0x10, &[], &main_data));
+
+    assert_eq!(message.record_type(), "RUNNING_XACTS");
+    assert_eq!(rmgr_record!(message, Standby), &StandbyRecord::RunningXacts(RunningXacts {
+        subxid_overflow: false,
+        next_xid: TransactionId(750),
+        oldest_running_xid: TransactionId(746),
+        latest_completed_xid: TransactionId(748),
+        xids: vec![TransactionId(746), TransactionId(749)],
+        subxids: vec![TransactionId(747)],
+    }));
+}
+
+#[test]
+fn standby_invalidations() {
+    let mut main_data = le_bytes(&[5, 1663, 1, 2]);
+    main_data.extend_from_slice(&[0; 32]);
+    let message = decode(&wal_record(ResourceManager::Standby, 0x20, &[], &main_data));
+
+    assert_eq!(message.record_type(), "INVALIDATIONS");
+    assert_eq!(rmgr_record!(message, Standby), &StandbyRecord::Invalidations {
+        db_id: 5,
+        ts_id: 1663,
+        relcache_init_file_inval: true,
+        nmsgs: 2,
+    });
+}
+
+#[test]
+fn activity_tracks_transactions_until_they_end() {
+    let mut tracker = ActivityTracker::new();
+
+    tracker.observe(&decode(&heap_insert(746)));
+    tracker.observe(&decode(&heap_insert(747)));
+    tracker.observe(&decode(&heap_insert(746)));
+    assert_eq!(in_progress(&tracker), vec![746, 747]);
+    assert_eq!(tracker.oldest().map(|(_, activity)| activity.records), Some(2));
+
+    tracker.observe(&decode(&commit(746)));
+    assert_eq!(in_progress(&tracker), vec![747]);
+}
+
+#[test]
+fn activity_tracks_access_exclusive_locks() {
+    let mut tracker = ActivityTracker::new();
+
+    tracker.observe(&test_buffer_record(0x1552C80));
+    tracker.observe(&decode(&wal_record_for(0, ResourceManager::Standby, 0x00, &[], &le_bytes(&[1, 748, 5, 0x6001]))));
+    assert_eq!(tracker.lock_count(), 2);
+    assert_eq!(tracker.locks_by_relation()[&(5, 0x6001)].len(), 2);
+    assert_eq!(in_progress(&tracker), vec![746, 748]);
+
+    tracker.observe(&decode(&commit(748)));
+    assert_eq!(tracker.lock_count(), 1);
+    assert_eq!(tracker.locks_by_relation()[&(5, 0x6001)], vec![TransactionId(746)]);
+}
+
+#[test]
+fn activity_forgets_transactions_missing_from_a_snapshot() {
+    let mut tracker = ActivityTracker::new();
+
+    tracker.observe(&decode(&heap_insert(740)));
+    tracker.observe(&decode(&heap_insert(746)));
+    tracker.observe(&test_buffer_record(0x1552C80));
+    // 740 ended without us seeing it; 751 started after the snapshot was taken
+    tracker.observe(&decode(&heap_insert(751)));
+    tracker.observe(&decode(&running_xacts(&[746], 750)));
+
+    assert_eq!(in_progress(&tracker), vec![746, 751]);
+    assert_eq!(tracker.lock_count(), 1);
+
+    tracker.observe(&decode(&running_xacts(&[], 752)));
+    assert!(tracker.in_progress().is_empty());
+    assert_eq!(tracker.lock_count(), 0);
+}
@@ -0,0 +1,211 @@
+use crate::postgres::fake_walsender::{FakeWalSender, Script, Step};
+use crate::postgres::test_data::TEST_BUFFER;
+use pg_dig_server::postgres::capture::{ReplaySource, ReplaySpeed};
+use pg_dig_server::config::{Config, StartPosition};
+use pg_dig_server::postgres::error::PgDigError;
+use pg_dig_server::postgres::message_stream::MessageStream;
+use pg_dig_server::postgres::source::{open_source, WalSource};
+use pg_dig_server::postgres::supervisor::{Backoff, ConnectionState, StateListener, SupervisedSource};
+use pg_dig_server::postgres::xlog_message::XLogMessageHeader;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const WAL_START: u64 = 0x1552C80;
+
+/// The five complete records at the start of TEST_BUFFER, ending at 0x1552D7C.
+fn complete_records() -> Vec<u8> {
+    TEST_BUFFER[1 + size_of::<XLogMessageHeader>()..][..0x100].to_vec()
+}
+
+fn config(server: &FakeWalSender) -> Config {
+    Config {
+        connection_string: server.connection_string(),
+        start_position: StartPosition::Lsn(WAL_START),
+        max_reconnect_delay: Duration::from_millis(50),
+        ..Config::default()
+    }
+}
+
+fn recording_listener() -> (StateListener, Arc<Mutex<Vec<ConnectionState>>>) {
+    let states = Arc::new(Mutex::new(Vec::new()));
+    let recorded = states.clone();
+    (Box::new(move |state: &ConnectionState| recorded.lock().unwrap().push(state.clone())), states)
+}
+
+#[test]
+fn backoff_doubles_up_to_the_limit() {
+    let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+    let delays: Vec<u64> = (0..5).map(|_| backoff.next_delay().as_secs()).collect();
+
+    assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+    assert_eq!(backoff.attempts(), 5);
+
+    backoff.reset();
+    assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+}
+
+#[test]
+fn reconnects_and_resumes_without_repeating_records() {
+    let server = FakeWalSender::start(Script {
+        connections: 2,
+        streams: vec![
+            vec![Step::XLogData { start_lsn: WAL_START, bytes: complete_records() }, Step::Disconnect],
+            // the same WAL again, then the records after it
+            vec![
+                Step::XLogData { start_lsn: WAL_START, bytes: complete_records() },
+                Step::XLogData { start_lsn: 0x1552D80, bytes: complete_records() },
+            ],
+        ],
+        ..Script::default()
+    });
+
+    let (listener, states) = recording_listener();
+    let config = config(&server);
+    let stream = MessageStream::spawn(move || open_source(&config, listener), 4);
+
+    let start_lsns: Vec<u64> = (0..10).map(|_| stream.recv().unwrap().unwrap().header.start_lsn).collect();
+    drop(stream);
+    let observed = server.finish();
+
+    assert_eq!(start_lsns, vec![
+        0x1552C80, 0x1552CB0, 0x1552CD0, 0x1552D00, 0x1552D40,
+        0x1552D80, 0x1552DB0, 0x1552DD0, 0x1552E00, 0x1552E40,
+    ]);
+    assert_eq!(observed.queries.iter().filter(|query| query.starts_with("START_REPLICATION")).count(), 2);
+
+    let states = states.lock().unwrap();
+    assert_eq!(states[0], ConnectionState::Connecting { attempt: 0 });
+    assert_eq!(states[1], ConnectionState::Connected { status: "connection ok".to_string(), start_lsn: WAL_START });
+    assert!(matches!(&states[2], ConnectionState::Disconnected { retry_in: Some(_), .. }));
+    assert_eq!(states[3], ConnectionState::Connecting { attempt: 1 });
+    assert!(matches!(&states[4], ConnectionState::Connected { status, start_lsn } if status == "connection ok" && *start_lsn >= WAL_START));
+}
+
+#[test]
+fn keeps_capturing_across_reconnects() {
+    let server = FakeWalSender::start(Script {
+        connections: 2,
+        streams: vec![
+            vec![Step::XLogData { start_lsn: WAL_START, bytes: complete_records() }, Step::Disconnect],
+            // only what comes after the first connection, so each half is in one part of the file
+            vec![Step::XLogData { start_lsn: 0x1552D80, bytes: complete_records() }],
+        ],
+        ..Script::default()
+    });
+
+    let path = std::env::temp_dir().join(format!("pg-dig-reconnect-{}.capture", std::process::id()));
+    let (listener, _) = recording_listener();
+    let config = Config { capture_path: Some(path.clone()), ..config(&server) };
+    let stream = MessageStream::spawn(move || open_source(&config, listener), 4);
+
+    for _ in 0..10 {
+        stream.recv().unwrap().unwrap();
+    }
+    drop(stream);
+    server.finish();
+
+    // both connections are in the file
+    let mut replay = ReplaySource::open(&path, ReplaySpeed::Unthrottled).unwrap();
+    let start_lsns: Vec<u64> = replay.messages().map(|message| message.unwrap().header.start_lsn).collect();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(start_lsns, vec![
+        0x1552C80, 0x1552CB0, 0x1552CD0, 0x1552D00, 0x1552D40,
+        0x1552D80, 0x1552DB0, 0x1552DD0, 0x1552E00, 0x1552E40,
+    ]);
+}
+
+#[test]
+fn resumes_from_the_confirmed_lsn() {
+    let server = FakeWalSender::start(Script {
+        connections: 2,
+        streams: vec![
+            vec![Step::XLogData { start_lsn: WAL_START, bytes: complete_records() }, Step::Disconnect],
+            vec![],
+        ],
+        ..Script::default()
+    });
+
+    let (mut record, states) = recording_listener();
+    let (sender, receiver) = mpsc::channel();
+    let listener: StateListener = Box::new(move |state: &ConnectionState| {
+        record(state);
+        let _ = sender.send(state.clone());
+    });
+
+    let mut source = SupervisedSource::connect(&config(&server), listener).unwrap();
+    let interrupter = source.interrupter().unwrap();
+    let watcher = std::thread::spawn(move || {
+        // cancel once the second connection is streaming
+        let mut connections = 0;
+        while connections < 2 {
+            let state = receiver.recv_timeout(Duration::from_secs(10)).expect("timed out waiting to reconnect");
+            connections += matches!(state, ConnectionState::Connected { .. }) as usize;
+        }
+        interrupter.interrupt();
+    });
+
+    // asking for another message confirms the last one, then finds the connection gone; the
+    // second connection is cancelled once it is streaming
+    let mut messages = source.messages();
+    let last = (0..5).map(|_| messages.next().unwrap().unwrap()).last().unwrap();
+    assert!(matches!(messages.next(), Some(Err(PgDigError::Connection(_)))));
+    watcher.join().unwrap();
+    drop(source);
+    server.finish();
+
+    let states = states.lock().unwrap();
+    let resumed_at = states.iter().skip(2).find_map(|state| match state {
+        ConnectionState::Connected { start_lsn, .. } => Some(*start_lsn),
+        _ => None,
+    });
+    assert_eq!(resumed_at, Some(last.header.end_lsn));
+}
+
+#[test]
+fn returns_errors_the_server_reports_after_reconnecting() {
+    // the second START_REPLICATION has no stream, so the server rejects it
+    let server = FakeWalSender::start(Script {
+        connections: 2,
+        streams: vec![vec![Step::XLogData { start_lsn: WAL_START, bytes: complete_records() }, Step::Disconnect]],
+        ..Script::default()
+    });
+
+    let (listener, states) = recording_listener();
+    let mut source = SupervisedSource::connect(&config(&server), listener).unwrap();
+    let mut messages = source.messages();
+    for _ in 0..5 {
+        messages.next().unwrap().unwrap();
+    }
+    assert!(matches!(messages.next(), Some(Err(PgDigError::Protocol(_)))));
+    drop(source);
+    server.finish();
+
+    assert_eq!(states.lock().unwrap().last(), Some(&ConnectionState::Connecting { attempt: 1 }));
+}
+
+#[test]
+fn gives_up_when_the_first_connection_fails() {
+    let server = FakeWalSender::start(Script { connections: 0, ..Script::default() });
+    let config = config(&server);
+    server.finish();
+
+    let (listener, states) = recording_listener();
+    assert!(matches!(SupervisedSource::connect(&config, listener), Err(PgDigError::Connection(_))));
+    assert!(matches!(&states.lock().unwrap()[1], ConnectionState::Disconnected { retry_in: None, .. }));
+}
+
+#[test]
+fn connection_state_display() {
+    let connected = ConnectionState::Connected { status: "connection ok".to_string(), start_lsn: WAL_START };
+    let lost = ConnectionState::Disconnected {
+        status: Some("connection bad".to_string()),
+        error: "server closed the connection unexpectedly".to_string(),
+        retry_in: Some(Duration::from_secs(2)),
+    };
+
+    assert_eq!(connected.to_string(), "connection ok, streaming from 0/1552C80");
+    assert_eq!(lost.to_string(), "connection bad: server closed the connection unexpectedly, retrying in 2.0s");
+    assert_eq!(ConnectionState::Connecting { attempt: 3 }.to_string(), "reconnecting, attempt 3");
+}
@@ -0,0 +1,63 @@
+use pg_dig_server::postgres::error::PgDigError;
+use pg_dig_server::postgres::replication::choose_timeline;
+use pg_dig_server::postgres::timeline::{SystemIdentification, TimelineHistory, TimelineHistoryEntry};
+
+const HISTORY: &str = "1\t0/3000158\tno recovery target specified\n\n# promoted again\n2\t0/5000000\tat restore point \"before\"\n";
+
+fn system(timeline: u32, xlogpos: u64) -> SystemIdentification {
+    SystemIdentification {
+        system_id: 7_412_345_678_901_234_567,
+        timeline,
+        xlogpos,
+        dbname: None,
+    }
+}
+
+#[test]
+fn timeline_history_parse() {
+    let history = TimelineHistory::parse(HISTORY).unwrap();
+
+    assert_eq!(history.entries, vec![
+        TimelineHistoryEntry { timeline: 1, switchpoint: 0x3000158, reason: "no recovery target specified".to_string() },
+        TimelineHistoryEntry { timeline: 2, switchpoint: 0x5000000, reason: "at restore point \"before\"".to_string() },
+    ]);
+    assert_eq!(history.switchpoint(2), Some(0x5000000));
+    assert_eq!(history.switchpoint(3), None);
+}
+
+#[test]
+fn timeline_history_invalid_line() {
+    assert!(matches!(TimelineHistory::parse("1\tnot-an-lsn\treason"), Err(PgDigError::Protocol(_))));
+}
+
+#[test]
+fn timeline_containing_lsn() {
+    let history = TimelineHistory::parse(HISTORY).unwrap();
+
+    assert_eq!(history.timeline_containing(0x1000000, 3), 1);
+    assert_eq!(history.timeline_containing(0x3000158, 3), 2);
+    assert_eq!(history.timeline_containing(0x6000000, 3), 3);
+}
+
+#[test]
+fn choose_timeline_from_history() {
+    let history = TimelineHistory::parse(HISTORY).unwrap();
+    let system = system(3, 0x7000000);
+
+    assert_eq!(choose_timeline(0x1000000, None, &system, &history).unwrap(), 1);
+    assert_eq!(choose_timeline(0x6000000, None, &system, &history).unwrap(), 3);
+    assert_eq!(choose_timeline(0x4000000, Some(2), &system, &history).unwrap(), 2);
+}
+
+#[test]
+fn choose_timeline_rejects_invalid_start() {
+    let history = TimelineHistory::parse(HISTORY).unwrap();
+    let system = system(3, 0x7000000);
+
+    /* past the server's WAL */
+    assert!(matches!(choose_timeline(0x8000000, None, &system, &history), Err(PgDigError::Config(_))));
+    /* timeline 1 ended before this LSN */
+    assert!(matches!(choose_timeline(0x4000000, Some(1), &system, &history), Err(PgDigError::Config(_))));
+    /* the server has never been on timeline 4 */
+    assert!(matches!(choose_timeline(0x1000000, Some(4), &system, &history), Err(PgDigError::Config(_))));
+}
@@ -0,0 +1,84 @@
+use crate::postgres::reassembler::page_header;
+use crate::postgres::test_data::TEST_BUFFER;
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::error::PgDigError;
+use pg_dig_server::postgres::wal_file::{SegmentName, WalFileReader};
+use pg_dig_server::postgres::xlog_message::XLogMessageHeader;
+use std::fs;
+use std::path::PathBuf;
+
+const SEGMENT_SIZE: u32 = 1024 * 1024;
+const SEGMENT_FILE: &str = "000000010000000000000001";
+
+/// A segment holding the five complete records of TEST_BUFFER, followed by an unwritten page.
+fn segment(magic: u16) -> Vec<u8> {
+    let mut page = page_header(0x0002, SEGMENT_SIZE as u64, 0);
+    page[0..2].copy_from_slice(&magic.to_le_bytes());
+    page[32..36].copy_from_slice(&SEGMENT_SIZE.to_le_bytes());
+
+    let records = &TEST_BUFFER[1 + size_of::<XLogMessageHeader>()..][..0x100];
+    page.extend_from_slice(records);
+    page.resize(8192 * 2, 0);
+    page
+}
+
+fn wal_dir(test: &str, bytes: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pg-dig-{}-{}", test, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(SEGMENT_FILE), bytes).unwrap();
+    fs::write(dir.join("archive_status"), b"").unwrap();
+    dir
+}
+
+#[test]
+fn segment_name_parse() {
+    let name = SegmentName::parse("00000002000000010000000A.partial").unwrap();
+
+    assert_eq!(name, SegmentName { timeline: 2, log: 1, seg: 10 });
+    assert_eq!(name.start_lsn(16 * 1024 * 1024), 0x1_0A00_0000);
+    assert_eq!(SegmentName::parse("00000001.history"), None);
+}
+
+#[test]
+fn wal_file_reads_records() {
+    let dir = wal_dir("reads", &segment(0xD113));
+    let mut reader = WalFileReader::open(&dir, None).unwrap();
+
+    let mut resource_managers = Vec::new();
+    loop {
+        match reader.read_message() {
+            Ok(message) => resource_managers.push(message.resource_manager),
+            Err(PgDigError::EndOfStream) => break,
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    assert_eq!(resource_managers, vec![
+        ResourceManager::Standby,
+        ResourceManager::XLOG,
+        ResourceManager::Storage,
+        ResourceManager::Heap2,
+        ResourceManager::Heap2,
+    ]);
+    assert_eq!(reader.segment_size(), Some(SEGMENT_SIZE));
+    assert_eq!(reader.last_record_end(), SEGMENT_SIZE as u64 + 40 + 0xFC);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn wal_file_rejects_wrong_magic() {
+    let dir = wal_dir("magic", &segment(0xD110));
+    let mut reader = WalFileReader::open(&dir, None).unwrap();
+
+    assert!(matches!(reader.read_message(), Err(PgDigError::WalFile(_))));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn wal_file_rejects_wrong_segment_size() {
+    let dir = wal_dir("size", &segment(0xD113));
+    let mut reader = WalFileReader::open(&dir.join(SEGMENT_FILE), Some(2 * SEGMENT_SIZE)).unwrap();
+
+    assert!(matches!(reader.read_message(), Err(PgDigError::WalFile(_))));
+    fs::remove_dir_all(dir).unwrap();
+}
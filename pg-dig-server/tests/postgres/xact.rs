@@ -0,0 +1,136 @@
+use crate::postgres::record_builder::{decode, le_bytes, rmgr_record, wal_record, XID};
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::common::timestamp::format_timestamp;
+use pg_dig_server::postgres::common::transaction_id::TransactionId;
+use pg_dig_server::postgres::common::RelFileLocator;
+use pg_dig_server::postgres::records::xact::{XactCompletion, XactInfo, XactOrigin, XactPrepare, XactRecord};
+use pg_dig_server::postgres::xlog_message::{XLogMessage, XLogMessageHeader};
+
+/* 2025-10-11 09:14:56.123456 UTC */
+const XACT_TIME: i64 = 813_489_296_123_456;
+
+#[test]
+fn xact_commit_with_sub_structures() {
+    // rmgr: Transaction desc: COMMIT 2025-10-11 09:14:56.123456 UTC; rels: 1663/5/16390; dropped stats: 1;
+    // subxacts: 747 748; inval msgs: 2
+    let xinfo = XactInfo::XACT_XINFO_HAS_DBINFO
+        | XactInfo::XACT_XINFO_HAS_SUBXACTS
+        | XactInfo::XACT_XINFO_HAS_RELFILELOCATORS
+        | XactInfo::XACT_XINFO_HAS_INVALS
+        | XactInfo::XACT_XINFO_HAS_DROPPED_STATS;
+    let mut main_data = XACT_TIME.to_le_bytes().to_vec();
+    main_data.extend(le_bytes(&[xinfo.bits(), 5, 1663, 2, 747, 748, 1, 1663, 5, 16390, 1]));
+    main_data.extend_from_slice(&[0; 12]);
+    main_data.extend(le_bytes(&[2]));
+    main_data.extend_from_slice(&[0; 32]);
+    let message = decode(&wal_record(ResourceManager::Transaction, 0x80, &[], &main_data));
+
+    assert_eq!(message.record_type(), "COMMIT");
+    assert_eq!(rmgr_record!(message, Transaction), &XactRecord::Commit(XactCompletion {
+        xact_time: XACT_TIME,
+        xinfo,
+        db_id: 5,
+        ts_id: 1663,
+        subxacts: vec![TransactionId(747), TransactionId(748)],
+        dropped_relations: vec![RelFileLocator { spc_oid: 1663, db_oid: 5, rel_number: 16390 }],
+        dropped_stats: 1,
+        invalidations: 2,
+        twophase_xid: None,
+        gid: None,
+        origin: None,
+    }));
+    assert_eq!(
+        rmgr_record!(message, Transaction).completed_xids(TransactionId(XID)),
+        vec![TransactionId(746), TransactionId(747), TransactionId(748)]
+    );
+}
+
+#[test]
+fn xact_abort_without_info() {
+    let message = decode(&wal_record(ResourceManager::Transaction, 0x20, &[], &XACT_TIME.to_le_bytes()));
+
+    assert_eq!(message.record_type(), "ABORT");
+    assert!(matches!(rmgr_record!(message, Transaction), XactRecord::Abort(abort)
+        if abort.xact_time == XACT_TIME && abort.xinfo.is_empty() && abort.subxacts.is_empty()));
+    assert_eq!(rmgr_record!(message, Transaction).completed_xids(TransactionId(XID)), vec![TransactionId(746)]);
+}
+
+#[test]
+fn xact_commit_prepared_with_gid_and_origin() {
+    let xinfo = XactInfo::XACT_XINFO_HAS_TWOPHASE | XactInfo::XACT_XINFO_HAS_GID | XactInfo::XACT_XINFO_HAS_ORIGIN;
+    let mut main_data = XACT_TIME.to_le_bytes().to_vec();
+    main_data.extend(le_bytes(&[xinfo.bits(), 740]));
+    // the origin follows the GID without any alignment
+    main_data.extend_from_slice(b"tx-1\0");
+    main_data.extend_from_slice(&0x1552C80u64.to_le_bytes());
+    main_data.extend_from_slice(&(XACT_TIME - 1_000).to_le_bytes());
+    let message = decode(&wal_record(ResourceManager::Transaction, 0xB0, &[], &main_data));
+
+    assert_eq!(message.record_type(), "COMMIT_PREPARED");
+    let XactRecord::CommitPrepared(commit) = rmgr_record!(message, Transaction) else { panic!("expected COMMIT_PREPARED") };
+    assert_eq!(commit.twophase_xid, Some(TransactionId(740)));
+    assert_eq!(commit.gid.as_deref(), Some("tx-1"));
+    assert_eq!(commit.origin, Some(XactOrigin { lsn: 0x1552C80, timestamp: XACT_TIME - 1_000 }));
+    assert_eq!(rmgr_record!(message, Transaction).completed_xids(TransactionId(0)), vec![TransactionId(740)]);
+}
+
+#[test]
+fn xact_prepare() {
+    let mut main_data = le_bytes(&[0x57F94534, 96, 740, 5]);
+    main_data.extend_from_slice(&XACT_TIME.to_le_bytes());
+    main_data.extend(le_bytes(&[10, 1, 0, 1, 0, 0, 3]));
+    main_data.extend_from_slice(&[0x00, 0x00, 0x05, 0x00]);
+    main_data.extend_from_slice(&[0; 16]);
+    main_data.extend_from_slice(b"tx-1\0\0\0\0");
+    let message = decode(&wal_record(ResourceManager::Transaction, 0x10, &[], &main_data));
+
+    assert_eq!(message.record_type(), "PREPARE");
+    assert_eq!(rmgr_record!(message, Transaction), &XactRecord::Prepare(XactPrepare {
+        xid: TransactionId(740),
+        database: 5,
+        prepared_at: XACT_TIME,
+        owner: 10,
+        nsubxacts: 1,
+        ncommitrels: 0,
+        nabortrels: 1,
+        ninvalmsgs: 3,
+        gid: "tx-1".to_string(),
+    }));
+}
+
+#[test]
+fn xact_assignment_and_invalidations() {
+    let assignment = decode(&wal_record(ResourceManager::Transaction, 0x50, &[], &le_bytes(&[746, 2, 747, 748])));
+    assert_eq!(assignment.record_type(), "ASSIGNMENT");
+    assert_eq!(rmgr_record!(assignment, Transaction), &XactRecord::Assignment {
+        xtop: TransactionId(746),
+        subxacts: vec![TransactionId(747), TransactionId(748)],
+    });
+    assert_eq!(rmgr_record!(assignment, Transaction).xact_time(), None);
+
+    let mut main_data = le_bytes(&[1]);
+    main_data.extend_from_slice(&[0; 16]);
+    let invalidations = decode(&wal_record(ResourceManager::Transaction, 0x60, &[], &main_data));
+    assert_eq!(invalidations.record_type(), "INVALIDATION");
+    assert_eq!(rmgr_record!(invalidations, Transaction), &XactRecord::Invalidations { nmsgs: 1 });
+}
+
+#[test]
+fn xact_time_replaces_message_time() {
+    let commit = decode(&wal_record(ResourceManager::Transaction, 0x00, &[], &XACT_TIME.to_le_bytes()));
+    assert_eq!(commit.message_time(), Some(XACT_TIME));
+    assert!(commit.to_string().contains("message_time: 2025-10-11 09:14:56.123456 UTC"));
+
+    // other records fall back to when the server sent them
+    let record = wal_record(ResourceManager::Transaction, 0x50, &[], &le_bytes(&[746, 0]));
+    let header = XLogMessageHeader { start_lsn: 0x1552C80, end_lsn: 0x1552CB0, send_time: XACT_TIME as u64 };
+    assert_eq!(XLogMessage::from_record(header, &record).unwrap().message_time(), Some(XACT_TIME));
+    assert!(decode(&record).to_string().contains("message_time: unknown"));
+}
+
+#[test]
+fn format_timestamps() {
+    assert_eq!(format_timestamp(0), "2000-01-01 00:00:00.000000 UTC");
+    assert_eq!(format_timestamp(-1), "1999-12-31 23:59:59.999999 UTC");
+    assert_eq!(format_timestamp(XACT_TIME), "2025-10-11 09:14:56.123456 UTC");
+}
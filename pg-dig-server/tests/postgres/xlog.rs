@@ -1,44 +1,65 @@
 use crate::postgres::test_data::TEST_BUFFER;
 use pg_dig_server::postgres::common::transaction_id::TransactionId;
 use pg_dig_server::postgres::common::{RelFileLocator};
-use pg_dig_server::postgres::common::rmgr::RmgrId;
+use pg_dig_server::postgres::common::rmgr::{ResourceManager, RmgrId};
 use pg_dig_server::postgres::xlog::block_header::XLogRecordBlockHeader;
 use pg_dig_server::postgres::xlog::block_image_header::XLogRecordBlockImageHeader;
+use pg_dig_server::postgres::xlog::decode_error::DecodeError;
 use pg_dig_server::postgres::xlog::record_header::XLogRecordHeader;
-use pg_dig_server::postgres::xlog_message::XLogMessageHeader;
+use pg_dig_server::postgres::xlog_message::{XLogMessage, XLogMessageHeader};
 
 #[test]
 fn xlog_header_from_buffer() {
-    unsafe {
-        let offset = 1;
-        let record = XLogMessageHeader::from_raw_ptr(TEST_BUFFER.as_ptr().add(offset));
+    let offset = 1;
+    let record = XLogMessageHeader::from_bytes(&TEST_BUFFER[offset..]).unwrap();
 
-        assert_eq!(
-            record.start_lsn, 9235850398613372928,
-            "data_start should be 9235850398613372928"
-        );
-    }
+    assert_eq!(
+        record.start_lsn, 22_359_168,
+        "data_start should be 22359168 (0/1552C80)"
+    );
 }
 
 #[test]
 fn xlog_record_from_buffer() {
-    unsafe {
-        let offset = size_of::<XLogMessageHeader>() + 1;
-        let record = XLogRecordHeader::from_raw_ptr(TEST_BUFFER.as_ptr().add(offset));
+    let offset = size_of::<XLogMessageHeader>() + 1;
+    let record = XLogRecordHeader::from_bytes(&TEST_BUFFER[offset..]).unwrap();
 
-        assert_eq!(record.xl_tot_len, 42, "total length should be 42");
-        assert_eq!(
-            record.xl_xid,
-            TransactionId(746),
-            "transaction id should be TransactionId(746)"
-        );
-        assert_eq!(
-            record.xl_prev, 22_359_112,
-            "previous xlog ptr should be 22359112"
-        );
-        assert_eq!(record.xl_rmid, RmgrId(8), "rmid should be RmgrId(8)");
-        assert_eq!(record.xl_crc, 101_056_512, "crc should be 101056512");
-    }
+    assert_eq!(record.xl_tot_len, 42, "total length should be 42");
+    assert_eq!(
+        record.xl_xid,
+        TransactionId(746),
+        "transaction id should be TransactionId(746)"
+    );
+    assert_eq!(
+        record.xl_prev, 22_359_112,
+        "previous xlog ptr should be 22359112"
+    );
+    assert_eq!(record.xl_rmid, RmgrId(8), "rmid should be RmgrId(8)");
+    assert_eq!(record.xl_crc, 545_719_814, "crc should be 545719814");
+}
+
+#[test]
+fn xlog_message_from_buffer() {
+    let message = XLogMessage::from_bytes(&TEST_BUFFER[1..]).unwrap();
+
+    assert_eq!(message.resource_manager, ResourceManager::Standby);
+    assert!(message.wal_block_headers.is_empty(), "standby lock record has no block references");
+}
+
+#[test]
+fn xlog_message_truncated_record() {
+    let length = 1 + size_of::<XLogMessageHeader>() + 16;
+    let result = XLogMessage::from_bytes(&TEST_BUFFER[1..length]);
+
+    assert_eq!(
+        result.err(),
+        Some(DecodeError::Truncated {
+            field: "xl_info",
+            offset: 16,
+            needed: 1,
+            available: 0,
+        })
+    );
 }
 
 // "block_ref": "blkref #0: rel 1663/1/1249 fork fsm blk 2 (FPW); hole: offset: 0, length: 0"
@@ -67,8 +88,25 @@ pub fn xlog_block_header_from_buffer() {
         block_number: 2,
     };
 
-    unsafe {
-        let record = XLogRecordBlockHeader::from_raw_ptr(buffer.as_ptr());
-        assert_eq!(record, expected);
-    }
+    let record = XLogRecordBlockHeader::from_bytes(&buffer).unwrap();
+    assert_eq!(record, expected);
+}
+
+#[test]
+pub fn xlog_block_header_truncated() {
+    let buffer = [
+        0x0, 0x11, 0x0, 0x0, 0x0, 0x20, 0x0, 0x0, 0x2, 0x7F, 0x6, 0x0, 0x0, 0x1, 0x0, 0x0, 0x0,
+        0xDF, 0x4, 0x0, 0x0, 0x2, 0x0,
+    ];
+
+    let result = XLogRecordBlockHeader::from_bytes(&buffer);
+    assert_eq!(
+        result.err(),
+        Some(DecodeError::Truncated {
+            field: "block_number",
+            offset: 21,
+            needed: 4,
+            available: 2,
+        })
+    );
 }
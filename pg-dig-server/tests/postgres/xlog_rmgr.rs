@@ -0,0 +1,122 @@
+use crate::postgres::record_builder::{decode, rmgr_record, test_buffer_record, wal_record};
+use pg_dig_server::postgres::common::rmgr::ResourceManager;
+use pg_dig_server::postgres::common::transaction_id::{FullTransactionId, TransactionId};
+use pg_dig_server::postgres::records::xlog::{CheckPoint, EndOfRecovery, ParameterChange, XLogRecord};
+
+fn checkpoint_bytes() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&0x1552C48u64.to_le_bytes());
+    bytes.extend_from_slice(&[0x01, 0, 0, 0, 0x01, 0, 0, 0]);
+    bytes.extend_from_slice(&[0x01, 0, 0, 0, 0, 0, 0, 0]);
+    bytes.extend_from_slice(&747u64.to_le_bytes());
+    [24576u32, 1, 0, 722, 1, 1, 1, 0].iter().for_each(|field| bytes.extend_from_slice(&field.to_le_bytes()));
+    bytes.extend_from_slice(&1_760_781_600i64.to_le_bytes());
+    [0u32, 0, 747, 0].iter().for_each(|field| bytes.extend_from_slice(&field.to_le_bytes()));
+    bytes
+}
+
+#[test]
+fn xlog_checkpoints() {
+    // rmgr: XLOG desc: CHECKPOINT_ONLINE redo 0/1552C48; tli 1; prev tli 1; fpw true; xid 0:747; oid 24576;
+    // multi 1; offset 0; oldest xid 722 in DB 1; oldest multi 1 in DB 1; oldest running xid 747; online
+    let online = decode(&wal_record(ResourceManager::XLOG, 0x10, &[], &checkpoint_bytes()));
+
+    assert_eq!(online.record_type(), "CHECKPOINT_ONLINE");
+    assert_eq!(rmgr_record!(online, XLog), &XLogRecord::CheckpointOnline(CheckPoint {
+        redo: 0x1552C48,
+        this_timeline: 1,
+        prev_timeline: 1,
+        full_page_writes: true,
+        next_xid: FullTransactionId(747),
+        next_oid: 24576,
+        next_multi: 1,
+        next_multi_offset: 0,
+        oldest_xid: TransactionId(722),
+        oldest_xid_db: 1,
+        oldest_multi: 1,
+        oldest_multi_db: 1,
+        time: 1_760_781_600,
+        oldest_commit_ts_xid: TransactionId(0),
+        newest_commit_ts_xid: TransactionId(0),
+        oldest_active_xid: TransactionId(747),
+    }));
+
+    let shutdown = decode(&wal_record(ResourceManager::XLOG, 0x00, &[], &checkpoint_bytes()));
+    assert_eq!(shutdown.record_type(), "CHECKPOINT_SHUTDOWN");
+    assert_eq!(rmgr_record!(shutdown, XLog).checkpoint().map(|checkpoint| checkpoint.redo), Some(0x1552C48));
+}
+
+#[test]
+fn xlog_next_oid() {
+    let message = test_buffer_record(0x1552CB0);
+
+    assert_eq!(message.record_type(), "NEXTOID");
+    assert_eq!(rmgr_record!(message, XLog), &XLogRecord::NextOid { next_oid: 0xA000 });
+    assert_eq!(rmgr_record!(message, XLog).checkpoint(), None);
+}
+
+#[test]
+fn xlog_switch_and_full_page_images() {
+    let switch = decode(&wal_record(ResourceManager::XLOG, 0x40, &[], &[]));
+    assert_eq!(switch.record_type(), "SWITCH");
+    assert_eq!(rmgr_record!(switch, XLog), &XLogRecord::Switch);
+
+    let fpi = decode(&wal_record(ResourceManager::XLOG, 0xB0, &[&[], &[], &[]], &[]));
+    assert_eq!(fpi.record_type(), "FPI");
+    assert_eq!(rmgr_record!(fpi, XLog), &XLogRecord::Fpi);
+    assert_eq!(fpi.get_block_numbers(), vec![0, 1, 2]);
+
+    let hint = decode(&wal_record(ResourceManager::XLOG, 0xA0, &[&[]], &[]));
+    assert_eq!(hint.record_type(), "FPI_FOR_HINT");
+    assert_eq!(rmgr_record!(hint, XLog), &XLogRecord::FpiForHint);
+}
+
+#[test]
+fn xlog_parameter_change() {
+    // max_connections=100 max_worker_processes=8 max_wal_senders=10 max_prepared_xacts=0
+    // max_locks_per_xact=64 wal_level=logical wal_log_hints=off track_commit_timestamp=on
+    let mut main_data = Vec::new();
+    [100i32, 8, 10, 0, 64, 2].iter().for_each(|field| main_data.extend_from_slice(&field.to_le_bytes()));
+    main_data.extend_from_slice(&[0x00, 0x01, 0x00, 0x00]);
+    let message = decode(&wal_record(ResourceManager::XLOG, 0x60, &[], &main_data));
+
+    assert_eq!(message.record_type(), "PARAMETER_CHANGE");
+    assert_eq!(rmgr_record!(message, XLog), &XLogRecord::ParameterChange(ParameterChange {
+        max_connections: 100,
+        max_worker_processes: 8,
+        max_wal_senders: 10,
+        max_prepared_xacts: 0,
+        max_locks_per_xact: 64,
+        wal_level: 2,
+        wal_log_hints: false,
+        track_commit_timestamp: true,
+    }));
+}
+
+#[test]
+fn xlog_restore_point_and_end_of_recovery() {
+    let mut main_data = 813_456_000_000_000i64.to_le_bytes().to_vec();
+    let mut name = b"before_migration".to_vec();
+    name.resize(64, 0);
+    main_data.extend(name);
+    let restore_point = decode(&wal_record(ResourceManager::XLOG, 0x70, &[], &main_data));
+
+    assert_eq!(restore_point.record_type(), "RESTORE_POINT");
+    assert_eq!(rmgr_record!(restore_point, XLog), &XLogRecord::RestorePoint {
+        time: 813_456_000_000_000,
+        name: "before_migration".to_string(),
+    });
+
+    let mut main_data = 813_456_000_000_000i64.to_le_bytes().to_vec();
+    [2u32, 1, 1].iter().for_each(|field| main_data.extend_from_slice(&field.to_le_bytes()));
+    main_data.extend_from_slice(&[0; 4]);
+    let end_of_recovery = decode(&wal_record(ResourceManager::XLOG, 0x90, &[], &main_data));
+
+    assert_eq!(end_of_recovery.record_type(), "END_OF_RECOVERY");
+    assert_eq!(rmgr_record!(end_of_recovery, XLog), &XLogRecord::EndOfRecovery(EndOfRecovery {
+        end_time: 813_456_000_000_000,
+        this_timeline: 2,
+        prev_timeline: 1,
+        wal_level: 1,
+    }));
+}